@@ -0,0 +1,163 @@
+use std::io::{prelude::*, BufReader};
+use socarel::{Tree, NodeContent};
+use crate::stack::*;
+use crate::parser::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::path::*;
+
+/// Check a node content in its context, with a message describing the problem.
+type NodeCheck = fn(&str, &NodeContext) -> Result<(), String>;
+
+/// Builds one tree from the node statements of a document.
+pub struct TreeBuilder<T: NodeContent> {
+    tree_id: String,
+    tree: Tree<T>,
+    stack: NodeStack,
+    prev_level: usize,
+    check: Option<NodeCheck>
+}
+
+impl<T: NodeContent> TreeBuilder<T> {
+    /// Create a builder that only creates the nodes with [`NodeContent::new()`].
+    pub fn new(tree_id: &str) -> Self {
+        Self {
+            tree_id: String::from(tree_id),
+            tree: Tree::new(),
+            stack: NodeStack::new(),
+            prev_level: 0,
+            check: None
+        }
+    }
+
+    pub fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError> {
+        if level > self.prev_level + 1 {
+            return Result::Err(ParseTreeError::new("Invalid node level", line));
+        }
+
+        // Root node
+        if level == 1 {
+            if self.stack.top().is_some() {
+                return Result::Err(ParseTreeError::new("Multiple root nodes in the same tree", line));
+            }
+            // Check root node in its context
+            if let Some(check) = self.check {
+                if let Err(reason) = check(content, &NodeContext::new(&self.tree_id, level, None, line)) {
+                    return Result::Err(ParseTreeError::with_content(&format!("Failed parsing root node: {}", reason), line, content));
+                }
+            }
+            // Create root node
+            if self.tree.set_root(content).is_none() {
+                return Result::Err(ParseTreeError::with_content("Failed parsing root node", line, content));
+            }
+            // Push root node reference to stack
+            self.stack.push_new(1, 0);
+        }
+        // Somebody's child node
+        else if let Some(parent_node_ref) = self.stack.pop_parent(level) {
+            // Check node in its context
+            if let Some(check) = self.check {
+                let parent = self.tree.get_node_content(parent_node_ref.tree_position).map(|p| p.get_val());
+                if let Err(reason) = check(content, &NodeContext::new(&self.tree_id, level, parent, line)) {
+                    let error = ParseTreeError::with_content(&format!("Failed parsing node: {}", reason), line, content);
+                    return Result::Err(self.with_node_path(error, parent_node_ref.tree_position, content));
+                }
+            }
+            if let Some(new_node) = self.tree.link_node(content, parent_node_ref.tree_position) {
+                // Push back parent node reference to stack
+                self.stack.push(parent_node_ref);
+                // Push new node reference to stack
+                self.stack.push_new(level, new_node);
+            }
+            else {
+                let error = ParseTreeError::with_content("Failed parsing node", line, content);
+                return Result::Err(self.with_node_path(error, parent_node_ref.tree_position, content));
+            }
+        }
+        else {
+            return Result::Err(ParseTreeError::new("Couldn't find a parent ref", line));
+        }
+
+        self.prev_level = level;
+        Result::Ok(())
+    }
+
+    pub fn tree_id(&self) -> &str {
+        &self.tree_id
+    }
+
+    pub fn finish(self) -> Tree<T> {
+        self.tree
+    }
+
+    /// Add the path of a node that couldn't be linked to its parent.
+    fn with_node_path(&self, error: ParseTreeError, parent: usize, content: &str) -> ParseTreeError {
+        match node_path(&self.tree, parent) {
+            Some(path) => error.with_path(&format!("{}{}{}", path, PATH_SEPARATOR, content)),
+            None => error
+        }
+    }
+}
+
+impl<T: TryNodeContent> TreeBuilder<T> {
+    /// Create a builder that checks every node with the dialect before creating it, so errors explain why a node was rejected.
+    pub fn checked(tree_id: &str) -> Self {
+        Self {
+            check: Some(T::validate_in),
+            ..Self::new(tree_id)
+        }
+    }
+}
+
+/// Receives the node statements of one tree.
+pub trait NodeSink {
+    fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError>;
+}
+
+impl<T: NodeContent> NodeSink for TreeBuilder<T> {
+    fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError> {
+        TreeBuilder::add_node(self, content, level, line)
+    }
+}
+
+/// Parse a TREF document, calling `new_tree` for each tree ID found and `end_tree` when all the nodes of a tree have been added.
+pub fn parse_document<S: NodeSink>(reader: BufReader<impl Read>, mut new_tree: impl FnMut(&str) -> S, mut end_tree: impl FnMut(S)) -> Result<(), ParseTreeError> {
+    let parser = TreeParser::new();
+    let mut current_tree: Option<S> = None;
+
+    for (i, l) in reader.lines().enumerate() {
+        if let Ok(line) = l {
+            match parser.parse_statement(&line) {
+                TreeStatement::Invalid => {
+                    return Result::Err(ParseTreeError::new("Invalid statement", i))
+                },
+                TreeStatement::TreeID(tree_id) => {
+                    if let Some(tree) = current_tree.take() {
+                        end_tree(tree);
+                    }
+                    current_tree = Some(new_tree(&tree_id));
+                },
+                TreeStatement::Node(content, level) => {
+                    if let Some(tree) = &mut current_tree {
+                        tree.add_node(&content, level, i)?;
+                    }
+                    else if level == 1 {
+                        return Result::Err(ParseTreeError::new("Found root node without previous tree ID", i));
+                    }
+                    else {
+                        return Result::Err(ParseTreeError::new("Invalid node level", i));
+                    }
+                },
+                _ => {}
+            }
+        }
+        else {
+            return Result::Err(ParseTreeError::new("Could not read line", i));
+        }
+    }
+
+    if let Some(tree) = current_tree.take() {
+        end_tree(tree);
+    }
+    Result::Ok(())
+}
@@ -0,0 +1,83 @@
+use socarel::{Forest, Tree, NodeContent};
+use crate::dialect::*;
+use crate::error::*;
+use crate::walk::*;
+
+/// Convert a forest into a forest of a different dialect.
+///
+/// The structure and the tree IDs are preserved. Each node is converted with the `conv` closure, and the resulting node is linked into the new tree using its [`gen_content()`][`NodeContent::gen_content()`].
+///
+/// ```
+/// use std::io::BufReader;
+/// use tref::{Model, NodeContent, TryNodeContent, convert_forest, dialects::IntegerNode};
+///
+/// let tref = "[my_tree]\n+ 1000\n+ + 800\n";
+/// let forest = <Model>::parse(BufReader::new(tref.as_bytes())).unwrap();
+/// let int_forest = convert_forest(&forest, |n| IntegerNode::try_new(n.get_val())).unwrap();
+/// assert_eq!(int_forest.get_tree("my_tree").unwrap().get_node_content(1).unwrap().get_num(), 800);
+/// ```
+///
+/// # Arguments
+///
+/// * `forest` - Forest to convert.
+/// * `conv` - Closure that converts one node, or returns a message describing the problem.
+///
+/// # Return
+///
+/// * A [`Result`] with the new [`Forest`] or a [`ConvertTreeError`] with the path of the first node that failed.
+///
+pub fn convert_forest<A, B, F>(forest: &Forest<A>, mut conv: F) -> Result<Forest<B>, ConvertTreeError>
+where A: NodeContent, B: TryNodeContent, F: FnMut(&A) -> Result<B, String> {
+    let mut new_forest = Forest::<B>::new();
+    for tree_id in sorted_tree_ids(forest) {
+        if let Some(tree) = forest.get_tree(tree_id) {
+            new_forest.add_tree(tree_id, convert_tree_in(Some(tree_id), tree, &mut conv)?);
+        }
+    }
+    Ok(new_forest)
+}
+
+/// Convert a tree into a tree of a different dialect.
+///
+/// Works like [`convert_forest()`], for one tree.
+///
+/// # Arguments
+///
+/// * `tree` - Tree to convert.
+/// * `conv` - Closure that converts one node, or returns a message describing the problem.
+///
+/// # Return
+///
+/// * A [`Result`] with the new [`Tree`] or a [`ConvertTreeError`] with the path of the first node that failed.
+///
+pub fn convert_tree<A, B, F>(tree: &Tree<A>, mut conv: F) -> Result<Tree<B>, ConvertTreeError>
+where A: NodeContent, B: TryNodeContent, F: FnMut(&A) -> Result<B, String> {
+    convert_tree_in(None, tree, &mut conv)
+}
+
+fn convert_tree_in<A, B, F>(tree_id: Option<&str>, tree: &Tree<A>, conv: &mut F) -> Result<Tree<B>, ConvertTreeError>
+where A: NodeContent, B: TryNodeContent, F: FnMut(&A) -> Result<B, String> {
+    let mut new_tree = Tree::<B>::new();
+    // Positions in the new tree, indexed by position in the old tree
+    let mut new_positions = vec![usize::MAX; tree.get_nodes_len()];
+    for position in pre_order(tree) {
+        let fail = |msg: &str| ConvertTreeError::new(msg, tree_id, content_path(tree, position));
+        let node = &tree.get_nodes_ref()[position];
+        let content = conv(node.get_content_ref()).map_err(|e| fail(&e))?.gen_content();
+        let new_position = match node.get_parent_position() {
+            Some(parent) => new_tree.link_node(&content, new_positions[parent]),
+            None => new_tree.set_root(&content)
+        };
+        match new_position {
+            Some(new_position) => new_positions[position] = new_position,
+            None => {
+                let reason = match B::try_new(&content) {
+                    Err(reason) => reason,
+                    Ok(_) => String::from("Could not link node")
+                };
+                return Err(fail(&format!("Converted content `{}` rejected: {}", content, reason)));
+            }
+        }
+    }
+    Ok(new_tree)
+}
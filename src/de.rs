@@ -0,0 +1,520 @@
+//! Deserialize Rust values from TREF documents.
+//!
+//! Requires the `serde` feature. Values are mapped like in the [`ser`][`crate::ser`] module.
+
+use std::io::{prelude::*, BufReader};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor, IntoDeserializer};
+use socarel::{Tree, NodeContent, RawNode};
+use crate::builder::*;
+use crate::ser::unquote;
+use crate::error::*;
+use crate::walk::*;
+
+/// Deserialize a value from a TREF document.
+///
+/// Node contents are unquoted into new strings, so the value can't borrow from the document.
+///
+/// # Arguments
+///
+/// * `s` - TREF document.
+///
+/// # Return
+///
+/// * A [`Result`] with the value or a [`SerdeTreeError`].
+///
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T, SerdeTreeError> {
+    from_reader(BufReader::new(s.as_bytes()))
+}
+
+/// Deserialize a value from a TREF document.
+///
+/// # Arguments
+///
+/// * `reader` - BufReader to read the document.
+///
+/// # Return
+///
+/// * A [`Result`] with the value or a [`SerdeTreeError`].
+///
+pub fn from_reader<T: DeserializeOwned>(reader: BufReader<impl Read>) -> Result<T, SerdeTreeError> {
+    let mut trees = vec!();
+    parse_document(reader, LineRecorder::new, |recorder| trees.push(recorder.into_branch()))?;
+    T::deserialize(TreesDeserializer { trees: &trees })
+}
+
+/// Tree builder that remembers the document line of each node.
+struct LineRecorder {
+    builder: TreeBuilder<RawNode>,
+    lines: Vec<usize>
+}
+
+impl LineRecorder {
+    fn new(tree_id: &str) -> Self {
+        Self { builder: TreeBuilder::new(tree_id), lines: vec!() }
+    }
+
+    fn into_branch(self) -> (String, Option<Branch>) {
+        let tree_id = String::from(self.builder.tree_id());
+        let tree = self.builder.finish();
+        if tree.get_nodes_len() > 0 {
+            (tree_id, Some(Branch::new(&tree, 0, &self.lines)))
+        }
+        else {
+            (tree_id, None)
+        }
+    }
+}
+
+impl NodeSink for LineRecorder {
+    fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError> {
+        self.builder.add_node(content, level, line)?;
+        // Nodes are created sequentially, so the position of the new node is the number of nodes added before
+        self.lines.push(line);
+        Ok(())
+    }
+}
+
+/// Node with its document line.
+struct Branch {
+    content: String,
+    line: usize,
+    children: Vec<Branch>
+}
+
+impl Branch {
+    fn new(tree: &Tree<RawNode>, position: usize, lines: &[usize]) -> Self {
+        Self {
+            content: tree.get_nodes_ref()[position].get_content_ref().get_val().to_string(),
+            line: lines[position],
+            children: children(tree, position).into_iter().map(|c| Branch::new(tree, c, lines)).collect()
+        }
+    }
+
+    fn is_marker(&self) -> bool {
+        self.content == "-"
+    }
+
+    fn text(&self) -> Result<String, SerdeTreeError> {
+        unquote(&self.content).map_err(|e| SerdeTreeError::new(&e, Some(self.line)))
+    }
+}
+
+fn with_line<T>(result: Result<T, SerdeTreeError>, line: usize) -> Result<T, SerdeTreeError> {
+    result.map_err(|e| e.or_line(line))
+}
+
+/// Top level deserializer, a map of tree IDs to the children of each root node.
+struct TreesDeserializer<'a> {
+    trees: &'a [(String, Option<Branch>)]
+}
+
+impl<'de, 'a> de::Deserializer<'de> for TreesDeserializer<'a> {
+    type Error = SerdeTreeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_map(TreesAccess { trees: self.trees.iter(), value: None })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct TreesAccess<'a> {
+    trees: std::slice::Iter<'a, (String, Option<Branch>)>,
+    value: Option<&'a Option<Branch>>
+}
+
+impl<'de, 'a> de::MapAccess<'de> for TreesAccess<'a> {
+    type Error = SerdeTreeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, SerdeTreeError> {
+        match self.trees.next() {
+            Some((tree_id, root)) => {
+                self.value = Some(root);
+                seed.deserialize(tree_id.as_str().into_deserializer()).map(Some)
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, SerdeTreeError> {
+        match self.value.take() {
+            Some(Some(root)) => seed.deserialize(ContainerDeserializer { nodes: &root.children, line: root.line }),
+            _ => seed.deserialize(ContainerDeserializer { nodes: &[], line: 0 })
+        }
+    }
+}
+
+/// Deserializer of a value represented by the children of a node.
+struct ContainerDeserializer<'a> {
+    nodes: &'a [Branch],
+    line: usize
+}
+
+impl<'a> ContainerDeserializer<'a> {
+    fn scalar(&self) -> Result<ScalarDeserializer, SerdeTreeError> {
+        match self.nodes {
+            [node] if node.children.is_empty() => Ok(ScalarDeserializer { text: node.text()?, line: node.line }),
+            [] => Err(SerdeTreeError::new("Expected a value, found nothing", Some(self.line))),
+            _ => Err(SerdeTreeError::new("Expected a single value node", Some(self.line)))
+        }
+    }
+}
+
+macro_rules! forward_to_scalar {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+                self.scalar()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ContainerDeserializer<'a> {
+    type Error = SerdeTreeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        match self.nodes {
+            [] => visitor.visit_unit(),
+            [node] if node.children.is_empty() => self.scalar()?.deserialize_any(visitor),
+            _ => self.deserialize_map(visitor)
+        }
+    }
+
+    forward_to_scalar! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string deserialize_identifier
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        if self.nodes.is_empty() {
+            visitor.visit_none()
+        }
+        else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        if self.nodes.is_empty() {
+            visitor.visit_unit()
+        }
+        else {
+            Err(SerdeTreeError::new("Expected no value", Some(self.line)))
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        with_line(visitor.visit_seq(SeqAccess { nodes: self.nodes.iter() }), self.line)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        with_line(visitor.visit_map(MapAccess { nodes: self.nodes.iter(), value: None }), self.line)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeTreeError> {
+        match self.nodes {
+            [node] => visitor.visit_enum(EnumAccess { node }),
+            _ => Err(SerdeTreeError::new("Expected a single enum variant node", Some(self.line)))
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_unit()
+    }
+}
+
+/// Deserializer of a sequence element, written directly or as children of a `-` node.
+struct ElementDeserializer<'a> {
+    node: &'a Branch
+}
+
+macro_rules! forward_element {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+                if self.node.is_marker() {
+                    ContainerDeserializer { nodes: &self.node.children, line: self.node.line }.$method(visitor)
+                }
+                else {
+                    ScalarDeserializer { text: self.node.text()?, line: self.node.line }.$method(visitor)
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ElementDeserializer<'a> {
+    type Error = SerdeTreeError;
+
+    forward_element! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_f32 deserialize_f64
+        deserialize_char deserialize_str deserialize_string deserialize_identifier
+        deserialize_bytes deserialize_byte_buf deserialize_option deserialize_unit
+        deserialize_seq deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeTreeError> {
+        if self.node.is_marker() {
+            ContainerDeserializer { nodes: &self.node.children, line: self.node.line }.deserialize_enum(name, variants, visitor)
+        }
+        else {
+            visitor.visit_enum(EnumAccess { node: self.node })
+        }
+    }
+}
+
+/// Deserializer of a node content.
+struct ScalarDeserializer {
+    text: String,
+    line: usize
+}
+
+impl ScalarDeserializer {
+    fn parse<T: std::str::FromStr>(&self, what: &str) -> Result<T, SerdeTreeError> {
+        self.text.trim().parse().map_err(|_| SerdeTreeError::new(&format!("Expected {}, found `{}`", what, self.text), Some(self.line)))
+    }
+
+    fn compound_error<T>(&self) -> Result<T, SerdeTreeError> {
+        Err(SerdeTreeError::new(&format!("Expected a compound value, found `{}`", self.text), Some(self.line)))
+    }
+}
+
+macro_rules! parse_scalar {
+    ($($method:ident $visit:ident $what:expr)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+                with_line(visitor.$visit(self.parse($what)?), self.line)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ScalarDeserializer {
+    type Error = SerdeTreeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        with_line(visitor.visit_string(self.text), self.line)
+    }
+
+    parse_scalar! {
+        deserialize_bool visit_bool "a boolean"
+        deserialize_i8 visit_i8 "an integer"
+        deserialize_i16 visit_i16 "an integer"
+        deserialize_i32 visit_i32 "an integer"
+        deserialize_i64 visit_i64 "an integer"
+        deserialize_u8 visit_u8 "an unsigned integer"
+        deserialize_u16 visit_u16 "an unsigned integer"
+        deserialize_u32 visit_u32 "an unsigned integer"
+        deserialize_u64 visit_u64 "an unsigned integer"
+        deserialize_f32 visit_f32 "a float"
+        deserialize_f64 visit_f64 "a float"
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        let mut chars = self.text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => with_line(visitor.visit_char(c), self.line),
+            _ => Err(SerdeTreeError::new(&format!("Expected a character, found `{}`", self.text), Some(self.line)))
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        with_line(visitor.visit_str(&self.text), self.line)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        with_line(visitor.visit_string(self.text), self.line)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeTreeError> {
+        let line = self.line;
+        with_line(visitor.visit_enum(self.text.into_deserializer()), line)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        Err(SerdeTreeError::new(&format!("Expected no value, found `{}`", self.text), Some(self.line)))
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value, SerdeTreeError> {
+        self.compound_error()
+    }
+}
+
+struct SeqAccess<'a> {
+    nodes: std::slice::Iter<'a, Branch>
+}
+
+impl<'de, 'a> de::SeqAccess<'de> for SeqAccess<'a> {
+    type Error = SerdeTreeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, SerdeTreeError> {
+        match self.nodes.next() {
+            Some(node) => seed.deserialize(ElementDeserializer { node }).map(Some),
+            None => Ok(None)
+        }
+    }
+}
+
+struct MapAccess<'a> {
+    nodes: std::slice::Iter<'a, Branch>,
+    value: Option<&'a Branch>
+}
+
+impl<'de, 'a> de::MapAccess<'de> for MapAccess<'a> {
+    type Error = SerdeTreeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, SerdeTreeError> {
+        match self.nodes.next() {
+            Some(node) => {
+                self.value = Some(node);
+                seed.deserialize(ScalarDeserializer { text: node.text()?, line: node.line }).map(Some)
+            },
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, SerdeTreeError> {
+        match self.value.take() {
+            Some(node) => seed.deserialize(ContainerDeserializer { nodes: &node.children, line: node.line }),
+            None => Err(SerdeTreeError::new("Map value without key", None))
+        }
+    }
+}
+
+struct EnumAccess<'a> {
+    node: &'a Branch
+}
+
+impl<'de, 'a> de::EnumAccess<'de> for EnumAccess<'a> {
+    type Error = SerdeTreeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), SerdeTreeError> {
+        let variant = seed.deserialize(ScalarDeserializer { text: self.node.text()?, line: self.node.line })?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> de::VariantAccess<'de> for EnumAccess<'a> {
+    type Error = SerdeTreeError;
+
+    fn unit_variant(self) -> Result<(), SerdeTreeError> {
+        if self.node.children.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(SerdeTreeError::new("Expected a unit variant", Some(self.node.line)))
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, SerdeTreeError> {
+        seed.deserialize(ContainerDeserializer { nodes: &self.node.children, line: self.node.line })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, SerdeTreeError> {
+        de::Deserializer::deserialize_seq(ContainerDeserializer { nodes: &self.node.children, line: self.node.line }, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, SerdeTreeError> {
+        de::Deserializer::deserialize_map(ContainerDeserializer { nodes: &self.node.children, line: self.node.line }, visitor)
+    }
+}
@@ -0,0 +1,53 @@
+//! Support functions for the [`TrefNode`][`crate::TrefNode`] derive macro.
+
+/// Escape the text of a field followed by a separator, prefixing with `\` the separator and backslashes.
+pub fn escape_field(text: &str, separator: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if !separator.is_empty() && rest.starts_with(separator) {
+            escaped.push('\\');
+            escaped.push_str(separator);
+            rest = &rest[separator.len()..];
+        }
+        else {
+            if c == '\\' {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    escaped
+}
+
+/// Split the text of a field from the first separator that is not escaped, removing the escapes.
+///
+/// Backslashes that don't escape anything are kept.
+///
+/// # Return
+///
+/// * An [`Option`] with the field text and the rest, starting with the separator, or None if there is no separator.
+///
+pub fn split_field<'a>(text: &'a str, separator: &str) -> Option<(String, &'a str)> {
+    let mut field = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' && !separator.is_empty() && rest[1..].starts_with(separator) {
+            field.push_str(separator);
+            rest = &rest[1 + separator.len()..];
+        }
+        else if rest.starts_with("\\\\") {
+            field.push('\\');
+            rest = &rest[2..];
+        }
+        else if rest.starts_with(separator) {
+            return Some((field, rest));
+        }
+        else {
+            field.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    None
+}
@@ -0,0 +1,118 @@
+use socarel::{NodeContent, RawNode};
+
+/// Fallible dialect.
+///
+/// Extends [`NodeContent`] with a constructor that explains why a node was rejected. [`Model::try_parse()`][`crate::Model::try_parse()`] uses it to build the [`ParseTreeError`][`crate::ParseTreeError`] when a node can't be parsed.
+///
+/// The default implementation calls [`NodeContent::new()`] and returns a generic message on failure, so an empty `impl` block is enough to use a dialect with [`Model::try_parse()`][`crate::Model::try_parse()`]. When overriding [`try_new()`][`TryNodeContent::try_new()`], implement [`NodeContent::new()`] in terms of it, not the other way around.
+pub trait TryNodeContent: NodeContent + Sized {
+    /// Try to parse a node content.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Node content.
+    ///
+    /// # Return
+    ///
+    /// * A [`Result`] with the parsed node or a message describing the problem.
+    ///
+    fn try_new(content: &str) -> Result<Self, String> {
+        Self::new(content).ok_or_else(|| String::from("Dialect rejected node content"))
+    }
+
+    /// Validate a node content knowing where it is located in the document.
+    ///
+    /// Called by [`Model::try_parse()`][`crate::Model::try_parse()`] for every node, before the node is added to the tree. Dialects that use a different grammar depending on the level or the parent can enforce it here.
+    ///
+    /// This only accepts or rejects the content: trees create their nodes from the text with [`NodeContent::new()`], without the context, so it must accept every content this method accepts.
+    ///
+    /// The default implementation ignores the context and calls [`try_new()`][`TryNodeContent::try_new()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Node content.
+    /// * `context` - Location of the node.
+    ///
+    /// # Return
+    ///
+    /// * A [`Result`] with a message describing the problem if the content is not valid.
+    ///
+    fn validate_in(content: &str, _context: &NodeContext) -> Result<(), String> {
+        Self::try_new(content).map(|_| ())
+    }
+}
+
+impl TryNodeContent for RawNode {}
+
+/// Location of a node being parsed.
+#[derive(Debug)]
+pub struct NodeContext<'a> {
+    tree_id: &'a str,
+    level: usize,
+    parent: Option<&'a str>,
+    line: usize
+}
+
+impl<'a> NodeContext<'a> {
+    /// Create new node context.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_id` - ID of the tree that contains the node.
+    /// * `level` - Node level, 1 for the root node.
+    /// * `parent` - Parent node content, None for the root node.
+    /// * `line` - Document line where the node is.
+    ///
+    /// # Return
+    ///
+    /// * A node context.
+    ///
+    pub fn new(tree_id: &'a str, level: usize, parent: Option<&'a str>, line: usize) -> Self {
+        Self {
+            tree_id,
+            level,
+            parent,
+            line
+        }
+    }
+
+    /// Get tree ID.
+    ///
+    /// # Return
+    ///
+    /// * Tree ID.
+    ///
+    pub fn tree_id(&self) -> &str {
+        self.tree_id
+    }
+
+    /// Get node level.
+    ///
+    /// # Return
+    ///
+    /// * Level, 1 for the root node.
+    ///
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Get parent node content.
+    ///
+    /// # Return
+    ///
+    /// * Parent content, None for the root node.
+    ///
+    pub fn parent(&self) -> Option<&str> {
+        self.parent
+    }
+
+    /// Get document line, starting from 0, like [`ParseTreeError::line()`][`crate::ParseTreeError::line()`].
+    ///
+    /// # Return
+    ///
+    /// * Line.
+    ///
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
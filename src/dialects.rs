@@ -0,0 +1,443 @@
+//! Ready to use dialects for common node formats.
+//!
+//! All of them keep the original node content, returned by [`get_val()`][`NodeContent::get_val()`], and generate a normalized content with [`gen_content()`][`NodeContent::gen_content()`], that is always accepted back by the dialect.
+//!
+//! ```
+//! use std::io::BufReader;
+//! use tref::{Model, dialects::WeightedEdgeNode};
+//!
+//! let tref =
+//! "[my_tree_dialect]\n\
+//! + 0:root_node\n\
+//! + + 27:child_1\n\
+//! + + + 99:child_1_1\n";
+//!
+//! let forest = Model::<WeightedEdgeNode>::parse(BufReader::new(tref.as_bytes())).unwrap();
+//! let tree = forest.get_tree("my_tree_dialect").unwrap();
+//! let child = tree.get_node_content(1).unwrap();
+//! assert_eq!(child.get_weight(), 27.0);
+//! assert_eq!(child.get_name(), "child_1");
+//! ```
+
+use std::fmt;
+use socarel::NodeContent;
+use crate::dialect::*;
+
+/// Integer node, like `1000`.
+#[derive(Debug)]
+pub struct IntegerNode {
+    num: i64,
+    content: String
+}
+
+impl IntegerNode {
+    /// Get node value.
+    pub fn get_num(&self) -> i64 {
+        self.num
+    }
+}
+
+impl NodeContent for IntegerNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        self.num.to_string()
+    }
+}
+
+impl TryNodeContent for IntegerNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        match content.trim().parse() {
+            Ok(num) => Ok(Self { num, content: String::from(content) }),
+            Err(e) => Err(format!("Not an integer ({})", e))
+        }
+    }
+}
+
+/// Float node, like `12.5`.
+#[derive(Debug)]
+pub struct FloatNode {
+    num: f64,
+    content: String
+}
+
+impl FloatNode {
+    /// Get node value.
+    pub fn get_num(&self) -> f64 {
+        self.num
+    }
+}
+
+impl NodeContent for FloatNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        self.num.to_string()
+    }
+}
+
+impl TryNodeContent for FloatNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        match content.trim().parse() {
+            Ok(num) => Ok(Self { num, content: String::from(content) }),
+            Err(e) => Err(format!("Not a float ({})", e))
+        }
+    }
+}
+
+/// Boolean node, `true` or `false`.
+#[derive(Debug)]
+pub struct BooleanNode {
+    val: bool,
+    content: String
+}
+
+impl BooleanNode {
+    /// Get node value.
+    pub fn get_bool(&self) -> bool {
+        self.val
+    }
+}
+
+impl NodeContent for BooleanNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        self.val.to_string()
+    }
+}
+
+impl TryNodeContent for BooleanNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        match content.trim().parse() {
+            Ok(val) => Ok(Self { val, content: String::from(content) }),
+            Err(_) => Err(String::from("Not a boolean, expected `true` or `false`"))
+        }
+    }
+}
+
+/// Key-value node, like `key=value`.
+///
+/// Key and value are trimmed, the key can't be empty and can't contain `=`.
+#[derive(Debug)]
+pub struct KeyValueNode {
+    key: String,
+    value: String,
+    content: String
+}
+
+impl KeyValueNode {
+    /// Get key.
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get value.
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl NodeContent for KeyValueNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        format!("{}={}", self.key, self.value)
+    }
+}
+
+impl TryNodeContent for KeyValueNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        let (key, value) = split_key_value(content, "=")?;
+        Ok(Self { key, value, content: String::from(content) })
+    }
+}
+
+/// Property node, like `key: value`.
+///
+/// Key and value are trimmed, the key can't be empty and can't contain `:`.
+#[derive(Debug)]
+pub struct PropertyNode {
+    key: String,
+    value: String,
+    content: String
+}
+
+impl PropertyNode {
+    /// Get key.
+    pub fn get_key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get value.
+    pub fn get_value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl NodeContent for PropertyNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        if self.value.is_empty() {
+            format!("{}:", self.key)
+        }
+        else {
+            format!("{}: {}", self.key, self.value)
+        }
+    }
+}
+
+impl TryNodeContent for PropertyNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        let (key, value) = split_key_value(content, ":")?;
+        Ok(Self { key, value, content: String::from(content) })
+    }
+}
+
+/// Weighted edge node, like `27:child_1`.
+///
+/// The weight is the weight of the edge that connects the node with its parent.
+#[derive(Debug)]
+pub struct WeightedEdgeNode {
+    weight: f64,
+    name: String,
+    content: String
+}
+
+impl WeightedEdgeNode {
+    /// Get edge weight.
+    pub fn get_weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Get node name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl NodeContent for WeightedEdgeNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        format!("{}:{}", self.weight, self.name)
+    }
+}
+
+impl TryNodeContent for WeightedEdgeNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        if let Some((weight, name)) = content.split_once(':') {
+            match weight.trim().parse() {
+                Ok(weight) => Ok(Self { weight, name: String::from(name), content: String::from(content) }),
+                Err(e) => Err(format!("Invalid edge weight ({})", e))
+            }
+        }
+        else {
+            Err(String::from("Missing edge weight, expected `weight:name`"))
+        }
+    }
+}
+
+/// Quoted string node, like `"some text"`.
+///
+/// Inside the quotes, `\"` and `\\` are used to escape quotes and backslashes.
+#[derive(Debug)]
+pub struct QuotedNode {
+    text: String,
+    content: String
+}
+
+impl QuotedNode {
+    /// Get unquoted and unescaped text.
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl NodeContent for QuotedNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        let mut quoted = String::from("\"");
+        for c in self.text.chars() {
+            if c == '"' || c == '\\' {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
+impl TryNodeContent for QuotedNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        let mut chars = content.trim_end().chars();
+        if chars.next() != Some('"') {
+            return Err(String::from("Missing opening quote"));
+        }
+        let mut text = String::new();
+        let mut closed = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(e) if e == '"' || e == '\\' => text.push(e),
+                    _ => return Err(String::from("Invalid escape sequence"))
+                },
+                '"' => {
+                    closed = true;
+                    break;
+                },
+                _ => text.push(c)
+            }
+        }
+        if !closed {
+            Err(String::from("Missing closing quote"))
+        }
+        else if chars.next().is_some() {
+            Err(String::from("Unexpected characters after closing quote"))
+        }
+        else {
+            Ok(Self { text, content: String::from(content) })
+        }
+    }
+}
+
+/// Kind of a file system entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Dir,
+    Link
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FileKind::File => write!(f, "file"),
+            FileKind::Dir => write!(f, "dir"),
+            FileKind::Link => write!(f, "link")
+        }
+    }
+}
+
+/// File system entry, like `file:1234:main.rs` or `dir:0:src`.
+///
+/// The content has the kind of entry (`file`, `dir` or `link`), the size in bytes and the name, separated by `:`. The name can contain `:`.
+#[derive(Debug)]
+pub struct FileNode {
+    kind: FileKind,
+    size: u64,
+    name: String,
+    content: String
+}
+
+impl FileNode {
+    /// Get entry kind.
+    pub fn get_kind(&self) -> FileKind {
+        self.kind
+    }
+
+    /// Get size in bytes.
+    pub fn get_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Get entry name.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl NodeContent for FileNode {
+    fn new(content: &str) -> Option<Self> {
+        Self::try_new(content).ok()
+    }
+
+    fn get_val(&self) -> &str {
+        &self.content
+    }
+
+    fn gen_content(&self) -> String {
+        format!("{}:{}:{}", self.kind, self.size, self.name)
+    }
+}
+
+impl TryNodeContent for FileNode {
+    fn try_new(content: &str) -> Result<Self, String> {
+        let mut parts = content.splitn(3, ':');
+        let kind = match parts.next().map(|k| k.trim()) {
+            Some("file") => FileKind::File,
+            Some("dir") => FileKind::Dir,
+            Some("link") => FileKind::Link,
+            _ => return Err(String::from("Invalid entry kind, expected `file`, `dir` or `link`"))
+        };
+        let size = match parts.next().map(|s| s.trim().parse()) {
+            Some(Ok(size)) => size,
+            Some(Err(e)) => return Err(format!("Invalid size ({})", e)),
+            None => return Err(String::from("Missing size, expected `kind:size:name`"))
+        };
+        match parts.next() {
+            Some(name) if !name.is_empty() => Ok(Self { kind, size, name: String::from(name), content: String::from(content) }),
+            _ => Err(String::from("Missing name, expected `kind:size:name`"))
+        }
+    }
+}
+
+fn split_key_value(content: &str, separator: &str) -> Result<(String, String), String> {
+    if let Some((key, value)) = content.split_once(separator) {
+        let key = key.trim();
+        if key.is_empty() {
+            Err(String::from("Empty key"))
+        }
+        else {
+            Ok((String::from(key), String::from(value.trim())))
+        }
+    }
+    else {
+        Err(format!("Missing `{}` separator, expected `key{}value`", separator, separator))
+    }
+}
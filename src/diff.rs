@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use socarel::{Forest, Tree, NodeContent};
+use crate::walk::*;
+use crate::path::*;
+
+/// Kind of change between two versions of a forest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// Tree only in the new forest.
+    AddTree,
+    /// Tree only in the old forest.
+    RemoveTree,
+    /// Node, with all its descendants, only in the new tree.
+    Insert,
+    /// Node, with all its descendants, only in the old tree.
+    Delete,
+    /// Node with a different parent in the new tree.
+    Move,
+    /// Node with a different content in the new tree.
+    Rename
+}
+
+/// Change between two versions of a forest, found by [`diff_forest()`] or [`diff_tree()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    kind: ChangeKind,
+    tree_id: String,
+    old: Option<(usize, String)>,
+    new: Option<(usize, String)>
+}
+
+impl Change {
+    /// Get kind of change.
+    pub fn kind(&self) -> ChangeKind {
+        self.kind
+    }
+
+    /// Get ID of the changed tree.
+    pub fn tree_id(&self) -> &str {
+        &self.tree_id
+    }
+
+    /// Get position of the node in the old tree, None for added trees and inserted nodes.
+    pub fn old_position(&self) -> Option<usize> {
+        self.old.as_ref().map(|(position, _)| *position)
+    }
+
+    /// Get content path of the node in the old tree, None for added trees and inserted nodes.
+    pub fn old_path(&self) -> Option<&str> {
+        self.old.as_ref().map(|(_, path)| path.as_str())
+    }
+
+    /// Get position of the node in the new tree, None for removed trees and deleted nodes.
+    pub fn new_position(&self) -> Option<usize> {
+        self.new.as_ref().map(|(position, _)| *position)
+    }
+
+    /// Get content path of the node in the new tree, None for removed trees and deleted nodes.
+    pub fn new_path(&self) -> Option<&str> {
+        self.new.as_ref().map(|(_, path)| path.as_str())
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let old = self.old_path().unwrap_or_default();
+        let new = self.new_path().unwrap_or_default();
+        match self.kind {
+            ChangeKind::AddTree => write!(f, "+ [{}]", self.tree_id),
+            ChangeKind::RemoveTree => write!(f, "- [{}]", self.tree_id),
+            ChangeKind::Insert => write!(f, "+ {}: {}", self.tree_id, new),
+            ChangeKind::Delete => write!(f, "- {}: {}", self.tree_id, old),
+            ChangeKind::Move => write!(f, "> {}: {} -> {}", self.tree_id, old, new),
+            ChangeKind::Rename => write!(f, "~ {}: {} -> {}", self.tree_id, old, new)
+        }
+    }
+}
+
+/// Find the structural changes between two versions of a forest.
+///
+/// Trees are compared by tree ID, and nodes are compared with [`diff_tree()`].
+///
+/// # Arguments
+///
+/// * `old` - Old forest.
+/// * `new` - New forest.
+///
+/// # Return
+///
+/// * Changes, sorted by tree ID.
+///
+pub fn diff_forest<T: NodeContent>(old: &Forest<T>, new: &Forest<T>) -> Vec<Change> {
+    let mut tree_ids: Vec<&String> = sorted_tree_ids(old);
+    tree_ids.extend(sorted_tree_ids(new));
+    tree_ids.sort();
+    tree_ids.dedup();
+    let mut changes = vec!();
+    for tree_id in tree_ids {
+        match (old.get_tree(tree_id), new.get_tree(tree_id)) {
+            (Some(old_tree), Some(new_tree)) => changes.extend(diff_tree(tree_id, old_tree, new_tree)),
+            (Some(_), None) => changes.push(Change { kind: ChangeKind::RemoveTree, tree_id: String::from(tree_id), old: None, new: None }),
+            _ => changes.push(Change { kind: ChangeKind::AddTree, tree_id: String::from(tree_id), old: None, new: None })
+        }
+    }
+    changes
+}
+
+/// Find the structural changes between two versions of a tree.
+///
+/// Nodes are matched by content, ignoring the order of siblings, so reordered siblings are not a change. Then:
+///
+/// * A node with a different content, whose parent is matched, is renamed if it has children in common with the old node, a similar content, or if it is a leaf and the same number of leaves were removed and added under its parent.
+/// * A subtree that was removed from one place and added in another is moved.
+/// * Other nodes are deleted or inserted, reported only once for the whole subtree.
+///
+/// ```
+/// use tref::{tref, Tree, RawNode, diff_tree};
+///
+/// let old: Tree<RawNode> = tref!("base" => { "port=80", "branch_1" => { "leaf" }, "branch_2" });
+/// let new: Tree<RawNode> = tref!("base" => { "branch_2" => { "leaf" }, "port=8080", "branch_1" });
+/// let changes: Vec<String> = diff_tree("config", &old, &new).iter().map(|c| c.to_string()).collect();
+/// assert_eq!(changes, vec!(
+///     "> config: base > branch_1 > leaf -> base > branch_2 > leaf",
+///     "~ config: base > port=80 -> base > port=8080"
+/// ));
+/// ```
+///
+/// # Arguments
+///
+/// * `tree_id` - Tree ID, for the changes.
+/// * `old` - Old tree.
+/// * `new` - New tree.
+///
+/// # Return
+///
+/// * Changes: deleted nodes in pre-order of the old tree, then the other changes in pre-order of the new tree.
+///
+pub fn diff_tree<T: NodeContent>(tree_id: &str, old: &Tree<T>, new: &Tree<T>) -> Vec<Change> {
+    let matches = match_nodes(old, new);
+    let mut new_to_old: Vec<Option<usize>> = vec![None; new.get_nodes_len()];
+    for (old_position, new_position) in matches.iter().enumerate() {
+        if let Some(new_position) = new_position {
+            new_to_old[*new_position] = Some(old_position);
+        }
+    }
+    let old_parents = parents(old);
+    let new_parents = parents(new);
+    // Nodes come from the pre-order, so they are linked
+    let node = |tree: &Tree<T>, position: usize| Some((position, content_path(tree, position).join(PATH_SEPARATOR)));
+    let change = |kind, old_position: Option<usize>, new_position: Option<usize>| Change {
+        kind,
+        tree_id: String::from(tree_id),
+        old: old_position.and_then(|p| node(old, p)),
+        new: new_position.and_then(|p| node(new, p))
+    };
+    let mut changes = vec!();
+    for position in pre_order(old) {
+        let parent_deleted = old_parents[position].map(|parent| matches[parent].is_none()).unwrap_or(false);
+        if matches[position].is_none() && !parent_deleted {
+            changes.push(change(ChangeKind::Delete, Some(position), None));
+        }
+    }
+    for position in pre_order(new) {
+        match new_to_old[position] {
+            Some(old_position) => {
+                if content(old, old_position) != content(new, position) {
+                    changes.push(change(ChangeKind::Rename, Some(old_position), Some(position)));
+                }
+                let old_parent = old_parents[old_position];
+                let new_parent = new_parents[position];
+                if old_parent.map(|parent| matches[parent]) != new_parent.map(Some) {
+                    changes.push(change(ChangeKind::Move, Some(old_position), Some(position)));
+                }
+            },
+            None => {
+                let parent_inserted = new_parents[position].map(|parent| new_to_old[parent].is_none()).unwrap_or(false);
+                if !parent_inserted {
+                    changes.push(change(ChangeKind::Insert, None, Some(position)));
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// Match the nodes of two versions of a tree, as described in [`diff_tree()`].
+///
+/// Returns the position in the new tree of each node of the old tree, or None if it was deleted.
+pub(crate) fn match_nodes<T: NodeContent>(old: &Tree<T>, new: &Tree<T>) -> Vec<Option<usize>> {
+    let old_signatures = signatures(old);
+    let new_signatures = signatures(new);
+    let mut matcher = Matcher {
+        old,
+        new,
+        unmatched_old: count_signatures(&old_signatures),
+        unmatched_new: count_signatures(&new_signatures),
+        old_signatures,
+        new_signatures,
+        old_to_new: vec![None; old.get_nodes_len()],
+        new_to_old: vec![None; new.get_nodes_len()]
+    };
+    if old.get_nodes_len() == 0 || new.get_nodes_len() == 0 {
+        return matcher.old_to_new;
+    }
+    // The roots are always the same node
+    matcher.pair(0, 0);
+    matcher.match_children(0, 0);
+    for position in pre_order(new) {
+        if let Some(old_position) = matcher.new_to_old[position] {
+            matcher.match_renamed(old_position, position);
+        }
+    }
+    matcher.match_moved();
+    matcher.old_to_new
+}
+
+/// Most pairs of unmatched siblings compared by similarity to find renamed nodes.
+const MAX_SIMILARITY_PAIRS: usize = 10_000;
+
+struct Matcher<'a, T: NodeContent> {
+    old: &'a Tree<T>,
+    new: &'a Tree<T>,
+    old_signatures: Vec<u64>,
+    new_signatures: Vec<u64>,
+    /// Number of unmatched nodes with each signature
+    unmatched_old: HashMap<u64, usize>,
+    unmatched_new: HashMap<u64, usize>,
+    old_to_new: Vec<Option<usize>>,
+    new_to_old: Vec<Option<usize>>
+}
+
+impl<'a, T: NodeContent> Matcher<'a, T> {
+    fn pair(&mut self, old_position: usize, new_position: usize) {
+        self.old_to_new[old_position] = Some(new_position);
+        self.new_to_old[new_position] = Some(old_position);
+        for (unmatched, signature) in [(&mut self.unmatched_old, self.old_signatures[old_position]), (&mut self.unmatched_new, self.new_signatures[new_position])] {
+            if let Some(count) = unmatched.get_mut(&signature) {
+                *count -= 1;
+            }
+        }
+    }
+
+    /// Match children with the same content, preferring the ones with the same subtree, and then their descendants.
+    fn match_children(&mut self, old_position: usize, new_position: usize) {
+        // Matched nodes whose children are not matched yet
+        let mut pending = vec!((old_position, new_position));
+        while let Some((old_position, new_position)) = pending.pop() {
+            let old_children = children(self.old, old_position);
+            let new_children = children(self.new, new_position);
+            // Unmatched old children by subtree and by content, the first one at the end
+            let mut by_signature: HashMap<u64, Vec<usize>> = HashMap::new();
+            let mut by_content: HashMap<&str, Vec<usize>> = HashMap::new();
+            for old_child in old_children.into_iter().rev().filter(|c| self.old_to_new[*c].is_none()) {
+                by_signature.entry(self.old_signatures[old_child]).or_default().push(old_child);
+                by_content.entry(content(self.old, old_child)).or_default().push(old_child);
+            }
+            for same_subtree in [true, false].iter() {
+                for new_child in new_children.iter().copied() {
+                    if self.new_to_old[new_child].is_some() {
+                        continue;
+                    }
+                    let candidates = if *same_subtree {
+                        by_signature.get_mut(&self.new_signatures[new_child])
+                    }
+                    else {
+                        by_content.get_mut(content(self.new, new_child))
+                    };
+                    let found = match candidates {
+                        Some(candidates) => {
+                            // Skip the ones matched in the other pass
+                            while candidates.last().map(|c| self.old_to_new[*c].is_some()).unwrap_or(false) {
+                                candidates.pop();
+                            }
+                            candidates.pop()
+                        },
+                        None => None
+                    };
+                    if let Some(old_child) = found {
+                        self.pair(old_child, new_child);
+                        pending.push((old_child, new_child));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Match the remaining children of two matched nodes as renamed nodes.
+    ///
+    /// Children are compared by similarity when there are at most [`MAX_SIMILARITY_PAIRS`] pairs of them. Otherwise only the children with the same child contents are matched, so diffing stays linear.
+    fn match_renamed(&mut self, old_position: usize, new_position: usize) {
+        let mut old_children: Vec<usize> = children(self.old, old_position).into_iter().filter(|c| self.old_to_new[*c].is_none()).collect();
+        let mut new_children: Vec<usize> = children(self.new, new_position).into_iter().filter(|c| self.new_to_old[*c].is_none()).collect();
+        if old_children.len() * new_children.len() <= MAX_SIMILARITY_PAIRS {
+            self.match_similar(&mut old_children, &mut new_children);
+        }
+        else {
+            self.match_same_children(&mut old_children, &mut new_children);
+        }
+        // Leaves in the same place, unless they look moved from somewhere else
+        let is_unmatched = |unmatched: &HashMap<u64, usize>, signature: u64| unmatched.get(&signature).map(|count| *count > 0).unwrap_or(false);
+        old_children.retain(|c| children(self.old, *c).is_empty() && !is_unmatched(&self.unmatched_new, self.old_signatures[*c]));
+        new_children.retain(|c| children(self.new, *c).is_empty() && !is_unmatched(&self.unmatched_old, self.new_signatures[*c]));
+        if old_children.len() == new_children.len() {
+            for (old_child, new_child) in old_children.into_iter().zip(new_children) {
+                self.pair(old_child, new_child);
+                self.match_children(old_child, new_child);
+            }
+        }
+    }
+
+    /// Match children with children in common, and then children with similar contents, removing them from the lists.
+    fn match_similar(&mut self, old_children: &mut Vec<usize>, new_children: &mut Vec<usize>) {
+        // Nodes with children in common
+        let old_contents: HashMap<usize, Vec<&str>> = old_children.iter().map(|c| (*c, child_contents(self.old, *c))).collect();
+        for new_child in new_children.clone() {
+            let new_contents = child_contents(self.new, new_child);
+            let best = old_children.iter().copied()
+                .map(|old_child| (old_child, common_contents(&old_contents[&old_child], &new_contents)))
+                .filter(|(old_child, common)| *common > 0 && common * 2 >= old_contents[old_child].len().max(new_contents.len()))
+                .max_by_key(|(_, common)| *common);
+            if let Some((old_child, _)) = best {
+                self.pair(old_child, new_child);
+                self.match_children(old_child, new_child);
+                old_children.retain(|c| *c != old_child);
+                new_children.retain(|c| *c != new_child);
+            }
+        }
+        // Nodes with similar contents, sharing at least half of the longest one as a prefix
+        for new_child in new_children.clone() {
+            let new_content = content(self.new, new_child);
+            let best = old_children.iter().copied()
+                .map(|old_child| (old_child, common_prefix(content(self.old, old_child), new_content)))
+                .filter(|(old_child, common)| *common > 0 && common * 2 >= content(self.old, *old_child).chars().count().max(new_content.chars().count()))
+                .max_by_key(|(_, common)| *common);
+            if let Some((old_child, _)) = best {
+                self.pair(old_child, new_child);
+                self.match_children(old_child, new_child);
+                old_children.retain(|c| *c != old_child);
+                new_children.retain(|c| *c != new_child);
+            }
+        }
+    }
+
+    /// Match children with the same child contents, in any order, removing them from the lists.
+    fn match_same_children(&mut self, old_children: &mut Vec<usize>, new_children: &mut Vec<usize>) {
+        let children_hash = |tree: &Tree<T>, position: usize| {
+            let mut contents = child_contents(tree, position);
+            contents.sort_unstable();
+            let mut hasher = DefaultHasher::new();
+            contents.hash(&mut hasher);
+            hasher.finish()
+        };
+        let (old, new) = (self.old, self.new);
+        // Old children with children, the first one at the end
+        let mut by_children: HashMap<u64, Vec<usize>> = HashMap::new();
+        for old_child in old_children.iter().rev().copied().filter(|c| !children(old, *c).is_empty()) {
+            by_children.entry(children_hash(old, old_child)).or_default().push(old_child);
+        }
+        for new_child in new_children.iter().copied().filter(|c| !children(new, *c).is_empty()) {
+            if let Some(old_child) = by_children.get_mut(&children_hash(new, new_child)).and_then(|candidates| candidates.pop()) {
+                self.pair(old_child, new_child);
+                self.match_children(old_child, new_child);
+            }
+        }
+        old_children.retain(|c| self.old_to_new[*c].is_none());
+        new_children.retain(|c| self.new_to_old[*c].is_none());
+    }
+
+    /// Match the remaining subtrees that are the same in both trees.
+    fn match_moved(&mut self) {
+        let mut unmatched: HashMap<u64, Vec<usize>> = HashMap::new();
+        for position in pre_order(self.old).into_iter().rev() {
+            if self.old_to_new[position].is_none() {
+                unmatched.entry(self.old_signatures[position]).or_default().push(position);
+            }
+        }
+        for position in pre_order(self.new) {
+            if self.new_to_old[position].is_some() {
+                continue;
+            }
+            // First unmatched old node with the same subtree, skipping the ones matched as descendants of another move
+            let found = match unmatched.get_mut(&self.new_signatures[position]) {
+                Some(candidates) => {
+                    while candidates.last().map(|c| self.old_to_new[*c].is_some()).unwrap_or(false) {
+                        candidates.pop();
+                    }
+                    candidates.pop()
+                },
+                None => None
+            };
+            if let Some(old_position) = found {
+                self.pair(old_position, position);
+                self.match_children(old_position, position);
+            }
+        }
+    }
+}
+
+fn content<T: NodeContent>(tree: &Tree<T>, position: usize) -> &str {
+    tree.get_nodes_ref()[position].get_content_ref().get_val()
+}
+
+fn child_contents<T: NodeContent>(tree: &Tree<T>, position: usize) -> Vec<&str> {
+    children(tree, position).into_iter().map(|c| content(tree, c)).collect()
+}
+
+/// Count the contents in both lists, each one used only once.
+fn common_contents(old: &[&str], new: &[&str]) -> usize {
+    let mut old = old.to_vec();
+    new.iter().filter(|content| match old.iter().position(|c| c == *content) {
+        Some(i) => {
+            old.swap_remove(i);
+            true
+        },
+        None => false
+    }).count()
+}
+
+/// Count the characters of the common prefix.
+fn common_prefix(old: &str, new: &str) -> usize {
+    old.chars().zip(new.chars()).take_while(|(o, n)| o == n).count()
+}
+
+/// Get a signature of each subtree, a hash equal for subtrees with the same contents in any sibling order.
+fn signatures<T: NodeContent>(tree: &Tree<T>) -> Vec<u64> {
+    let mut signatures = vec![0; tree.get_nodes_len()];
+    for position in pre_order(tree).into_iter().rev() {
+        let mut child_signatures: Vec<u64> = children(tree, position).into_iter().map(|c| signatures[c]).collect();
+        child_signatures.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        content(tree, position).hash(&mut hasher);
+        child_signatures.hash(&mut hasher);
+        signatures[position] = hasher.finish();
+    }
+    signatures
+}
+
+/// Count the nodes with each signature.
+fn count_signatures(signatures: &[u64]) -> HashMap<u64, usize> {
+    let mut counts = HashMap::new();
+    for signature in signatures {
+        *counts.entry(*signature).or_default() += 1;
+    }
+    counts
+}
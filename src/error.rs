@@ -1,11 +1,14 @@
 use std::error::Error;
 use std::fmt;
+use crate::path::PATH_SEPARATOR;
 
 #[derive(Debug)]
 /// Parse TREF document error.
 pub struct ParseTreeError {
     message: String,
-    line: usize
+    line: usize,
+    content: Option<String>,
+    path: Option<String>
 }
 
 impl ParseTreeError {
@@ -23,10 +26,48 @@ impl ParseTreeError {
     pub fn new(msg: &str, line: usize) -> Self {
         ParseTreeError {
             message: String::from(msg),
-            line
+            line,
+            content: None,
+            path: None
         }
     }
 
+    /// Create new parse tree error for a node.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `line` - Document line where the error hapened.
+    /// * `content` - Content of the node that caused the problem.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn with_content(msg: &str, line: usize, content: &str) -> Self {
+        ParseTreeError {
+            message: String::from(msg),
+            line,
+            content: Some(String::from(content)),
+            path: None
+        }
+    }
+
+    /// Set the content path of the node that caused the error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `path` - Contents of the nodes from the root to the node, separated by [`PATH_SEPARATOR`].
+    /// 
+    /// # Return
+    /// 
+    /// * The error model with the path.
+    ///
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(String::from(path));
+        self
+    }
+
     /// Get error line.
     /// 
     /// # Return
@@ -36,11 +77,39 @@ impl ParseTreeError {
     pub fn line(&self) -> usize {
         self.line
     }
+
+    /// Get content of the node that caused the error.
+    /// 
+    /// # Return
+    /// 
+    /// * Node content, if known.
+    ///
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    /// Get content path of the node that caused the error, if known.
+    /// 
+    /// # Return
+    /// 
+    /// * Node path, if known.
+    ///
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
 }
 
 impl fmt::Display for ParseTreeError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "`{}` at line {}", self.message, self.line + 1)
+        if let (Some(content), Some(path)) = (&self.content, &self.path) {
+            write!(f, "`{}` at line {} with node `{}` at path `{}`", self.message, self.line + 1, content, path)
+        }
+        else if let Some(content) = &self.content {
+            write!(f, "`{}` at line {} with node `{}`", self.message, self.line + 1, content)
+        }
+        else {
+            write!(f, "`{}` at line {}", self.message, self.line + 1)
+        }
     }
 }
 
@@ -115,4 +184,372 @@ impl Error for SerializeTreeError {
     fn description(&self) -> &str {
         &self.message
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug)]
+/// Dialect conversion error.
+pub struct ConvertTreeError {
+    message: String,
+    tree_id: Option<String>,
+    path: Vec<String>
+}
+
+impl ConvertTreeError {
+    /// Create new conversion error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `tree_id` - ID of the tree that contains the node, None if the tree is not part of a forest.
+    /// * `path` - Contents of the nodes from the root to the node that caused the problem.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn new(msg: &str, tree_id: Option<&str>, path: Vec<String>) -> Self {
+        ConvertTreeError {
+            message: String::from(msg),
+            tree_id: tree_id.map(String::from),
+            path
+        }
+    }
+
+    /// Get ID of the tree that contains the node.
+    /// 
+    /// # Return
+    /// 
+    /// * An [`Option`] with the tree ID, or None if the tree is not part of a forest.
+    ///
+    pub fn tree_id(&self) -> Option<&str> {
+        self.tree_id.as_deref()
+    }
+
+    /// Get path of the node that caused the error.
+    /// 
+    /// # Return
+    /// 
+    /// * Contents of the nodes from the root to the node.
+    ///
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+}
+
+impl fmt::Display for ConvertTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.tree_id {
+            Some(tree_id) => write!(f, "`{}` in tree `{}` at node `{}`", self.message, tree_id, self.path.join(PATH_SEPARATOR)),
+            None => write!(f, "`{}` at node `{}`", self.message, self.path.join(PATH_SEPARATOR))
+        }
+    }
+}
+
+impl Error for ConvertTreeError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+#[derive(Debug)]
+/// Error importing a tree from another format.
+pub struct ImportTreeError {
+    message: String,
+    line: Option<usize>,
+    content: Option<String>
+}
+
+impl ImportTreeError {
+    /// Create new import error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `line` - Line of the imported text where the error hapened, if known.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn new(msg: &str, line: Option<usize>) -> Self {
+        ImportTreeError {
+            message: String::from(msg),
+            line,
+            content: None
+        }
+    }
+
+    /// Create new import error for a node.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `line` - Line of the imported text where the error hapened, if known.
+    /// * `content` - Content of the node that caused the problem.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn with_content(msg: &str, line: Option<usize>, content: &str) -> Self {
+        ImportTreeError {
+            message: String::from(msg),
+            line,
+            content: Some(String::from(content))
+        }
+    }
+
+    /// Get error line.
+    /// 
+    /// # Return
+    /// 
+    /// * Line, if known.
+    ///
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Get content of the node that caused the error.
+    /// 
+    /// # Return
+    /// 
+    /// * Node content, if known.
+    ///
+    pub fn content(&self) -> Option<&str> {
+        self.content.as_deref()
+    }
+
+    /// Convert an error of the tree builder, that doesn't know the real line.
+    pub(crate) fn from_parse_error(e: ParseTreeError, line: Option<usize>) -> Self {
+        ImportTreeError {
+            message: e.message,
+            line,
+            content: e.content
+        }
+    }
+}
+
+impl fmt::Display for ImportTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}`", self.message)?;
+        if let Some(line) = self.line {
+            write!(f, " at line {}", line + 1)?;
+        }
+        if let Some(content) = &self.content {
+            write!(f, " with node `{}`", content)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ImportTreeError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+#[derive(Debug)]
+/// Node query error.
+pub struct QueryError {
+    message: String,
+    position: usize
+}
+
+impl QueryError {
+    /// Create new query error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `position` - Position of the query, in characters, where the error hapened.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn new(msg: &str, position: usize) -> Self {
+        QueryError {
+            message: String::from(msg),
+            position
+        }
+    }
+
+    /// Get error position.
+    /// 
+    /// # Return
+    /// 
+    /// * Position in the query, in characters.
+    ///
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` at position {}", self.message, self.position)
+    }
+}
+
+impl Error for QueryError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+#[derive(Debug)]
+/// Error finding a node by its content path.
+pub struct PathError {
+    message: String,
+    path: String,
+    matches: usize
+}
+
+impl PathError {
+    /// Create new path error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `path` - Content path that was searched.
+    /// * `matches` - Number of nodes that matched the path.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn new(msg: &str, path: &str, matches: usize) -> Self {
+        PathError {
+            message: String::from(msg),
+            path: String::from(path),
+            matches
+        }
+    }
+
+    /// Get content path that was searched.
+    /// 
+    /// # Return
+    /// 
+    /// * Content path.
+    ///
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get number of nodes that matched the path.
+    /// 
+    /// # Return
+    /// 
+    /// * Number of matches, 0 if the node was not found.
+    ///
+    pub fn matches(&self) -> usize {
+        self.matches
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.matches > 1 {
+            write!(f, "`{}` for path `{}`, {} nodes match", self.message, self.path, self.matches)
+        }
+        else {
+            write!(f, "`{}` for path `{}`", self.message, self.path)
+        }
+    }
+}
+
+impl Error for PathError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+/// Error serializing or deserializing Rust values, requires the `serde` feature.
+pub struct SerdeTreeError {
+    message: String,
+    line: Option<usize>
+}
+
+#[cfg(feature = "serde")]
+impl SerdeTreeError {
+    /// Create new serde error.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `msg` - Error message.
+    /// * `line` - Document line where the error hapened, if known.
+    /// 
+    /// # Return
+    /// 
+    /// * An error model.
+    ///
+    pub fn new(msg: &str, line: Option<usize>) -> Self {
+        SerdeTreeError {
+            message: String::from(msg),
+            line
+        }
+    }
+
+    /// Get error line.
+    /// 
+    /// # Return
+    /// 
+    /// * Line, if known.
+    ///
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Set the error line, if it was unknown.
+    pub(crate) fn or_line(mut self, line: usize) -> Self {
+        if self.line.is_none() {
+            self.line = Some(line);
+        }
+        self
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ParseTreeError> for SerdeTreeError {
+    fn from(e: ParseTreeError) -> Self {
+        match e.content {
+            Some(content) => SerdeTreeError::new(&format!("{} with node `{}`", e.message, content), Some(e.line)),
+            None => SerdeTreeError::new(&e.message, Some(e.line))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for SerdeTreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "`{}` at line {}", self.message, line + 1)
+        }
+        else {
+            write!(f, "`{}`", self.message)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error for SerdeTreeError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::ser::Error for SerdeTreeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeTreeError::new(&msg.to_string(), None)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::de::Error for SerdeTreeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        SerdeTreeError::new(&msg.to_string(), None)
+    }
+}
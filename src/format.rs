@@ -0,0 +1,170 @@
+use std::io::BufReader;
+use socarel::{Forest, NodeContent, RawNode};
+use crate::model::*;
+use crate::parser::*;
+use crate::error::*;
+use crate::walk::*;
+
+/// Format a TREF document with the canonical layout.
+///
+/// The canonical layout has:
+///
+/// * `\n` line endings, and a newline at the end of the document.
+/// * No trailing whitespaces in comments.
+/// * One empty line between trees, and no empty lines inside them.
+///
+/// Tree IDs and nodes are kept as they are, including whitespaces at the end of node contents, that are part of the content.
+///
+/// Comments are preserved. Comments right before a tree ID, without empty lines in between, stay attached to it. Other comments stay at the end of the previous tree, or at the beginning of the document if there is no previous tree.
+///
+/// ```
+/// use tref::format_document;
+///
+/// let tref = "# Doc  \r\n\r\n[a]\r\n\r\n+ root\r\n# Tree b\n[b]\n+ root\n\n\n";
+/// assert_eq!(format_document(tref).unwrap(), "# Doc\n\n[a]\n+ root\n\n# Tree b\n[b]\n+ root\n");
+/// ```
+///
+/// # Arguments
+///
+/// * `tref` - TREF document.
+///
+/// # Return
+///
+/// * A [`Result`] with the formatted document, or a [`ParseTreeError`] if the document is not valid.
+///
+pub fn format_document(tref: &str) -> Result<String, ParseTreeError> {
+    Model::<RawNode>::parse(BufReader::new(tref.as_bytes()))?;
+
+    let parser = TreeParser::new();
+    let mut output: Vec<&str> = vec!();
+    // Comments not placed yet, and if there were empty lines after them
+    let mut comments: Vec<&str> = vec!();
+    let mut blank = false;
+    let mut in_tree = false;
+    for line in tref.lines() {
+        match parser.parse_statement(line) {
+            TreeStatement::Empty => blank = true,
+            TreeStatement::Comment => {
+                if blank {
+                    place_detached(&mut comments, in_tree, &mut output);
+                }
+                comments.push(line.trim_end());
+                blank = false;
+            },
+            TreeStatement::TreeID(_) => {
+                if blank {
+                    place_detached(&mut comments, in_tree, &mut output);
+                }
+                if !output.is_empty() {
+                    output.push("");
+                }
+                output.append(&mut comments);
+                output.push(line);
+                blank = false;
+                in_tree = true;
+            },
+            TreeStatement::Node(_, _) | TreeStatement::Invalid => {
+                output.append(&mut comments);
+                output.push(line);
+                blank = false;
+            }
+        }
+    }
+    place_detached(&mut comments, in_tree, &mut output);
+
+    let mut formatted = output.join("\n");
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+/// Write a forest as a TREF document with the canonical layout, keeping the comments and the order of the trees of another document, like the one the forest was parsed from before it was changed.
+///
+/// Comments are placed like in [`format_document()`]: comments attached to a tree ID stay attached to the same tree, and comments before the first tree stay at the beginning. Other comments are placed at the end of their tree, because its nodes may have changed. Comments of trees that are not in the forest are dropped.
+///
+/// Trees are written in the order of the document, followed by the trees that are not in it, sorted by tree ID.
+///
+/// ```
+/// use std::io::BufReader;
+/// use tref::{Model, RawNode, format_forest};
+///
+/// let tref = "# Settings\n\n# Main server\n[server]\n+ host\n# Port\n+ + port=80\n";
+/// let changed = "[client]\n+ url\n[server]\n+ host\n+ + port=8080\n";
+/// let forest = Model::<RawNode>::parse(BufReader::new(changed.as_bytes())).unwrap();
+/// assert_eq!(format_forest(&forest, tref).unwrap(), "# Settings\n\n# Main server\n[server]\n+ host\n+ + port=8080\n# Port\n\n[client]\n+ url\n");
+/// ```
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `tref` - TREF document with the comments.
+///
+/// # Return
+///
+/// * A [`Result`] with the document, or a [`SerializeTreeError`] if a tree can't be written.
+///
+pub fn format_forest<T: NodeContent>(forest: &Forest<T>, tref: &str) -> Result<String, SerializeTreeError> {
+    let parser = TreeParser::new();
+    // Comments at the beginning, and the tree IDs with their attached comments and the comments inside the tree
+    let mut leading: Vec<&str> = vec!();
+    let mut trees: Vec<(String, Vec<&str>, Vec<&str>)> = vec!();
+    let mut comments: Vec<&str> = vec!();
+    for line in tref.lines().map(|l| l.trim_end()) {
+        match parser.parse_statement(line) {
+            TreeStatement::Comment => comments.push(line),
+            TreeStatement::TreeID(tree_id) => trees.push((tree_id, std::mem::take(&mut comments), vec!())),
+            TreeStatement::Empty | TreeStatement::Node(_, _) | TreeStatement::Invalid => match trees.last_mut() {
+                Some((_, _, inside)) => inside.append(&mut comments),
+                None => leading.append(&mut comments)
+            }
+        }
+    }
+    match trees.last_mut() {
+        Some((_, _, inside)) => inside.append(&mut comments),
+        None => leading.append(&mut comments)
+    }
+
+    let mut tree_ids: Vec<&str> = vec!();
+    for (tree_id, _, _) in trees.iter() {
+        if forest.get_tree(tree_id).is_some() && !tree_ids.contains(&tree_id.as_str()) {
+            tree_ids.push(tree_id);
+        }
+    }
+    let mut others: Vec<&str> = sorted_tree_ids(forest).into_iter().map(|id| id.as_str()).filter(|id| !tree_ids.contains(id)).collect();
+    tree_ids.append(&mut others);
+
+    let mut output: Vec<u8> = vec!();
+    let mut num_lines = 0;
+    let write_comments = |output: &mut Vec<u8>, comments: &[&str]| for comment in comments {
+        output.extend_from_slice(comment.as_bytes());
+        output.push(b'\n');
+    };
+    write_comments(&mut output, &leading);
+    for tree_id in tree_ids {
+        if !output.is_empty() {
+            output.push(b'\n');
+        }
+        let (before, inside) = match trees.iter().find(|(id, _, _)| id == tree_id) {
+            Some((_, before, inside)) => (before.as_slice(), inside.as_slice()),
+            None => (&[][..], &[][..])
+        };
+        write_comments(&mut output, before);
+        if let Some(tree) = forest.get_tree(tree_id) {
+            serialize_tree(&parser, tree_id, tree, &mut output, &mut num_lines)?;
+        }
+        write_comments(&mut output, inside);
+    }
+    Ok(String::from_utf8_lossy(&output).into_owned())
+}
+
+/// Place comments that are not attached to the next tree ID: at the end of the current tree, or as a separate block before the first tree.
+fn place_detached<'a>(comments: &mut Vec<&'a str>, in_tree: bool, output: &mut Vec<&'a str>) {
+    if comments.is_empty() {
+        return;
+    }
+    if !in_tree && !output.is_empty() {
+        output.push("");
+    }
+    output.append(comments);
+}
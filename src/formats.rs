@@ -0,0 +1,340 @@
+//! Conversion between TREF trees and other formats.
+//!
+//! Each format lives in its own module, with `read_*` functions to import trees and `write_*` functions to export them.
+//!
+//! Readers build trees of any dialect that implements [`TryNodeContent`], and check every node exactly as [`Model::try_parse()`][`crate::Model::try_parse()`] does, failing with an [`ImportTreeError`] when the dialect rejects one. Writers use the content generated by [`NodeContent::gen_content()`][`socarel::NodeContent::gen_content()`], like [`Model::serialize()`][`crate::Model::serialize()`], and skip unlinked nodes. Forests are always written sorted by tree ID.
+//!
+//! Diagram formats, like [`dot`] and [`mermaid`], only have writers, configured with [`DiagramOptions`]. Trees can also be rendered directly as images with [`svg`], or as text with [`text`].
+
+mod diagram;
+
+pub mod newick;
+pub mod nexus;
+#[cfg(feature = "xml")]
+pub mod phyloxml;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod dot;
+pub mod mermaid;
+pub mod svg;
+pub mod text;
+pub mod markdown;
+pub mod org;
+pub mod paths;
+pub mod csv;
+pub mod fs;
+#[cfg(feature = "xml")]
+pub mod xml;
+pub mod sexp;
+
+pub use diagram::{DiagramOptions, Direction, NodeLabel};
+pub use svg::{SvgOptions, NodeShape};
+pub use text::TextOptions;
+
+use socarel::{Forest, Tree};
+use crate::builder::*;
+use crate::error::*;
+use crate::dialect::*;
+
+/// Node of a tree being imported, with all its descendants.
+pub(crate) struct Branch {
+    pub content: String,
+    pub line: Option<usize>,
+    pub children: Vec<Branch>
+}
+
+impl Branch {
+    pub fn new(content: &str, line: Option<usize>) -> Self {
+        Self {
+            content: String::from(content),
+            line,
+            children: vec!()
+        }
+    }
+}
+
+impl Drop for Branch {
+    // Drop deep trees without recursion
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(mut branch) = stack.pop() {
+            stack.append(&mut branch.children);
+        }
+    }
+}
+
+/// Builds a forest from outline formats, where each node comes with its level.
+///
+/// Levels are checked like in a TREF document.
+pub(crate) struct OutlineBuilder<T: TryNodeContent> {
+    forest: Forest<T>,
+    builder: Option<TreeBuilder<T>>
+}
+
+impl<T: TryNodeContent> OutlineBuilder<T> {
+    pub fn new() -> Self {
+        Self {
+            forest: Forest::new(),
+            builder: None
+        }
+    }
+
+    pub fn start_tree(&mut self, tree_id: &str) {
+        self.end_tree();
+        self.builder = Some(TreeBuilder::checked(tree_id));
+    }
+
+    pub fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ImportTreeError> {
+        match self.builder.as_mut() {
+            Some(builder) => builder.add_node(content, level, line).map_err(|e| ImportTreeError::from_parse_error(e, Some(line))),
+            None => Err(ImportTreeError::with_content("Found node without previous tree ID", Some(line), content))
+        }
+    }
+
+    pub fn finish(mut self) -> Forest<T> {
+        self.end_tree();
+        self.forest
+    }
+
+    fn end_tree(&mut self) {
+        if let Some(builder) = self.builder.take() {
+            let tree_id = String::from(builder.tree_id());
+            self.forest.add_tree(&tree_id, builder.finish());
+        }
+    }
+}
+
+/// Content of unnamed nodes in phylogenetic trees. Node contents can't be empty, so labels made only of underscores get one more.
+pub(crate) const UNNAMED: &str = "_";
+
+/// Get the node content of a phylogenetic label, escaping the labels that would look like [`UNNAMED`].
+pub(crate) fn label_content(label: &str) -> String {
+    if label.chars().all(|c| c == '_') {
+        format!("_{}", label)
+    }
+    else {
+        String::from(label)
+    }
+}
+
+/// Get the phylogenetic label of a node content, the reverse of [`label_content()`].
+///
+/// # Return
+///
+/// * An [`Option`] with the label, or None for unnamed nodes.
+///
+pub(crate) fn content_label(content: &str) -> Option<&str> {
+    if content == UNNAMED {
+        None
+    }
+    else if !content.is_empty() && content.chars().all(|c| c == '_') {
+        Some(&content[1..])
+    }
+    else {
+        Some(content)
+    }
+}
+
+/// Node of a phylogenetic tree being imported, before knowing if the tree has branch lengths.
+pub(crate) struct PhyloNode {
+    pub label: String,
+    pub length: Option<String>,
+    pub line: usize,
+    pub children: Vec<PhyloNode>
+}
+
+impl PhyloNode {
+    pub fn new(line: usize) -> Self {
+        Self {
+            label: String::new(),
+            length: None,
+            line,
+            children: vec!()
+        }
+    }
+
+    /// Convert into a branch, with `length:label` contents if any node has a branch length.
+    pub fn into_tree_branch(mut self) -> Branch {
+        let with_lengths = self.has_lengths();
+        // The branch being built and its ancestors, with the nodes of their children still to convert
+        let mut ancestors: Vec<(Branch, std::vec::IntoIter<PhyloNode>)> = vec!();
+        let mut current = (self.to_branch(with_lengths), std::mem::take(&mut self.children).into_iter());
+        loop {
+            match current.1.next() {
+                Some(mut child) => {
+                    let next = (child.to_branch(with_lengths), std::mem::take(&mut child.children).into_iter());
+                    ancestors.push(std::mem::replace(&mut current, next));
+                },
+                None => match ancestors.pop() {
+                    Some(mut parent) => {
+                        parent.0.children.push(current.0);
+                        current = parent;
+                    },
+                    None => return current.0
+                }
+            }
+        }
+    }
+
+    fn has_lengths(&self) -> bool {
+        let mut stack = vec!(self);
+        while let Some(node) = stack.pop() {
+            if node.length.is_some() {
+                return true;
+            }
+            stack.extend(node.children.iter());
+        }
+        false
+    }
+
+    /// Convert into a branch without children.
+    fn to_branch(&self, with_lengths: bool) -> Branch {
+        let label = label_content(&self.label);
+        let content = if with_lengths {
+            format!("{}:{}", self.length.as_deref().unwrap_or("0"), label)
+        }
+        else {
+            label
+        };
+        Branch::new(&content, Some(self.line))
+    }
+}
+
+impl Drop for PhyloNode {
+    // Drop deep trees without recursion
+    fn drop(&mut self) {
+        let mut stack = std::mem::take(&mut self.children);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children);
+        }
+    }
+}
+
+/// Build a tree from an imported branch, checking nodes with the dialect.
+pub(crate) fn build_tree<T: TryNodeContent>(tree_id: &str, root: &Branch) -> Result<Tree<T>, ImportTreeError> {
+    let mut builder = TreeBuilder::<T>::checked(tree_id);
+    let mut stack = vec!((root, 1));
+    while let Some((branch, level)) = stack.pop() {
+        if let Err(e) = builder.add_node(&branch.content, level, branch.line.unwrap_or(0)) {
+            return Err(ImportTreeError::from_parse_error(e, branch.line));
+        }
+        for child in branch.children.iter().rev() {
+            stack.push((child, level + 1));
+        }
+    }
+    Ok(builder.finish())
+}
+
+/// Node of an adjacency list, that references its parent by ID.
+pub(crate) struct AdjacencyNode {
+    pub id: String,
+    pub parent: Option<String>,
+    pub content: String,
+    pub line: Option<usize>
+}
+
+/// Build branches from an adjacency list, one for each root node.
+///
+/// Children keep the order of the list. Fails if IDs are duplicated, parents don't exist or there are cycles.
+pub(crate) fn adjacency_branches(nodes: &[AdjacencyNode]) -> Result<Vec<Branch>, ImportTreeError> {
+    let mut positions = std::collections::HashMap::new();
+    for (position, node) in nodes.iter().enumerate() {
+        if positions.insert(node.id.as_str(), position).is_some() {
+            return Err(ImportTreeError::new(&format!("Duplicated node ID `{}`", node.id), node.line));
+        }
+    }
+    let mut roots = vec!();
+    let mut children: Vec<Vec<usize>> = vec![vec!(); nodes.len()];
+    for (position, node) in nodes.iter().enumerate() {
+        match &node.parent {
+            Some(parent) => match positions.get(parent.as_str()) {
+                Some(parent_position) => children[*parent_position].push(position),
+                None => return Err(ImportTreeError::new(&format!("Unknown parent `{}` of node `{}`", parent, node.id), node.line))
+            },
+            None => roots.push(position)
+        }
+    }
+    let mut visited = vec![false; nodes.len()];
+    let mut branches = vec!();
+    for root in roots {
+        branches.push(expand_branch(root, |position| {
+            visited[position] = true;
+            Ok((Branch::new(&nodes[position].content, nodes[position].line), children[position].iter().copied()))
+        })?);
+    }
+    // Nodes not reachable from a root have a parent, so they must be in a cycle
+    if let Some(position) = visited.iter().position(|v| !v) {
+        return Err(ImportTreeError::new(&format!("Cycle found at node `{}`", nodes[position].id), nodes[position].line));
+    }
+    Ok(branches)
+}
+
+/// Build a branch from nested nodes of any kind, without recursion.
+///
+/// # Arguments
+///
+/// * `root` - Root node.
+/// * `expand` - Function that gets the branch of a node, without children, and its child nodes.
+///
+/// # Return
+///
+/// * A [`Result`] with the branch or the first [`ImportTreeError`] of `expand`.
+///
+pub(crate) fn expand_branch<N, I, F>(root: N, mut expand: F) -> Result<Branch, ImportTreeError>
+where I: Iterator<Item = N>, F: FnMut(N) -> Result<(Branch, I), ImportTreeError> {
+    // The branch being built and its ancestors, with the child nodes still to expand
+    let mut ancestors = vec!();
+    let mut current = expand(root)?;
+    loop {
+        match current.1.next() {
+            Some(child) => {
+                let next = expand(child)?;
+                ancestors.push(std::mem::replace(&mut current, next));
+            },
+            None => match ancestors.pop() {
+                Some(mut parent) => {
+                    parent.0.children.push(current.0);
+                    current = parent;
+                },
+                None => return Ok(current.0)
+            }
+        }
+    }
+}
+
+/// Build a forest with one tree per root branch, named after the root content.
+pub(crate) fn forest_by_root<T: TryNodeContent>(roots: &[Branch]) -> Result<Forest<T>, ImportTreeError> {
+    let mut forest = Forest::new();
+    for root in roots {
+        if forest.get_tree(&root.content).is_some() {
+            return Err(ImportTreeError::with_content("Duplicated tree ID", root.line, &root.content));
+        }
+        forest.add_tree(&root.content, build_tree(&root.content, root)?);
+    }
+    Ok(forest)
+}
+
+/// Build a tree from the only root branch.
+pub(crate) fn single_root<T: TryNodeContent>(roots: &[Branch]) -> Result<Tree<T>, ImportTreeError> {
+    match roots {
+        [root] => build_tree(&root.content, root),
+        [] => Err(ImportTreeError::new("No root node found", None)),
+        [_, second, ..] => Err(ImportTreeError::with_content("Multiple root nodes in the same tree", second.line, &second.content))
+    }
+}
+
+/// Escape XML special characters.
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c)
+        }
+    }
+    escaped
+}
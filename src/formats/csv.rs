@@ -0,0 +1,130 @@
+//! Parent/child CSV tables.
+//!
+//! Each row is a node, with the columns `id`, `parent_id` and `label`. Root nodes have an empty `parent_id`. Children keep the order of the rows, and a parent can appear after its children. A first row with these column names is taken as a header.
+//!
+//! ```
+//! use tref::{Tree, RawNode, NodeContent, formats::csv};
+//!
+//! let text = "id,parent_id,label\n1,,root_node\n2,1,child_1\n3,1,\"child, 2\"\n";
+//! let tree: Tree<RawNode> = csv::read_tree(text).unwrap();
+//! assert_eq!(tree.get_node_content(2).unwrap().get_val(), "child, 2");
+//! assert_eq!(csv::write_tree(&tree), "id,parent_id,label\n0,,root_node\n1,0,child_1\n2,0,\"child, 2\"\n");
+//! ```
+//!
+//! Fields can be quoted with `"`, using `""` for a quote inside a quoted field. Fields can't contain line breaks.
+
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a tree from a CSV table.
+///
+/// # Arguments
+///
+/// * `text` - CSV table.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree or an [`ImportTreeError`]. The table must have exactly one root node, and no cycles.
+///
+pub fn read_tree<T: TryNodeContent>(text: &str) -> Result<Tree<T>, ImportTreeError> {
+    single_root(&adjacency_branches(&read_rows(text)?)?)
+}
+
+/// Read a forest from a CSV table.
+///
+/// # Arguments
+///
+/// * `text` - CSV table.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`]. There is one tree for each root node, using the root label as tree ID. The table can't have cycles.
+///
+pub fn read_forest<T: TryNodeContent>(text: &str) -> Result<Forest<T>, ImportTreeError> {
+    forest_by_root(&adjacency_branches(&read_rows(text)?)?)
+}
+
+/// Write a tree as a CSV table.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+///
+/// # Return
+///
+/// * CSV table with a header, and one row per node in pre-order, using node positions as IDs.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>) -> String {
+    let mut text = String::from("id,parent_id,label\n");
+    for position in pre_order(tree) {
+        let node = &tree.get_nodes_ref()[position];
+        let parent = node.get_parent_position().map(|p| p.to_string()).unwrap_or_default();
+        text.push_str(&format!("{},{},{}\n", position, parent, quote(&node.get_content_ref().gen_content())));
+    }
+    text
+}
+
+fn read_rows(text: &str) -> Result<Vec<AdjacencyNode>, ImportTreeError> {
+    let mut nodes = vec!();
+    for (line, row) in text.lines().enumerate() {
+        if row.trim().is_empty() {
+            continue;
+        }
+        let fields = split_row(row, line)?;
+        if fields.len() != 3 {
+            return Err(ImportTreeError::new(&format!("Expected 3 columns, found {}", fields.len()), Some(line)));
+        }
+        if nodes.is_empty() && fields[0].trim() == "id" && fields[1].trim() == "parent_id" && fields[2].trim() == "label" {
+            continue;
+        }
+        let parent = fields[1].trim();
+        nodes.push(AdjacencyNode {
+            id: String::from(fields[0].trim()),
+            parent: if parent.is_empty() { None } else { Some(String::from(parent)) },
+            content: fields[2].clone(),
+            line: Some(line)
+        });
+    }
+    Ok(nodes)
+}
+
+/// Split a CSV row into fields.
+fn split_row(row: &str, line: usize) -> Result<Vec<String>, ImportTreeError> {
+    let mut fields = vec!();
+    let mut field = String::new();
+    let mut chars = row.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            },
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            },
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c)
+        }
+    }
+    if quoted {
+        return Err(ImportTreeError::new("Unclosed quoted field", Some(line)));
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Quote a CSV field if needed.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) || field.trim() != field {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        String::from(field)
+    }
+}
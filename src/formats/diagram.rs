@@ -0,0 +1,119 @@
+use socarel::{Tree, NodeContent};
+use crate::dialects::WeightedEdgeNode;
+use crate::walk::*;
+
+/// Direction of a diagram, from the root to the leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    TopDown,
+    BottomUp,
+    LeftRight,
+    RightLeft
+}
+
+/// Text shown in the diagram nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeLabel {
+    /// Node content.
+    Content,
+    /// Node content followed by the node position in the tree, like `child_1 (1)`.
+    ContentAndPosition
+}
+
+/// Options to render trees as diagrams.
+///
+/// ```
+/// use tref::formats::{DiagramOptions, Direction, NodeLabel};
+///
+/// let options = DiagramOptions::new()
+///     .direction(Direction::LeftRight)
+///     .label(NodeLabel::ContentAndPosition)
+///     .clusters(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiagramOptions {
+    label: NodeLabel,
+    direction: Direction,
+    clusters: bool
+}
+
+impl DiagramOptions {
+    /// Create default options: node contents as labels, top-down direction and one cluster per tree.
+    pub fn new() -> Self {
+        Self {
+            label: NodeLabel::Content,
+            direction: Direction::TopDown,
+            clusters: true
+        }
+    }
+
+    /// Set the text of the nodes.
+    pub fn label(mut self, label: NodeLabel) -> Self {
+        self.label = label;
+        self
+    }
+
+    /// Set the diagram direction.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set if each tree of a forest is drawn inside its own cluster, labeled with the tree ID.
+    pub fn clusters(mut self, clusters: bool) -> Self {
+        self.clusters = clusters;
+        self
+    }
+
+    pub(crate) fn get_direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub(crate) fn get_clusters(&self) -> bool {
+        self.clusters
+    }
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nodes and edges of a tree, ready to be rendered.
+pub(crate) struct Diagram {
+    /// Node positions and labels, in pre-order.
+    pub nodes: Vec<(usize, String)>,
+    /// Parent position, child position and edge label.
+    pub edges: Vec<(usize, usize, Option<String>)>
+}
+
+/// Get the label of a node, and no edge label.
+pub(crate) fn content_label<T: NodeContent>(content: &T) -> (String, Option<String>) {
+    (content.gen_content(), None)
+}
+
+/// Get the name of a weighted node, and its weight as the label of the edge that connects it with its parent.
+pub(crate) fn weighted_label(content: &WeightedEdgeNode) -> (String, Option<String>) {
+    (String::from(content.get_name()), Some(content.get_weight().to_string()))
+}
+
+impl Diagram {
+    pub fn new<T: NodeContent>(tree: &Tree<T>, options: &DiagramOptions, label_of: &dyn Fn(&T) -> (String, Option<String>)) -> Self {
+        let mut nodes = vec!();
+        let mut edges = vec!();
+        for position in pre_order(tree) {
+            let node = &tree.get_nodes_ref()[position];
+            let (label, edge_label) = label_of(node.get_content_ref());
+            let label = match options.label {
+                NodeLabel::Content => label,
+                NodeLabel::ContentAndPosition => format!("{} ({})", label, position)
+            };
+            nodes.push((position, label));
+            if let Some(parent) = node.get_parent_position() {
+                edges.push((parent, position, edge_label));
+            }
+        }
+        Self { nodes, edges }
+    }
+}
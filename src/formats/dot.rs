@@ -0,0 +1,125 @@
+//! Graphviz DOT format.
+//!
+//! Trees are rendered as directed graphs, that can be converted into images with the Graphviz tools, like `dot -Tsvg tree.dot > tree.svg`.
+//!
+//! ```
+//! use tref::{tref, Tree, RawNode, formats::{dot, DiagramOptions}};
+//!
+//! let tree: Tree<RawNode> = tref!("root_node" => { "child_1", "child_2" });
+//! let graph = dot::write_tree(&tree, &DiagramOptions::new());
+//! assert!(graph.contains("n0 -> n1;"));
+//! ```
+//!
+//! Weighted trees can be rendered with the weights as edge labels:
+//!
+//! ```
+//! use tref::{tref, Tree, formats::{dot, DiagramOptions}, dialects::WeightedEdgeNode};
+//!
+//! let tree: Tree<WeightedEdgeNode> = tref!("0:root_node" => { "27:child_1" });
+//! let graph = dot::write_weighted_tree(&tree, &DiagramOptions::new());
+//! assert!(graph.contains("n0 -> n1 [label=\"27\"];"));
+//! ```
+
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::diagram::*;
+use crate::dialects::WeightedEdgeNode;
+use crate::walk::*;
+
+/// Write a tree as a DOT graph.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * DOT graph.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, options: &DiagramOptions) -> String {
+    graph(&[("tree", tree)], options, &content_label, false)
+}
+
+/// Write a weighted tree as a DOT graph, with weights as edge labels.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * DOT graph.
+///
+pub fn write_weighted_tree(tree: &Tree<WeightedEdgeNode>, options: &DiagramOptions) -> String {
+    graph(&[("tree", tree)], options, &weighted_label, false)
+}
+
+/// Write a forest as a DOT graph.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * DOT graph, with trees sorted by ID.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>, options: &DiagramOptions) -> String {
+    graph(&sorted_trees(forest), options, &content_label, true)
+}
+
+/// Write a weighted forest as a DOT graph, with weights as edge labels.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * DOT graph, with trees sorted by ID.
+///
+pub fn write_weighted_forest(forest: &Forest<WeightedEdgeNode>, options: &DiagramOptions) -> String {
+    graph(&sorted_trees(forest), options, &weighted_label, true)
+}
+
+fn graph<T: NodeContent>(trees: &[(&str, &Tree<T>)], options: &DiagramOptions, label_of: &dyn Fn(&T) -> (String, Option<String>), is_forest: bool) -> String {
+    let rankdir = match options.get_direction() {
+        Direction::TopDown => "TB",
+        Direction::BottomUp => "BT",
+        Direction::LeftRight => "LR",
+        Direction::RightLeft => "RL"
+    };
+    let name = if is_forest { "forest" } else { trees[0].0 };
+    let mut dot = format!("digraph {} {{\n    rankdir={};\n", quote(name), rankdir);
+    for (i, (tree_id, tree)) in trees.iter().enumerate() {
+        let prefix = if is_forest { format!("t{}_", i) } else { String::new() };
+        let cluster = is_forest && options.get_clusters();
+        let indent = if cluster { "        " } else { "    " };
+        if cluster {
+            dot.push_str(&format!("    subgraph {} {{\n        label={};\n", quote(&format!("cluster_{}", i)), quote(tree_id)));
+        }
+        let diagram = Diagram::new(*tree, options, label_of);
+        for (position, label) in diagram.nodes {
+            dot.push_str(&format!("{}{}n{} [label={}];\n", indent, prefix, position, quote(&label)));
+        }
+        for (parent, child, label) in diagram.edges {
+            match label {
+                Some(label) => dot.push_str(&format!("{}{}n{} -> {}n{} [label={}];\n", indent, prefix, parent, prefix, child, quote(&label))),
+                None => dot.push_str(&format!("{}{}n{} -> {}n{};\n", indent, prefix, parent, prefix, child))
+            }
+        }
+        if cluster {
+            dot.push_str("    }\n");
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Quote and escape a DOT string.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
@@ -0,0 +1,235 @@
+//! File system directories.
+//!
+//! A directory is converted into a tree with the directory as root node, and its files and subdirectories as children, sorted by name. By default node contents are just the entry names. With metadata enabled, contents have the format of [`FileNode`], like `file:1234:main.rs`.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use tref::{Forest, formats::fs::{self, DirOptions}, dialects::FileNode};
+//!
+//! let forest: Forest<FileNode> = fs::read_forest(Path::new("src"), &DirOptions::new().metadata(true)).unwrap();
+//! let tree = forest.get_tree("src").unwrap();
+//! fs::write_file_tree(tree, Path::new("/tmp/fixture")).unwrap();
+//! ```
+//!
+//! Trees can also be materialized as empty files and directories. Existing files are never overwritten, and node names that are not a single path component, like `..` or `a/b`, or that are duplicated among siblings, are rejected before creating anything.
+//!
+//! Entries with a name that can't be a TREF node content, because it contains a line break or, without metadata, starts with a space or `+`, can't be read.
+
+use std::collections::HashSet as Set;
+use std::fs;
+use std::io;
+use std::path::Path;
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::dialects::{FileNode, FileKind};
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Options to read directories.
+#[derive(Debug, Clone)]
+pub struct DirOptions {
+    metadata: bool,
+    hidden: bool,
+    max_depth: Option<usize>
+}
+
+impl DirOptions {
+    /// Create default options: only names, no hidden entries and no depth limit.
+    pub fn new() -> Self {
+        Self {
+            metadata: false,
+            hidden: false,
+            max_depth: None
+        }
+    }
+
+    /// Set if node contents include the entry kind and size, like `file:1234:main.rs`, to be parsed with [`FileNode`].
+    pub fn metadata(mut self, metadata: bool) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Set if hidden entries, with a name that starts with `.`, are included.
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Set the maximum depth, where the root directory is depth 0. Contents of deeper directories are not read.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+impl Default for DirOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a directory as a tree.
+///
+/// # Arguments
+///
+/// * `path` - Directory path.
+/// * `options` - Directory options.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree or an [`ImportTreeError`].
+///
+pub fn read_tree<T: TryNodeContent>(path: &Path, options: &DirOptions) -> Result<Tree<T>, ImportTreeError> {
+    let name = dir_name(path)?;
+    let root = read_entry(path, &name, 0, options)?;
+    build_tree(&name, &root)
+}
+
+/// Read a directory as a forest with one tree, named after the directory.
+///
+/// # Arguments
+///
+/// * `path` - Directory path.
+/// * `options` - Directory options.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(path: &Path, options: &DirOptions) -> Result<Forest<T>, ImportTreeError> {
+    let mut forest = Forest::new();
+    forest.add_tree(&dir_name(path)?, read_tree(path, options)?);
+    Ok(forest)
+}
+
+/// Create the files and directories of a tree, using node contents as names.
+///
+/// Nodes with children are directories, and leaf nodes are empty files.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `target` - Directory where the root node is created.
+///
+/// # Return
+///
+/// * An [`io::Result`], that fails if a node name is invalid, a file already exists or the file system fails.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, target: &Path) -> io::Result<()> {
+    write_entries(tree, target, |tree, position| {
+        let name = tree.get_node_content(position).map(|c| c.gen_content()).unwrap_or_default();
+        (name, !children(tree, position).is_empty())
+    })
+}
+
+/// Create the files and directories of a [`FileNode`] tree.
+///
+/// Directory nodes are directories, even if empty, and file and link nodes are empty files.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `target` - Directory where the root node is created.
+///
+/// # Return
+///
+/// * An [`io::Result`], that fails if a node name is invalid, a file already exists or the file system fails.
+///
+pub fn write_file_tree(tree: &Tree<FileNode>, target: &Path) -> io::Result<()> {
+    write_entries(tree, target, |tree, position| {
+        match tree.get_node_content(position) {
+            Some(content) => (String::from(content.get_name()), content.get_kind() == FileKind::Dir),
+            None => (String::new(), false)
+        }
+    })
+}
+
+/// Create the entries of a tree, getting the name of each node and if it is a directory with `entry_of`.
+fn write_entries<T: NodeContent, F: Fn(&Tree<T>, usize) -> (String, bool)>(tree: &Tree<T>, target: &Path, entry_of: F) -> io::Result<()> {
+    let nodes = pre_order(tree);
+    // Check all names before creating anything
+    for position in nodes.iter() {
+        let (name, _) = entry_of(tree, *position);
+        if !is_valid_name(&name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid file name `{}` at node {}", name, position)));
+        }
+        let mut names = Set::new();
+        for child in children(tree, *position) {
+            let (name, _) = entry_of(tree, child);
+            if names.contains(&name) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Duplicated file name `{}` at node {}", name, child)));
+            }
+            names.insert(name);
+        }
+    }
+    for position in nodes {
+        let mut path = target.to_path_buf();
+        for p in position_path(tree, position) {
+            path.push(entry_of(tree, p).0);
+        }
+        if entry_of(tree, position).1 {
+            fs::create_dir_all(&path)?;
+        }
+        else {
+            fs::OpenOptions::new().write(true).create_new(true).open(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check that a name is a single, normal path component.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+fn dir_name(path: &Path) -> Result<String, ImportTreeError> {
+    // Canonicalize to get a name for paths like `.`
+    let path = path.canonicalize().map_err(|e| ImportTreeError::new(&format!("Could not read `{}` ({})", path.display(), e), None))?;
+    match path.file_name() {
+        Some(name) => Ok(name.to_string_lossy().into_owned()),
+        None => Ok(path.to_string_lossy().into_owned())
+    }
+}
+
+fn read_entry(path: &Path, name: &str, depth: usize, options: &DirOptions) -> Result<Branch, ImportTreeError> {
+    let io_error = |e: io::Error| ImportTreeError::new(&format!("Could not read `{}` ({})", path.display(), e), None);
+    // Follow the root directory if it is a link, but not the links inside it
+    let metadata = if depth == 0 { fs::metadata(path) } else { fs::symlink_metadata(path) }.map_err(io_error)?;
+    let kind = if metadata.file_type().is_symlink() {
+        FileKind::Link
+    }
+    else if metadata.is_dir() {
+        FileKind::Dir
+    }
+    else {
+        FileKind::File
+    };
+    // Names that can't be written as a TREF node statement
+    if name.contains(['\n', '\r']) || (!options.metadata && name.starts_with([' ', '+'])) {
+        return Err(ImportTreeError::new(&format!("Entry name of `{}` can't be a node content", path.display()), None));
+    }
+    let content = if options.metadata {
+        let size = if kind == FileKind::File { metadata.len() } else { 0 };
+        format!("{}:{}:{}", kind, size, name)
+    }
+    else {
+        String::from(name)
+    };
+    let mut branch = Branch::new(&content, None);
+    if kind == FileKind::Dir && options.max_depth.map(|max| depth < max).unwrap_or(true) {
+        let mut entries = vec!();
+        for entry in fs::read_dir(path).map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if options.hidden || !name.starts_with('.') {
+                entries.push((name, entry.path()));
+            }
+        }
+        entries.sort();
+        for (name, path) in entries {
+            branch.children.push(read_entry(&path, &name, depth + 1, options)?);
+        }
+    }
+    Ok(branch)
+}
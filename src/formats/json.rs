@@ -0,0 +1,226 @@
+//! JSON format. Requires the `json` feature.
+//!
+//! Forests are JSON objects with one entry per tree. Trees can be represented in two ways:
+//!
+//! * Nested, where each node is an object with its `content` and its `children` array: `{"my_tree": {"content": "root", "children": [{"content": "child"}]}}`.
+//! * Adjacency list, where each tree is an array of nodes that reference their parent by `id`: `{"my_tree": [{"id": 0, "parent": null, "content": "root"}, {"id": 1, "parent": 0, "content": "child"}]}`.
+//!
+//! When reading, node contents can also be numbers or booleans, and IDs can be numbers or strings. When writing, IDs are the node positions in the tree.
+//!
+//! Empty trees are `null` in nested JSON, and an empty array in adjacency lists.
+//!
+//! Nested JSON can be read up to 63 tree levels, because each level takes two of the 128 nesting levels allowed by the JSON parser. Adjacency lists have no depth limit.
+//!
+//! ```
+//! use tref::{Forest, RawNode, formats::json};
+//!
+//! let text = r#"{"my_tree": {"content": "root", "children": [{"content": "child_1"}, {"content": "child_2"}]}}"#;
+//! let forest: Forest<RawNode> = json::read_forest(text).unwrap();
+//! assert_eq!(forest.get_tree("my_tree").unwrap().get_nodes_len(), 3);
+//! assert_eq!(json::write_forest(&forest), text.replace(" ", ""));
+//! ```
+
+use serde_json::{json, Map, Value};
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a forest from nested JSON.
+///
+/// # Arguments
+///
+/// * `json` - JSON document.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(json: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut forest = Forest::new();
+    for (tree_id, root) in read_trees(json)? {
+        if root.is_null() {
+            forest.add_tree(&tree_id, Tree::new());
+            continue;
+        }
+        let branch = nested_branch(&root)?;
+        forest.add_tree(&tree_id, build_tree(&tree_id, &branch)?);
+    }
+    Ok(forest)
+}
+
+/// Read a forest from a JSON adjacency list.
+///
+/// # Arguments
+///
+/// * `json` - JSON document.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_adjacency_forest<T: TryNodeContent>(json: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut forest = Forest::new();
+    for (tree_id, nodes) in read_trees(json)? {
+        let nodes = match nodes {
+            Value::Array(nodes) => nodes,
+            _ => return Err(ImportTreeError::new(&format!("Tree `{}` must be an array of nodes", tree_id), None))
+        };
+        if nodes.is_empty() {
+            forest.add_tree(&tree_id, Tree::new());
+            continue;
+        }
+        let nodes = nodes.iter().map(adjacency_node).collect::<Result<Vec<_>, _>>()?;
+        let mut branches = adjacency_branches(&nodes)?;
+        if branches.len() != 1 {
+            return Err(ImportTreeError::new(&format!("Tree `{}` must have one root node, found {}", tree_id, branches.len()), None));
+        }
+        forest.add_tree(&tree_id, build_tree(&tree_id, &branches.remove(0))?);
+    }
+    Ok(forest)
+}
+
+/// Write a forest as nested JSON.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * JSON document.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    let mut json = String::from("{");
+    for (i, (tree_id, tree)) in sorted_trees(forest).into_iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!("{}:", Value::from(tree_id)));
+        if tree.get_nodes_len() > 0 {
+            write_nested_node(tree, 0, &mut json);
+        }
+        else {
+            json.push_str("null");
+        }
+    }
+    json.push('}');
+    json
+}
+
+/// Write a forest as a JSON adjacency list.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * JSON document, with nodes in pre-order.
+///
+pub fn write_adjacency_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    let mut trees = Map::new();
+    for tree_id in sorted_tree_ids(forest) {
+        if let Some(tree) = forest.get_tree(tree_id) {
+            let nodes: Vec<Value> = pre_order(tree).into_iter().map(|position| {
+                let node = &tree.get_nodes_ref()[position];
+                json!({
+                    "id": position,
+                    "parent": node.get_parent_position(),
+                    "content": node.get_content_ref().gen_content()
+                })
+            }).collect();
+            trees.insert(tree_id.clone(), Value::Array(nodes));
+        }
+    }
+    Value::Object(trees).to_string()
+}
+
+/// Step of writing a nested node, to write deep trees without recursion.
+enum Step {
+    Open(usize),
+    Separator,
+    Close
+}
+
+fn write_nested_node<T: NodeContent>(tree: &Tree<T>, position: usize, json: &mut String) {
+    let mut steps = vec!(Step::Open(position));
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Open(position) => {
+                let content = tree.get_node_content(position).map(|c| c.gen_content()).unwrap_or_default();
+                json.push_str(&format!("{{\"content\":{}", Value::from(content)));
+                let children = children(tree, position);
+                if children.is_empty() {
+                    json.push('}');
+                    continue;
+                }
+                json.push_str(",\"children\":[");
+                steps.push(Step::Close);
+                for (i, child) in children.into_iter().enumerate().rev() {
+                    steps.push(Step::Open(child));
+                    if i > 0 {
+                        steps.push(Step::Separator);
+                    }
+                }
+            },
+            Step::Separator => json.push(','),
+            Step::Close => json.push_str("]}")
+        }
+    }
+}
+
+/// Parse the document and get its trees.
+fn read_trees(json: &str) -> Result<Vec<(String, Value)>, ImportTreeError> {
+    let value: Value = serde_json::from_str(json).map_err(|e| ImportTreeError::new(&format!("Invalid JSON ({})", e), Some(e.line().saturating_sub(1))))?;
+    match value {
+        Value::Object(trees) => Ok(trees.into_iter().collect()),
+        _ => Err(ImportTreeError::new("Expected an object with trees", None))
+    }
+}
+
+fn nested_branch(root: &Value) -> Result<Branch, ImportTreeError> {
+    expand_branch(root, |node| {
+        let branch = Branch::new(&node_content(node)?, None);
+        let children: &[Value] = match node.get("children") {
+            Some(Value::Array(children)) => children,
+            Some(Value::Null) | None => &[],
+            Some(_) => return Err(ImportTreeError::with_content("Node children must be an array", None, &branch.content))
+        };
+        Ok((branch, children.iter()))
+    })
+}
+
+fn adjacency_node(node: &Value) -> Result<AdjacencyNode, ImportTreeError> {
+    let content = node_content(node)?;
+    let id = match node.get("id").and_then(scalar) {
+        Some(id) => id,
+        None => return Err(ImportTreeError::with_content("Node without a valid `id`", None, &content))
+    };
+    let parent = match node.get("parent") {
+        Some(Value::Null) | None => None,
+        Some(parent) => match scalar(parent) {
+            Some(parent) => Some(parent),
+            None => return Err(ImportTreeError::with_content("Invalid node `parent`", None, &content))
+        }
+    };
+    Ok(AdjacencyNode { id, parent, content, line: None })
+}
+
+fn node_content(node: &Value) -> Result<String, ImportTreeError> {
+    match node.get("content").and_then(scalar) {
+        Some(content) => Ok(content),
+        None => Err(ImportTreeError::new(&format!("Node without a valid `content`: {}", node), None))
+    }
+}
+
+/// Convert a string, number or boolean into a string.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None
+    }
+}
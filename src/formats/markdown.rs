@@ -0,0 +1,101 @@
+//! Markdown nested lists.
+//!
+//! Each tree starts with a level 2 header with its tree ID, followed by a nested list with the nodes. The root node is the only item at the first level of the list:
+//!
+//! ```markdown
+//! ## my_tree
+//!
+//! - root_node
+//!   - child_1
+//!     - child_1_1
+//!   - child_2
+//! ```
+//!
+//! List items can start with `-`, `*`, `+`, or a number followed by `.` or `)`. Nesting is given by the indentation, where a tab counts as four spaces. Other lines, like paragraphs or other headers, are ignored.
+//!
+//! ```
+//! use tref::{Forest, RawNode, formats::markdown};
+//!
+//! let text = "## my_tree\n\n- root_node\n  - child_1\n    - child_1_1\n  - child_2\n";
+//! let forest: Forest<RawNode> = markdown::read_forest(text).unwrap();
+//! assert_eq!(forest.get_tree("my_tree").unwrap().get_nodes_len(), 4);
+//! assert_eq!(markdown::write_forest(&forest), text);
+//! ```
+
+use socarel::{Forest, NodeContent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a forest from Markdown.
+///
+/// # Arguments
+///
+/// * `markdown` - Markdown document.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(markdown: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut outline = OutlineBuilder::new();
+    // Indentation of each level of the current list
+    let mut indents: Vec<usize> = vec!();
+    for (line, text) in markdown.lines().enumerate() {
+        if let Some(tree_id) = text.strip_prefix("## ") {
+            outline.start_tree(tree_id.trim());
+            indents.clear();
+        }
+        else if let Some((indent, content)) = list_item(text) {
+            while indents.last().map(|last| indent < *last).unwrap_or(false) {
+                indents.pop();
+            }
+            if indents.last() != Some(&indent) {
+                indents.push(indent);
+            }
+            outline.add_node(content, indents.len(), line)?;
+        }
+    }
+    Ok(outline.finish())
+}
+
+/// Write a forest as Markdown.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Markdown document, with trees sorted by ID and lists indented with two spaces.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    let mut trees = vec!();
+    for (tree_id, tree) in sorted_trees(forest) {
+        let mut markdown = format!("## {}\n\n", tree_id);
+        for position in pre_order(tree) {
+            let node = &tree.get_nodes_ref()[position];
+            markdown.push_str(&format!("{}- {}\n", "  ".repeat(node.get_level() - 1), node.get_content_ref().gen_content()));
+        }
+        trees.push(markdown);
+    }
+    trees.join("\n")
+}
+
+/// Get the indentation and content of a list item.
+fn list_item(text: &str) -> Option<(usize, &str)> {
+    let trimmed = text.trim_start();
+    let indent: usize = text[..text.len() - trimmed.len()].chars().map(|c| if c == '\t' { 4 } else { 1 }).sum();
+    let content = if let Some(content) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")).or_else(|| trimmed.strip_prefix("+ ")) {
+        content
+    }
+    else {
+        let digits = trimmed.len() - trimmed.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return None;
+        }
+        trimmed[digits..].strip_prefix(". ").or_else(|| trimmed[digits..].strip_prefix(") "))?
+    };
+    Some((indent, content.trim()))
+}
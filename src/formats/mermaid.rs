@@ -0,0 +1,114 @@
+//! Mermaid flowchart format.
+//!
+//! Trees are rendered as Mermaid flowcharts, that can be embedded in Markdown documents supported by many platforms.
+//!
+//! ```
+//! use tref::{tref, Tree, RawNode, formats::{mermaid, DiagramOptions, Direction}};
+//!
+//! let tree: Tree<RawNode> = tref!("root_node" => { "child_1", "child_2" });
+//! let chart = mermaid::write_tree(&tree, &DiagramOptions::new().direction(Direction::LeftRight));
+//! assert!(chart.starts_with("flowchart LR\n"));
+//! assert!(chart.contains("n0 --> n1\n"));
+//! ```
+
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::diagram::*;
+use crate::dialects::WeightedEdgeNode;
+use crate::walk::*;
+
+/// Write a tree as a Mermaid flowchart.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * Mermaid flowchart.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, options: &DiagramOptions) -> String {
+    flowchart(&[("tree", tree)], options, &content_label, false)
+}
+
+/// Write a weighted tree as a Mermaid flowchart, with weights as edge labels.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * Mermaid flowchart.
+///
+pub fn write_weighted_tree(tree: &Tree<WeightedEdgeNode>, options: &DiagramOptions) -> String {
+    flowchart(&[("tree", tree)], options, &weighted_label, false)
+}
+
+/// Write a forest as a Mermaid flowchart.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * Mermaid flowchart, with trees sorted by ID.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>, options: &DiagramOptions) -> String {
+    flowchart(&sorted_trees(forest), options, &content_label, true)
+}
+
+/// Write a weighted forest as a Mermaid flowchart, with weights as edge labels.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `options` - Diagram options.
+///
+/// # Return
+///
+/// * Mermaid flowchart, with trees sorted by ID.
+///
+pub fn write_weighted_forest(forest: &Forest<WeightedEdgeNode>, options: &DiagramOptions) -> String {
+    flowchart(&sorted_trees(forest), options, &weighted_label, true)
+}
+
+fn flowchart<T: NodeContent>(trees: &[(&str, &Tree<T>)], options: &DiagramOptions, label_of: &dyn Fn(&T) -> (String, Option<String>), is_forest: bool) -> String {
+    let direction = match options.get_direction() {
+        Direction::TopDown => "TD",
+        Direction::BottomUp => "BT",
+        Direction::LeftRight => "LR",
+        Direction::RightLeft => "RL"
+    };
+    let mut chart = format!("flowchart {}\n", direction);
+    for (i, (tree_id, tree)) in trees.iter().enumerate() {
+        let prefix = if is_forest { format!("t{}_", i) } else { String::new() };
+        let cluster = is_forest && options.get_clusters();
+        let indent = if cluster { "        " } else { "    " };
+        if cluster {
+            chart.push_str(&format!("    subgraph t{} [{}]\n", i, quote(tree_id)));
+        }
+        let diagram = Diagram::new(*tree, options, label_of);
+        for (position, label) in diagram.nodes {
+            chart.push_str(&format!("{}{}n{}[{}]\n", indent, prefix, position, quote(&label)));
+        }
+        for (parent, child, label) in diagram.edges {
+            match label {
+                Some(label) => chart.push_str(&format!("{}{}n{} -->|{}| {}n{}\n", indent, prefix, parent, quote(&label), prefix, child)),
+                None => chart.push_str(&format!("{}{}n{} --> {}n{}\n", indent, prefix, parent, prefix, child))
+            }
+        }
+        if cluster {
+            chart.push_str("    end\n");
+        }
+    }
+    chart
+}
+
+/// Quote a Mermaid string, using entity codes for quotes.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "#quot;").replace('\n', "<br>"))
+}
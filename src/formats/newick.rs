@@ -0,0 +1,366 @@
+//! Newick format.
+//!
+//! Each Newick tree, ended by `;`, is converted into a TREF tree. Labels become the node contents, and unnamed nodes get the content `_`. Labels made only of underscores get one more, so a node labelled `_` gets the content `__`.
+//!
+//! When a tree has branch lengths, every node content has the form `length:label`, as expected by [`WeightedEdgeNode`]. Nodes without a branch length, like the root, get length `0`. Trees without branch lengths just use labels as contents.
+//!
+//! ```
+//! use tref::{Tree, RawNode, NodeContent, formats::newick, dialects::WeightedEdgeNode};
+//!
+//! let tree: Tree<WeightedEdgeNode> = newick::read_tree("(A:0.1,'Homo sapiens':0.2)root;").unwrap();
+//! let child = tree.get_node_content(2).unwrap();
+//! assert_eq!(child.get_weight(), 0.2);
+//! assert_eq!(child.get_name(), "Homo sapiens");
+//! assert_eq!(newick::write_weighted_tree(&tree), "(A:0.1,'Homo sapiens':0.2)root;");
+//!
+//! let tree: Tree<RawNode> = newick::read_tree("((A,B),C);").unwrap();
+//! assert_eq!(tree.get_node_content(0).unwrap().get_val(), "_");
+//! assert_eq!(newick::write_tree(&tree), "((A,B),C);");
+//! ```
+//!
+//! Unquoted labels are kept as they are, underscores are not converted into spaces. Comments in brackets are ignored.
+
+use std::iter::Peekable;
+use std::str::Chars;
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::dialects::WeightedEdgeNode;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a Newick string with one tree.
+///
+/// # Arguments
+///
+/// * `newick` - Newick string.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree or an [`ImportTreeError`].
+///
+pub fn read_tree<T: TryNodeContent>(newick: &str) -> Result<Tree<T>, ImportTreeError> {
+    let mut reader = NewickReader::new(newick);
+    let tree = match reader.read_tree()? {
+        Some(root) => build_tree("tree_1", &root)?,
+        None => return Err(ImportTreeError::new("No tree found", None))
+    };
+    reader.skip_blanks()?;
+    if reader.peek().is_some() {
+        return Err(ImportTreeError::new("Expected only one tree", Some(reader.line)));
+    }
+    Ok(tree)
+}
+
+/// Read a Newick string with any number of trees.
+///
+/// Trees are named `tree_1`, `tree_2`, etc., in the order they appear.
+///
+/// # Arguments
+///
+/// * `newick` - Newick string.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(newick: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut reader = NewickReader::new(newick);
+    let mut forest = Forest::new();
+    let mut num_trees = 0;
+    while let Some(root) = reader.read_tree()? {
+        num_trees += 1;
+        let tree_id = format!("tree_{}", num_trees);
+        let tree = build_tree(&tree_id, &root)?;
+        forest.add_tree(&tree_id, tree);
+    }
+    Ok(forest)
+}
+
+/// Write a tree in Newick format, without branch lengths.
+///
+/// Newick can't represent empty trees: they are written as `;`, that reads back as a tree with an unnamed root.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+///
+/// # Return
+///
+/// * Newick string, ended by `;`.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>) -> String {
+    let mut newick = String::new();
+    if tree.get_nodes_len() > 0 {
+        write_node(tree, 0, &mut newick, &|content: &T| (content.gen_content(), None));
+    }
+    newick.push(';');
+    newick
+}
+
+/// Write a weighted tree in Newick format, using weights as branch lengths.
+///
+/// The root length is omitted when it is `0`. Empty trees are written as `;`, like in [`write_tree()`].
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+///
+/// # Return
+///
+/// * Newick string, ended by `;`.
+///
+pub fn write_weighted_tree(tree: &Tree<WeightedEdgeNode>) -> String {
+    let mut newick = String::new();
+    if tree.get_nodes_len() > 0 {
+        write_node(tree, 0, &mut newick, &|content: &WeightedEdgeNode| (String::from(content.get_name()), Some(content.get_weight())));
+    }
+    newick.push(';');
+    newick
+}
+
+/// Write a forest in Newick format, without branch lengths.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Newick string, with one tree per line. Tree IDs are lost, and empty trees are skipped.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    let mut newick = String::new();
+    for tree_id in sorted_tree_ids(forest) {
+        if let Some(tree) = forest.get_tree(tree_id).filter(|t| t.get_nodes_len() > 0) {
+            newick.push_str(&write_tree(tree));
+            newick.push('\n');
+        }
+    }
+    newick
+}
+
+/// Write a weighted forest in Newick format, using weights as branch lengths.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Newick string, with one tree per line. Tree IDs are lost, and empty trees are skipped.
+///
+pub fn write_weighted_forest(forest: &Forest<WeightedEdgeNode>) -> String {
+    let mut newick = String::new();
+    for tree_id in sorted_tree_ids(forest) {
+        if let Some(tree) = forest.get_tree(tree_id).filter(|t| t.get_nodes_len() > 0) {
+            newick.push_str(&write_weighted_tree(tree));
+            newick.push('\n');
+        }
+    }
+    newick
+}
+
+/// Step of writing a node, to write deep trees without recursion.
+enum Step {
+    Open(usize),
+    Separator,
+    Close(usize)
+}
+
+fn write_node<T: NodeContent>(tree: &Tree<T>, position: usize, newick: &mut String, label_of: &dyn Fn(&T) -> (String, Option<f64>)) {
+    let mut steps = vec!(Step::Open(position));
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Open(position) => {
+                let children = children(tree, position);
+                if children.is_empty() {
+                    write_label(tree, position, newick, label_of);
+                    continue;
+                }
+                newick.push('(');
+                steps.push(Step::Close(position));
+                for (i, child) in children.into_iter().enumerate().rev() {
+                    steps.push(Step::Open(child));
+                    if i > 0 {
+                        steps.push(Step::Separator);
+                    }
+                }
+            },
+            Step::Separator => newick.push(','),
+            Step::Close(position) => {
+                newick.push(')');
+                write_label(tree, position, newick, label_of);
+            }
+        }
+    }
+}
+
+fn write_label<T: NodeContent>(tree: &Tree<T>, position: usize, newick: &mut String, label_of: &dyn Fn(&T) -> (String, Option<f64>)) {
+    if let Some(content) = tree.get_node_content(position) {
+        let (label, length) = label_of(content);
+        if let Some(label) = content_label(&label) {
+            newick.push_str(&quote_label(label));
+        }
+        match length {
+            Some(length) if position != 0 || length != 0.0 => newick.push_str(&format!(":{}", length)),
+            _ => {}
+        }
+    }
+}
+
+/// Quote a label if it contains special characters.
+pub(crate) fn quote_label(label: &str) -> String {
+    if label.chars().any(|c| c.is_whitespace() || "()[]':;,".contains(c)) {
+        format!("'{}'", label.replace('\'', "''"))
+    }
+    else {
+        String::from(label)
+    }
+}
+
+/// Reader of Newick trees, also used to read the Newick parts of other formats.
+pub(crate) struct NewickReader<'a> {
+    chars: Peekable<Chars<'a>>,
+    pub line: usize
+}
+
+impl<'a> NewickReader<'a> {
+    pub fn new(newick: &'a str) -> Self {
+        Self {
+            chars: newick.chars().peekable(),
+            line: 0
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    pub fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Skip whitespaces and comments.
+    pub fn skip_blanks(&mut self) -> Result<(), ImportTreeError> {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.next();
+            }
+            else if c == '[' {
+                let line = self.line;
+                loop {
+                    match self.next() {
+                        Some(']') => break,
+                        Some(_) => {},
+                        None => return Err(ImportTreeError::new("Unclosed comment", Some(line)))
+                    }
+                }
+            }
+            else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read next tree, or None if there are no more trees.
+    pub fn read_tree(&mut self) -> Result<Option<Branch>, ImportTreeError> {
+        self.skip_blanks()?;
+        if self.peek().is_none() {
+            return Ok(None);
+        }
+        let root = self.read_node()?;
+        self.skip_blanks()?;
+        match self.next() {
+            Some(';') => Ok(Some(root.into_tree_branch())),
+            Some(c) => Err(ImportTreeError::new(&format!("Unexpected `{}`, expected `;`", c), Some(self.line))),
+            None => Err(ImportTreeError::new("Missing `;` at the end of the tree", Some(self.line)))
+        }
+    }
+
+    pub fn read_node(&mut self) -> Result<PhyloNode, ImportTreeError> {
+        // Children read so far of each node whose `(` is still open
+        let mut open: Vec<Vec<PhyloNode>> = vec!();
+        loop {
+            self.skip_blanks()?;
+            if self.peek() == Some('(') {
+                self.next();
+                open.push(vec!());
+                continue;
+            }
+            let mut node = self.read_node_end(vec!())?;
+            loop {
+                match open.last_mut() {
+                    Some(siblings) => siblings.push(node),
+                    None => return Ok(node)
+                }
+                self.skip_blanks()?;
+                match self.next() {
+                    Some(',') => break,
+                    Some(')') => {
+                        let children = open.pop().unwrap_or_default();
+                        node = self.read_node_end(children)?;
+                    },
+                    Some(c) => return Err(ImportTreeError::new(&format!("Unexpected `{}`, expected `,` or `)`", c), Some(self.line))),
+                    None => return Err(ImportTreeError::new("Unclosed `(`", Some(self.line)))
+                }
+            }
+        }
+    }
+
+    /// Read the label and the branch length of a node, after its children.
+    fn read_node_end(&mut self, children: Vec<PhyloNode>) -> Result<PhyloNode, ImportTreeError> {
+        self.skip_blanks()?;
+        let mut node = PhyloNode::new(self.line);
+        node.children = children;
+        node.label = self.read_label()?;
+        self.skip_blanks()?;
+        if self.peek() == Some(':') {
+            self.next();
+            self.skip_blanks()?;
+            let text = self.read_unquoted();
+            if text.parse::<f64>().is_err() {
+                return Err(ImportTreeError::new(&format!("Invalid branch length `{}`", text), Some(self.line)));
+            }
+            node.length = Some(text);
+        }
+        Ok(node)
+    }
+
+    pub fn read_label(&mut self) -> Result<String, ImportTreeError> {
+        if self.peek() != Some('\'') {
+            return Ok(self.read_unquoted());
+        }
+        let line = self.line;
+        self.next();
+        let mut label = String::new();
+        loop {
+            match self.next() {
+                Some('\'') if self.peek() == Some('\'') => {
+                    self.next();
+                    label.push('\'');
+                },
+                Some('\'') => return Ok(label),
+                Some(c) => label.push(c),
+                None => return Err(ImportTreeError::new("Unclosed quoted label", Some(line)))
+            }
+        }
+    }
+
+    pub fn read_unquoted(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || "()[]':;,".contains(c) {
+                break;
+            }
+            text.push(c);
+            self.next();
+        }
+        text
+    }
+}
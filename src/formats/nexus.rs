@@ -0,0 +1,177 @@
+//! Nexus format.
+//!
+//! Only the `TREES` block is used, other blocks are ignored. Each `TREE` command becomes a TREF tree named after it, and its Newick description is converted like in the [`newick`][`super::newick`] module, applying the `TRANSLATE` table to the labels.
+//!
+//! ```
+//! use tref::{Forest, formats::nexus, dialects::WeightedEdgeNode};
+//!
+//! let nexus =
+//! "#NEXUS\n\
+//! BEGIN TREES;\n\
+//!     TRANSLATE 1 Homo_sapiens, 2 Pan_paniscus;\n\
+//!     TREE primates = [&R] (1:0.1,2:0.2);\n\
+//! END;\n";
+//!
+//! let forest: Forest<WeightedEdgeNode> = nexus::read_forest(nexus).unwrap();
+//! let tree = forest.get_tree("primates").unwrap();
+//! assert_eq!(tree.get_node_content(1).unwrap().get_name(), "Homo_sapiens");
+//! assert_eq!(nexus::write_weighted_forest(&forest), "#NEXUS\nBEGIN TREES;\n\tTREE primates = (Homo_sapiens:0.1,Pan_paniscus:0.2);\nEND;\n");
+//! ```
+
+use std::collections::HashMap as Map;
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::formats::newick::*;
+use crate::dialects::WeightedEdgeNode;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a Nexus document.
+///
+/// # Arguments
+///
+/// * `nexus` - Nexus document.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(nexus: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut reader = NewickReader::new(nexus);
+    let mut forest = Forest::new();
+    let mut translate = Map::new();
+    let mut in_trees = false;
+    loop {
+        reader.skip_blanks()?;
+        let line = reader.line;
+        let command = reader.read_label()?.to_lowercase();
+        match command.as_str() {
+            "" if reader.peek().is_none() => break,
+            "#nexus" => {},
+            "begin" => {
+                reader.skip_blanks()?;
+                in_trees = reader.read_label()?.eq_ignore_ascii_case("trees");
+                expect_end(&mut reader)?;
+            },
+            "end" | "endblock" => {
+                in_trees = false;
+                expect_end(&mut reader)?;
+            },
+            "translate" if in_trees => {
+                loop {
+                    reader.skip_blanks()?;
+                    let key = reader.read_label()?;
+                    reader.skip_blanks()?;
+                    let label = reader.read_label()?;
+                    reader.skip_blanks()?;
+                    translate.insert(key, label);
+                    match reader.next() {
+                        Some(',') => continue,
+                        Some(';') => break,
+                        _ => return Err(ImportTreeError::new("Invalid TRANSLATE command", Some(line)))
+                    }
+                }
+            },
+            "tree" | "utree" if in_trees => {
+                reader.skip_blanks()?;
+                if reader.peek() == Some('*') {
+                    reader.next();
+                    reader.skip_blanks()?;
+                }
+                let tree_id = reader.read_label()?;
+                reader.skip_blanks()?;
+                if tree_id.is_empty() || reader.next() != Some('=') {
+                    return Err(ImportTreeError::new("Invalid TREE command, expected `TREE name = newick;`", Some(line)));
+                }
+                let mut root = reader.read_node()?;
+                translate_labels(&mut root, &translate);
+                expect_end(&mut reader)?;
+                let tree = build_tree(&tree_id, &root.into_tree_branch())?;
+                forest.add_tree(&tree_id, tree);
+            },
+            _ => skip_command(&mut reader)?
+        }
+    }
+    if forest.iter().next().is_none() {
+        return Err(ImportTreeError::new("No TREES block found", None));
+    }
+    Ok(forest)
+}
+
+/// Write a forest as a Nexus document, without branch lengths.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Nexus document with a `TREES` block. Empty trees are skipped, because Newick can't represent them.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    write_trees(forest, write_tree)
+}
+
+/// Write a weighted forest as a Nexus document, using weights as branch lengths.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Nexus document with a `TREES` block. Empty trees are skipped, because Newick can't represent them.
+///
+pub fn write_weighted_forest(forest: &Forest<WeightedEdgeNode>) -> String {
+    write_trees(forest, write_weighted_tree)
+}
+
+fn write_trees<T: NodeContent>(forest: &Forest<T>, write: fn(&Tree<T>) -> String) -> String {
+    let mut nexus = String::from("#NEXUS\nBEGIN TREES;\n");
+    for tree_id in sorted_tree_ids(forest) {
+        if let Some(tree) = forest.get_tree(tree_id).filter(|t| t.get_nodes_len() > 0) {
+            nexus.push_str(&format!("\tTREE {} = {}\n", quote_label(tree_id), write(tree)));
+        }
+    }
+    nexus.push_str("END;\n");
+    nexus
+}
+
+fn translate_labels(root: &mut PhyloNode, translate: &Map<String, String>) {
+    let mut stack = vec!(root);
+    while let Some(node) = stack.pop() {
+        if let Some(label) = translate.get(&node.label) {
+            node.label = label.clone();
+        }
+        stack.extend(node.children.iter_mut());
+    }
+}
+
+/// Check that a command ends here.
+fn expect_end(reader: &mut NewickReader) -> Result<(), ImportTreeError> {
+    reader.skip_blanks()?;
+    match reader.next() {
+        Some(';') => Ok(()),
+        _ => Err(ImportTreeError::new("Expected `;` at the end of the command", Some(reader.line)))
+    }
+}
+
+/// Skip an unknown command.
+fn skip_command(reader: &mut NewickReader) -> Result<(), ImportTreeError> {
+    loop {
+        reader.skip_blanks()?;
+        match reader.peek() {
+            Some(';') | None => {
+                reader.next();
+                return Ok(());
+            },
+            Some('\'') => {
+                reader.read_label()?;
+            },
+            Some(_) => {
+                reader.next();
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Org-mode outlines.
+//!
+//! Each tree is a first level heading with its tree ID, and the nodes are the headings below it, with one more star for each level:
+//!
+//! ```org
+//! * my_tree
+//! ** root_node
+//! *** child_1
+//! **** child_1_1
+//! *** child_2
+//! ```
+//!
+//! Other lines, like text or settings, are ignored.
+//!
+//! ```
+//! use tref::{Forest, RawNode, formats::org};
+//!
+//! let text = "* my_tree\n** root_node\n*** child_1\n**** child_1_1\n*** child_2\n";
+//! let forest: Forest<RawNode> = org::read_forest(text).unwrap();
+//! assert_eq!(forest.get_tree("my_tree").unwrap().get_nodes_len(), 4);
+//! assert_eq!(org::write_forest(&forest), text);
+//! ```
+
+use socarel::{Forest, NodeContent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a forest from an Org-mode outline.
+///
+/// # Arguments
+///
+/// * `org` - Org-mode document.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(org: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut outline = OutlineBuilder::new();
+    for (line, text) in org.lines().enumerate() {
+        let stars = text.len() - text.trim_start_matches('*').len();
+        if stars == 0 || !text[stars..].starts_with(' ') {
+            continue;
+        }
+        let content = text[stars..].trim();
+        if stars == 1 {
+            outline.start_tree(content);
+        }
+        else {
+            outline.add_node(content, stars - 1, line)?;
+        }
+    }
+    Ok(outline.finish())
+}
+
+/// Write a forest as an Org-mode outline.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Org-mode document, with trees sorted by ID.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    let mut org = String::new();
+    for (tree_id, tree) in sorted_trees(forest) {
+        org.push_str(&format!("* {}\n", tree_id));
+        for position in pre_order(tree) {
+            let node = &tree.get_nodes_ref()[position];
+            org.push_str(&format!("{} {}\n", "*".repeat(node.get_level() + 1), node.get_content_ref().gen_content()));
+        }
+    }
+    org
+}
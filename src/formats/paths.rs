@@ -0,0 +1,94 @@
+//! Path lists, like `a/b/c`.
+//!
+//! Each line is a path from a root node, with node contents separated by a separator. Paths that share a prefix share the nodes, and children keep the order in which they first appear. Empty lines and empty path components are ignored.
+//!
+//! ```
+//! use tref::{Tree, RawNode, formats::paths};
+//!
+//! let text = "root/child_1/child_1_1\nroot/child_1/child_1_2\nroot/child_2\n";
+//! let tree: Tree<RawNode> = paths::read_tree(text, "/").unwrap();
+//! assert_eq!(tree.get_nodes_len(), 5);
+//! assert_eq!(paths::write_tree(&tree, "/"), text);
+//! ```
+//!
+//! Node contents that contain the separator will be split when read back.
+
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a tree from a path list.
+///
+/// # Arguments
+///
+/// * `text` - Path list, one path per line.
+/// * `separator` - Separator of the path components.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree or an [`ImportTreeError`]. All paths must start with the same root node.
+///
+pub fn read_tree<T: TryNodeContent>(text: &str, separator: &str) -> Result<Tree<T>, ImportTreeError> {
+    single_root(&read_branches(text, separator)?)
+}
+
+/// Read a forest from a path list.
+///
+/// # Arguments
+///
+/// * `text` - Path list, one path per line.
+/// * `separator` - Separator of the path components.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`]. There is one tree for each root node, using the root content as tree ID.
+///
+pub fn read_forest<T: TryNodeContent>(text: &str, separator: &str) -> Result<Forest<T>, ImportTreeError> {
+    forest_by_root(&read_branches(text, separator)?)
+}
+
+/// Write a tree as a path list.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `separator` - Separator of the path components.
+///
+/// # Return
+///
+/// * Path list, with the path of each leaf node.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, separator: &str) -> String {
+    let mut text = String::new();
+    for position in pre_order(tree) {
+        if children(tree, position).is_empty() {
+            let path: Vec<String> = position_path(tree, position).into_iter().filter_map(|p| tree.get_node_content(p).map(|c| c.gen_content())).collect();
+            text.push_str(&path.join(separator));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+fn read_branches(text: &str, separator: &str) -> Result<Vec<Branch>, ImportTreeError> {
+    if separator.is_empty() {
+        return Err(ImportTreeError::new("Empty path separator", None));
+    }
+    let mut roots: Vec<Branch> = vec!();
+    for (line, path) in text.lines().enumerate() {
+        let mut siblings = &mut roots;
+        for component in path.split(separator).map(|c| c.trim()).filter(|c| !c.is_empty()) {
+            let index = match siblings.iter().position(|b| b.content == component) {
+                Some(index) => index,
+                None => {
+                    siblings.push(Branch::new(component, Some(line)));
+                    siblings.len() - 1
+                }
+            };
+            siblings = &mut siblings[index].children;
+        }
+    }
+    Ok(roots)
+}
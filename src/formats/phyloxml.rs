@@ -0,0 +1,203 @@
+//! PhyloXML format. Requires the `xml` feature.
+//!
+//! Each `<phylogeny>` becomes a TREF tree named after its `<name>`, or `tree_1`, `tree_2`, etc. if it has no name. A phylogeny without clades becomes an empty tree, and empty trees are written that way. Its `<clade>` hierarchy is converted like in the [`newick`][`super::newick`] module: clade names are the node contents, and branch lengths, given as `<branch_length>` elements or `branch_length` attributes, turn contents into `length:name`. Other clade information is ignored.
+//!
+//! ```
+//! use tref::{Forest, formats::phyloxml, dialects::WeightedEdgeNode};
+//!
+//! let xml = r#"<phyloxml xmlns="http://www.phyloxml.org">
+//!   <phylogeny rooted="true">
+//!     <name>primates</name>
+//!     <clade>
+//!       <clade branch_length="0.1"><name>Homo sapiens</name></clade>
+//!       <clade><name>Pan paniscus</name><branch_length>0.2</branch_length></clade>
+//!     </clade>
+//!   </phylogeny>
+//! </phyloxml>"#;
+//!
+//! let forest: Forest<WeightedEdgeNode> = phyloxml::read_forest(xml).unwrap();
+//! let tree = forest.get_tree("primates").unwrap();
+//! assert_eq!(tree.get_node_content(2).unwrap().get_weight(), 0.2);
+//! assert!(phyloxml::write_weighted_forest(&forest).contains("<branch_length>0.2</branch_length>"));
+//! ```
+
+use socarel::{Forest, Tree, NodeContent};
+use xml_rs::common::Position;
+use xml_rs::reader::{EventReader, XmlEvent};
+use crate::formats::*;
+use crate::dialects::WeightedEdgeNode;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a PhyloXML document.
+///
+/// # Arguments
+///
+/// * `xml` - PhyloXML document.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`].
+///
+pub fn read_forest<T: TryNodeContent>(xml: &str) -> Result<Forest<T>, ImportTreeError> {
+    let mut reader = EventReader::new(xml.as_bytes());
+    let mut forest = Forest::new();
+    let mut num_trees = 0;
+    let mut elements: Vec<String> = vec!();
+    let mut clades: Vec<PhyloNode> = vec!();
+    let mut tree_name = String::new();
+    let mut root: Option<PhyloNode> = None;
+    loop {
+        let event = reader.next().map_err(|e| ImportTreeError::new(e.msg(), Some(e.position().row as usize)))?;
+        let line = reader.position().row as usize;
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                match name.local_name.as_str() {
+                    "phylogeny" => {
+                        tree_name.clear();
+                        root = None;
+                    },
+                    "clade" => {
+                        let mut clade = PhyloNode::new(line);
+                        if let Some(attr) = attributes.iter().find(|a| a.name.local_name == "branch_length") {
+                            clade.length = Some(branch_length(&attr.value, line)?);
+                        }
+                        clades.push(clade);
+                    },
+                    _ => {}
+                }
+                elements.push(name.local_name);
+            },
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                let parent = elements.len().checked_sub(2).and_then(|i| elements.get(i)).map(|p| p.as_str());
+                match (elements.last().map(|e| e.as_str()), parent, clades.last_mut()) {
+                    (Some("name"), Some("phylogeny"), _) => tree_name.push_str(text.trim()),
+                    (Some("name"), Some("clade"), Some(clade)) => clade.label.push_str(text.trim()),
+                    (Some("branch_length"), Some("clade"), Some(clade)) => clade.length = Some(branch_length(&text, line)?),
+                    _ => {}
+                }
+            },
+            XmlEvent::EndElement { name } => {
+                elements.pop();
+                match name.local_name.as_str() {
+                    "clade" => {
+                        if let Some(clade) = clades.pop() {
+                            if let Some(parent) = clades.last_mut() {
+                                parent.children.push(clade);
+                            }
+                            else if root.is_some() {
+                                return Err(ImportTreeError::new("Multiple root clades in the same phylogeny", Some(clade.line)));
+                            }
+                            else {
+                                root = Some(clade);
+                            }
+                        }
+                    },
+                    "phylogeny" => {
+                        num_trees += 1;
+                        let tree_id = if tree_name.is_empty() { format!("tree_{}", num_trees) } else { tree_name.clone() };
+                        if forest.get_tree(&tree_id).is_some() {
+                            return Err(ImportTreeError::new(&format!("Duplicated phylogeny `{}`", tree_id), Some(line)));
+                        }
+                        match root.take() {
+                            Some(root) => forest.add_tree(&tree_id, build_tree(&tree_id, &root.into_tree_branch())?),
+                            None => forest.add_tree(&tree_id, Tree::new())
+                        }
+                    },
+                    _ => {}
+                }
+            },
+            XmlEvent::EndDocument => break,
+            _ => {}
+        }
+    }
+    Ok(forest)
+}
+
+/// Write a forest as a PhyloXML document, without branch lengths.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * PhyloXML document, with one phylogeny per tree.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    write_phylogenies(forest, &|content: &T| (content.gen_content(), None))
+}
+
+/// Write a weighted forest as a PhyloXML document, using weights as branch lengths.
+///
+/// The root branch length is omitted when it is `0`.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * PhyloXML document, with one phylogeny per tree.
+///
+pub fn write_weighted_forest(forest: &Forest<WeightedEdgeNode>) -> String {
+    write_phylogenies(forest, &|content: &WeightedEdgeNode| (String::from(content.get_name()), Some(content.get_weight())))
+}
+
+fn write_phylogenies<T: NodeContent>(forest: &Forest<T>, label_of: &dyn Fn(&T) -> (String, Option<f64>)) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<phyloxml xmlns=\"http://www.phyloxml.org\">\n");
+    for tree_id in sorted_tree_ids(forest) {
+        if let Some(tree) = forest.get_tree(tree_id) {
+            xml.push_str("  <phylogeny rooted=\"true\">\n");
+            xml.push_str(&format!("    <name>{}</name>\n", xml_escape(tree_id)));
+            if tree.get_nodes_len() > 0 {
+                write_clade(tree, 0, 2, &mut xml, label_of);
+            }
+            xml.push_str("  </phylogeny>\n");
+        }
+    }
+    xml.push_str("</phyloxml>\n");
+    xml
+}
+
+/// Step of writing a clade, to write deep trees without recursion.
+enum Step {
+    Open(usize, usize),
+    Close(usize)
+}
+
+fn write_clade<T: NodeContent>(tree: &Tree<T>, position: usize, depth: usize, xml: &mut String, label_of: &dyn Fn(&T) -> (String, Option<f64>)) {
+    let mut steps = vec!(Step::Open(position, depth));
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Open(position, depth) => {
+                let indent = "  ".repeat(depth);
+                xml.push_str(&format!("{}<clade>\n", indent));
+                if let Some(content) = tree.get_node_content(position) {
+                    let (label, length) = label_of(content);
+                    if let Some(label) = content_label(&label) {
+                        xml.push_str(&format!("{}  <name>{}</name>\n", indent, xml_escape(label)));
+                    }
+                    match length {
+                        Some(length) if position != 0 || length != 0.0 => xml.push_str(&format!("{}  <branch_length>{}</branch_length>\n", indent, length)),
+                        _ => {}
+                    }
+                }
+                steps.push(Step::Close(depth));
+                for child in children(tree, position).into_iter().rev() {
+                    steps.push(Step::Open(child, depth + 1));
+                }
+            },
+            Step::Close(depth) => xml.push_str(&format!("{}</clade>\n", "  ".repeat(depth)))
+        }
+    }
+}
+
+fn branch_length(text: &str, line: usize) -> Result<String, ImportTreeError> {
+    let text = text.trim();
+    match text.parse::<f64>() {
+        Ok(_) => Ok(String::from(text)),
+        Err(_) => Err(ImportTreeError::new(&format!("Invalid branch length `{}`", text), Some(line)))
+    }
+}
@@ -0,0 +1,257 @@
+//! S-expressions, like `(root_node (child_1 child_1_1) child_2)`.
+//!
+//! A list is a node with children: its first element is the node content, and the rest are its children. An atom alone is a leaf node, so `child_2` and `(child_2)` are the same node. Atoms that contain whitespaces, parentheses, quotes or `;` are written between double quotes, with `\"` and `\\` escapes. Comments start with `;` and last until the end of the line.
+//!
+//! ```
+//! use tref::{Tree, RawNode, NodeContent, formats::sexp};
+//!
+//! let tree: Tree<RawNode> = sexp::read_tree("(root_node (child_1 child_1_1) \"child 2\")").unwrap();
+//! assert_eq!(tree.get_node_content(3).unwrap().get_val(), "child 2");
+//! assert_eq!(sexp::write_tree(&tree), "(root_node (child_1 child_1_1) \"child 2\")\n");
+//! ```
+
+use std::iter::Peekable;
+use std::str::Chars;
+use socarel::{Forest, Tree, NodeContent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Read a tree from an S-expression.
+///
+/// # Arguments
+///
+/// * `text` - One S-expression.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree or an [`ImportTreeError`].
+///
+pub fn read_tree<T: TryNodeContent>(text: &str) -> Result<Tree<T>, ImportTreeError> {
+    single_root(&read_branches(text)?)
+}
+
+/// Read a forest from a sequence of S-expressions.
+///
+/// # Arguments
+///
+/// * `text` - S-expressions, one per tree.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`]. There is one tree for each expression, using the root content as tree ID.
+///
+pub fn read_forest<T: TryNodeContent>(text: &str) -> Result<Forest<T>, ImportTreeError> {
+    forest_by_root(&read_branches(text)?)
+}
+
+/// Write a tree as an S-expression.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+///
+/// # Return
+///
+/// * S-expression, in one line.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>) -> String {
+    let mut text = String::new();
+    if tree.get_nodes_len() > 0 {
+        write_node(tree, 0, &mut text);
+        text.push('\n');
+    }
+    text
+}
+
+/// Write a forest as S-expressions.
+///
+/// Tree IDs are not written, trees read back are named after their root nodes. Empty trees can't be written, so they are skipped.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * S-expressions, one line per tree, sorted by tree ID.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>) -> String {
+    sorted_trees(forest).into_iter().map(|(_, tree)| write_tree(tree)).collect()
+}
+
+/// Step of writing a node, to write deep trees without recursion.
+enum Step {
+    Open(usize),
+    Separator,
+    Close
+}
+
+fn write_node<T: NodeContent>(tree: &Tree<T>, position: usize, text: &mut String) {
+    let mut steps = vec!(Step::Open(position));
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Open(position) => {
+                let atom = quote_atom(&tree.get_node_content(position).map(|c| c.gen_content()).unwrap_or_default());
+                let children = children(tree, position);
+                if children.is_empty() {
+                    text.push_str(&atom);
+                    continue;
+                }
+                text.push('(');
+                text.push_str(&atom);
+                steps.push(Step::Close);
+                for child in children.into_iter().rev() {
+                    steps.push(Step::Open(child));
+                    steps.push(Step::Separator);
+                }
+            },
+            Step::Separator => text.push(' '),
+            Step::Close => text.push(')')
+        }
+    }
+}
+
+fn quote_atom(atom: &str) -> String {
+    if atom.is_empty() || atom.chars().any(|c| c.is_whitespace() || "()\";\\".contains(c)) {
+        let mut quoted = String::from("\"");
+        for c in atom.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                _ => quoted.push(c)
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+    else {
+        String::from(atom)
+    }
+}
+
+fn read_branches(text: &str) -> Result<Vec<Branch>, ImportTreeError> {
+    let mut reader = SexpReader::new(text);
+    let mut roots = vec!();
+    loop {
+        reader.skip_blanks();
+        if reader.peek().is_none() {
+            return Ok(roots);
+        }
+        roots.push(reader.read_node()?);
+    }
+}
+
+struct SexpReader<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize
+}
+
+impl<'a> SexpReader<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            chars: text.chars().peekable(),
+            line: 0
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Skip whitespaces and comments.
+    fn skip_blanks(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.next();
+            }
+            else if c == ';' {
+                while !matches!(self.next(), Some('\n') | None) {}
+            }
+            else {
+                break;
+            }
+        }
+    }
+
+    fn read_node(&mut self) -> Result<Branch, ImportTreeError> {
+        // Lists still open, with the children read so far
+        let mut open: Vec<Branch> = vec!();
+        loop {
+            self.skip_blanks();
+            let line = self.line;
+            let branch = match self.peek() {
+                Some('(') => {
+                    self.next();
+                    self.skip_blanks();
+                    if self.peek() == Some('(') {
+                        return Err(ImportTreeError::new("The first element of a list must be an atom", Some(self.line)));
+                    }
+                    if self.peek() == Some(')') {
+                        return Err(ImportTreeError::new("Empty list", Some(self.line)));
+                    }
+                    open.push(Branch::new(&self.read_atom()?, Some(line)));
+                    continue;
+                },
+                Some(')') => match open.pop() {
+                    Some(branch) => {
+                        self.next();
+                        branch
+                    },
+                    None => return Err(ImportTreeError::new("Unexpected `)`", Some(line)))
+                },
+                None if !open.is_empty() => {
+                    let line = open.last().and_then(|branch| branch.line);
+                    return Err(ImportTreeError::new("Unclosed `(`", line));
+                },
+                _ => Branch::new(&self.read_atom()?, Some(line))
+            };
+            match open.last_mut() {
+                Some(parent) => parent.children.push(branch),
+                None => return Ok(branch)
+            }
+        }
+    }
+
+    fn read_atom(&mut self) -> Result<String, ImportTreeError> {
+        let mut atom = String::new();
+        if self.peek() != Some('"') {
+            while let Some(c) = self.peek() {
+                if c.is_whitespace() || "()\";".contains(c) {
+                    break;
+                }
+                atom.push(c);
+                self.next();
+            }
+            return Ok(atom);
+        }
+        let line = self.line;
+        self.next();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(atom),
+                Some('\\') => match self.next() {
+                    Some('"') => atom.push('"'),
+                    Some('\\') => atom.push('\\'),
+                    Some('n') => atom.push('\n'),
+                    Some('t') => atom.push('\t'),
+                    Some(c) => return Err(ImportTreeError::new(&format!("Invalid escape `\\{}`", c), Some(self.line))),
+                    None => return Err(ImportTreeError::new("Unclosed string", Some(line)))
+                },
+                Some(c) => atom.push(c),
+                None => return Err(ImportTreeError::new("Unclosed string", Some(line)))
+            }
+        }
+    }
+}
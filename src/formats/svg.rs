@@ -0,0 +1,286 @@
+//! SVG rendering.
+//!
+//! Trees are laid out with a tidy tree algorithm, in the style of Reingold–Tilford: each subtree is placed as close as possible to its left siblings without overlapping them, and parents are centered over their children. The SVG is generated directly, without external tools.
+//!
+//! ```
+//! use tref::{tref, Tree, RawNode, formats::{svg, SvgOptions, NodeShape, Direction}};
+//!
+//! let tree: Tree<RawNode> = tref!("root_node" => { "child_1" => { "child_1_1" }, "child_2" });
+//! let options = SvgOptions::new()
+//!     .shape(NodeShape::Ellipse)
+//!     .direction(Direction::LeftRight)
+//!     .font_size(12.0);
+//! let image = svg::write_tree(&tree, &options);
+//! assert!(image.starts_with("<svg"));
+//! ```
+//!
+//! Node sizes are estimated from the length of their contents, assuming an average character width of 0.6 times the font size.
+
+use socarel::{Tree, NodeContent};
+use crate::formats::*;
+use crate::walk::*;
+
+/// Shape of the nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeShape {
+    Rectangle,
+    RoundedRectangle,
+    Ellipse,
+    Circle
+}
+
+/// Options to render trees as SVG.
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    shape: NodeShape,
+    direction: Direction,
+    font_size: f64,
+    sibling_spacing: f64,
+    level_spacing: f64
+}
+
+impl SvgOptions {
+    /// Create default options: rounded rectangles, top-down direction, 14 pixels font, 20 pixels between siblings and 40 pixels between levels.
+    pub fn new() -> Self {
+        Self {
+            shape: NodeShape::RoundedRectangle,
+            direction: Direction::TopDown,
+            font_size: 14.0,
+            sibling_spacing: 20.0,
+            level_spacing: 40.0
+        }
+    }
+
+    /// Set the shape of the nodes.
+    pub fn shape(mut self, shape: NodeShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Set the tree direction.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Set the font size, in pixels.
+    pub fn font_size(mut self, font_size: f64) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Set the minimum space between nodes of the same level, in pixels.
+    pub fn sibling_spacing(mut self, spacing: f64) -> Self {
+        self.sibling_spacing = spacing;
+        self
+    }
+
+    /// Set the space between levels, in pixels.
+    pub fn level_spacing(mut self, spacing: f64) -> Self {
+        self.level_spacing = spacing;
+        self
+    }
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write a tree as an SVG image.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - SVG options.
+///
+/// # Return
+///
+/// * SVG document.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, options: &SvgOptions) -> String {
+    let layout = TreeLayout::new(tree, options);
+    let margin = options.font_size;
+    let horizontal = matches!(options.direction, Direction::LeftRight | Direction::RightLeft);
+    let (width, height) = if horizontal {
+        (layout.depth_size + 2.0 * margin, layout.breadth_size + 2.0 * margin)
+    }
+    else {
+        (layout.breadth_size + 2.0 * margin, layout.depth_size + 2.0 * margin)
+    };
+    // Node centers, in image coordinates
+    let center = |position: usize| -> (f64, f64) {
+        let (breadth, depth) = (layout.breadth[position], layout.depth[position]);
+        match options.direction {
+            Direction::TopDown => (margin + breadth, margin + depth),
+            Direction::BottomUp => (margin + breadth, margin + layout.depth_size - depth),
+            Direction::LeftRight => (margin + depth, margin + breadth),
+            Direction::RightLeft => (margin + layout.depth_size - depth, margin + breadth)
+        }
+    };
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"{f}\">\n",
+        w = num(width), h = num(height), f = num(options.font_size)
+    );
+    // Edges first, so nodes are drawn over them
+    svg.push_str("  <g stroke=\"black\">\n");
+    for position in layout.order.iter() {
+        if let Some(parent) = tree.get_nodes_ref()[*position].get_parent_position() {
+            let (x1, y1) = center(parent);
+            let (x2, y2) = center(*position);
+            svg.push_str(&format!("    <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", num(x1), num(y1), num(x2), num(y2)));
+        }
+    }
+    svg.push_str("  </g>\n");
+    svg.push_str("  <g fill=\"white\" stroke=\"black\">\n");
+    for position in layout.order.iter() {
+        let (x, y) = center(*position);
+        let (w, h) = layout.sizes[*position];
+        let shape = match options.shape {
+            NodeShape::Rectangle => format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>", num(x - w / 2.0), num(y - h / 2.0), num(w), num(h)),
+            NodeShape::RoundedRectangle => format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>", num(x - w / 2.0), num(y - h / 2.0), num(w), num(h), num(h / 4.0)),
+            NodeShape::Ellipse => format!("<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"/>", num(x), num(y), num(w / 2.0), num(h / 2.0)),
+            NodeShape::Circle => format!("<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>", num(x), num(y), num(w / 2.0))
+        };
+        svg.push_str(&format!("    {}\n", shape));
+    }
+    svg.push_str("  </g>\n");
+    svg.push_str("  <g text-anchor=\"middle\" dominant-baseline=\"central\">\n");
+    for position in layout.order.iter() {
+        let (x, y) = center(*position);
+        let label = tree.get_node_content(*position).map(|c| c.gen_content()).unwrap_or_default();
+        svg.push_str(&format!("    <text x=\"{}\" y=\"{}\">{}</text>\n", num(x), num(y), xml_escape(&label)));
+    }
+    svg.push_str("  </g>\n</svg>\n");
+    svg
+}
+
+/// Format a coordinate with at most one decimal.
+fn num(n: f64) -> String {
+    let text = format!("{:.1}", n);
+    match text.strip_suffix(".0") {
+        Some(integer) => String::from(integer),
+        None => text
+    }
+}
+
+/// Position of each node, along the breadth axis (across siblings) and the depth axis (from the root).
+struct TreeLayout {
+    order: Vec<usize>,
+    sizes: Vec<(f64, f64)>,
+    breadth: Vec<f64>,
+    depth: Vec<f64>,
+    breadth_size: f64,
+    depth_size: f64
+}
+
+impl TreeLayout {
+    fn new<T: NodeContent>(tree: &Tree<T>, options: &SvgOptions) -> Self {
+        let num_nodes = tree.get_nodes_len();
+        let horizontal = matches!(options.direction, Direction::LeftRight | Direction::RightLeft);
+        let order = pre_order(tree);
+
+        // Node sizes, and their extent along each axis
+        let padding = options.font_size / 2.0;
+        let mut sizes = vec![(0.0, 0.0); num_nodes];
+        for position in order.iter() {
+            let chars = tree.get_node_content(*position).map(|c| c.gen_content().chars().count()).unwrap_or(0);
+            let w = chars as f64 * options.font_size * 0.6 + 2.0 * padding;
+            let h = options.font_size + 2.0 * padding;
+            sizes[*position] = match options.shape {
+                NodeShape::Circle => (w.max(h), w.max(h)),
+                NodeShape::Ellipse => (w * 1.2, h * 1.2),
+                _ => (w, h)
+            };
+        }
+        let extent = |position: usize| -> (f64, f64) {
+            let (w, h) = sizes[position];
+            if horizontal { (h, w) } else { (w, h) }
+        };
+
+        // Depth axis: each level is as deep as its deepest node
+        let mut levels = vec![0; num_nodes];
+        let mut level_sizes: Vec<f64> = vec!();
+        for position in order.iter() {
+            let level = tree.get_nodes_ref()[*position].get_parent_position().map(|p| levels[p] + 1).unwrap_or(0);
+            levels[*position] = level;
+            if level_sizes.len() <= level {
+                level_sizes.push(0.0);
+            }
+            level_sizes[level] = level_sizes[level].max(extent(*position).1);
+        }
+        let mut level_offsets = vec!();
+        let mut offset = 0.0;
+        for size in level_sizes.iter() {
+            level_offsets.push(offset + size / 2.0);
+            offset += size + options.level_spacing;
+        }
+        let depth_size = (offset - options.level_spacing).max(0.0);
+        let mut depth = vec![0.0; num_nodes];
+        for position in order.iter() {
+            depth[*position] = level_offsets[levels[*position]];
+        }
+
+        // Breadth axis: offsets relative to the parent, then absolute
+        let mut relative = vec![0.0; num_nodes];
+        let mut breadth = vec![0.0; num_nodes];
+        let mut breadth_size = 0.0;
+        if !order.is_empty() {
+            let contour = Self::place(tree, 0, &extent, options.sibling_spacing, &mut relative);
+            let left = contour.iter().map(|(l, _)| *l).fold(f64::INFINITY, f64::min);
+            let right = contour.iter().map(|(_, r)| *r).fold(f64::NEG_INFINITY, f64::max);
+            breadth_size = right - left;
+            for position in order.iter() {
+                breadth[*position] = match tree.get_nodes_ref()[*position].get_parent_position() {
+                    Some(parent) => breadth[parent] + relative[*position],
+                    None => -left
+                };
+            }
+        }
+
+        Self { order, sizes, breadth, depth, breadth_size, depth_size }
+    }
+
+    /// Place the children of a node relative to it, and get the contour of its subtree.
+    ///
+    /// The contour has the left and right limits of each level of the subtree, relative to the node center.
+    fn place<T: NodeContent>(tree: &Tree<T>, position: usize, extent: &dyn Fn(usize) -> (f64, f64), spacing: f64, relative: &mut Vec<f64>) -> Vec<(f64, f64)> {
+        let half = extent(position).0 / 2.0;
+        let mut children_contour: Vec<(f64, f64)> = vec!();
+        let children = children(tree, position);
+        let mut shifts = vec!();
+        for child in children.iter() {
+            let contour = Self::place(tree, *child, extent, spacing, relative);
+            // Move the child right until it doesn't overlap with its left siblings at any level
+            let shift = if shifts.is_empty() {
+                0.0
+            }
+            else {
+                children_contour.iter().zip(contour.iter()).map(|((_, right), (left, _))| right - left + spacing).fold(f64::NEG_INFINITY, f64::max)
+            };
+            for (level, (left, right)) in contour.into_iter().enumerate() {
+                if level < children_contour.len() {
+                    children_contour[level].1 = right + shift;
+                    children_contour[level].0 = children_contour[level].0.min(left + shift);
+                }
+                else {
+                    children_contour.push((left + shift, right + shift));
+                }
+            }
+            shifts.push(shift);
+        }
+        // Center the node over its first and last children
+        let middle = match (shifts.first(), shifts.last()) {
+            (Some(first), Some(last)) => (first + last) / 2.0,
+            _ => 0.0
+        };
+        for (child, shift) in children.iter().zip(shifts.iter()) {
+            relative[*child] = shift - middle;
+        }
+        let mut contour = vec!((-half, half));
+        contour.extend(children_contour.into_iter().map(|(left, right)| (left - middle, right - middle)));
+        contour
+    }
+}
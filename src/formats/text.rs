@@ -0,0 +1,139 @@
+//! Plain text rendering, like the Unix `tree` command.
+//!
+//! ```
+//! use tref::{tref, Tree, RawNode, formats::{text, TextOptions}};
+//!
+//! let tree: Tree<RawNode> = tref!("root_node" => { "child_1" => { "child_1_1", "child_1_2" }, "child_2" });
+//! assert_eq!(text::write_tree(&tree, &TextOptions::new()),
+//! "root_node\n\
+//! ├── child_1\n\
+//! │   ├── child_1_1\n\
+//! │   └── child_1_2\n\
+//! └── child_2\n");
+//! ```
+
+use socarel::{Forest, Tree, NodeContent};
+use crate::walk::*;
+
+/// Options to render trees as text.
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    ascii: bool,
+    positions: bool,
+    max_depth: Option<usize>,
+    max_width: Option<usize>
+}
+
+impl TextOptions {
+    /// Create default options: Unicode box-drawing characters, no positions and no limits.
+    pub fn new() -> Self {
+        Self {
+            ascii: false,
+            positions: false,
+            max_depth: None,
+            max_width: None
+        }
+    }
+
+    /// Set if only ASCII characters are used to draw the branches, like `|--`.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Set if the position of each node is shown after its content, like `child_1 (1)`.
+    pub fn positions(mut self, positions: bool) -> Self {
+        self.positions = positions;
+        self
+    }
+
+    /// Set the maximum depth, where the root node is depth 0. Deeper nodes are not shown.
+    pub fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the maximum number of characters of a node content. Longer contents are truncated with an ellipsis.
+    pub fn max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Write a tree as text.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - Text options.
+///
+/// # Return
+///
+/// * Text, one line per node.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, options: &TextOptions) -> String {
+    let mut text = String::new();
+    if tree.get_nodes_len() > 0 {
+        text.push_str(&node_label(tree, 0, options));
+        text.push('\n');
+        write_children(tree, 0, 1, "", options, &mut text);
+    }
+    text
+}
+
+/// Write a forest as text.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `options` - Text options.
+///
+/// # Return
+///
+/// * Text, with each tree preceded by its tree ID in brackets, sorted by tree ID and separated by empty lines.
+///
+pub fn write_forest<T: NodeContent>(forest: &Forest<T>, options: &TextOptions) -> String {
+    let trees: Vec<String> = sorted_trees(forest).into_iter().map(|(tree_id, tree)| format!("[{}]\n{}", tree_id, write_tree(tree, options))).collect();
+    trees.join("\n")
+}
+
+fn write_children<T: NodeContent>(tree: &Tree<T>, position: usize, depth: usize, prefix: &str, options: &TextOptions, text: &mut String) {
+    if options.max_depth.map(|max| depth > max).unwrap_or(false) {
+        return;
+    }
+    let (branch, last_branch, pipe) = if options.ascii { ("|-- ", "`-- ", "|   ") } else { ("├── ", "└── ", "│   ") };
+    let children = children(tree, position);
+    for (i, child) in children.iter().enumerate() {
+        let is_last = i + 1 == children.len();
+        text.push_str(prefix);
+        text.push_str(if is_last { last_branch } else { branch });
+        text.push_str(&node_label(tree, *child, options));
+        text.push('\n');
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { pipe });
+        write_children(tree, *child, depth + 1, &child_prefix, options, text);
+    }
+}
+
+fn node_label<T: NodeContent>(tree: &Tree<T>, position: usize, options: &TextOptions) -> String {
+    let content = tree.get_node_content(position).map(|c| c.gen_content()).unwrap_or_default();
+    // Contents can't contain new lines when parsed from TREF, but trees built in code can
+    let content = content.replace('\n', " ");
+    let mut label = match options.max_width {
+        Some(max) if content.chars().count() > max => {
+            let ellipsis = if options.ascii { "..." } else { "…" };
+            let keep = max.saturating_sub(ellipsis.chars().count());
+            content.chars().take(keep).collect::<String>() + ellipsis
+        },
+        _ => content
+    };
+    if options.positions {
+        label.push_str(&format!(" ({})", position));
+    }
+    label
+}
@@ -0,0 +1,248 @@
+//! XML element hierarchies. Requires the `xml` feature.
+//!
+//! Each element becomes a node, with the element name as content and its child elements as children. Namespace prefixes are ignored. Attributes and text are optional, enabled with [`XmlOptions`]:
+//!
+//! * Attributes become leaf nodes like `@name=value`, before the child elements.
+//! * Text becomes leaf nodes like `#text`, trimmed and with whitespaces and line breaks collapsed into single spaces, in the order it appears among the child elements. Consecutive text nodes are merged when read back.
+//!
+//! ```
+//! use tref::{Tree, RawNode, NodeContent, formats::xml::{self, XmlOptions}};
+//!
+//! let text = r#"<config version="2">
+//!   <server>example.com</server>
+//!   <debug/>
+//! </config>"#;
+//! let options = XmlOptions::new().attributes(true).text(true);
+//! let tree: Tree<RawNode> = xml::read_tree(text, &options).unwrap();
+//! assert_eq!(tree.get_node_content(1).unwrap().get_val(), "@version=2");
+//! assert_eq!(tree.get_node_content(3).unwrap().get_val(), "#example.com");
+//! assert!(xml::write_tree(&tree, &options).unwrap().contains("<server>example.com</server>"));
+//! ```
+
+use socarel::{Forest, Tree, NodeContent};
+use xml_rs::common::Position;
+use xml_rs::reader::{EventReader, XmlEvent};
+use crate::formats::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::walk::*;
+
+/// Options to convert XML documents.
+#[derive(Debug, Clone)]
+pub struct XmlOptions {
+    attributes: bool,
+    text: bool
+}
+
+impl XmlOptions {
+    /// Create default options: only elements.
+    pub fn new() -> Self {
+        Self {
+            attributes: false,
+            text: false
+        }
+    }
+
+    /// Set if attributes are converted from and to `@name=value` nodes.
+    pub fn attributes(mut self, attributes: bool) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Set if text is converted from and to `#text` nodes.
+    pub fn text(mut self, text: bool) -> Self {
+        self.text = text;
+        self
+    }
+}
+
+impl Default for XmlOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read a tree from an XML document.
+///
+/// # Arguments
+///
+/// * `xml` - XML document.
+/// * `options` - XML options.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree or an [`ImportTreeError`].
+///
+pub fn read_tree<T: TryNodeContent>(xml: &str, options: &XmlOptions) -> Result<Tree<T>, ImportTreeError> {
+    let root = read_root(xml, options)?;
+    build_tree(&root.content, &root)
+}
+
+/// Read a forest from an XML document.
+///
+/// # Arguments
+///
+/// * `xml` - XML document.
+/// * `options` - XML options.
+///
+/// # Return
+///
+/// * A [`Result`] with the forest or an [`ImportTreeError`]. The forest has one tree, using the name of the root element as tree ID.
+///
+pub fn read_forest<T: TryNodeContent>(xml: &str, options: &XmlOptions) -> Result<Forest<T>, ImportTreeError> {
+    let root = read_root(xml, options)?;
+    let mut forest = Forest::new();
+    forest.add_tree(&root.content, build_tree(&root.content, &root)?);
+    Ok(forest)
+}
+
+/// Write a tree as an XML document.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `options` - XML options.
+///
+/// # Return
+///
+/// * A [`Result`] with the XML document, or a [`ConvertTreeError`] if a node is not a valid element, attribute or text. The error has no tree ID.
+///
+pub fn write_tree<T: NodeContent>(tree: &Tree<T>, options: &XmlOptions) -> Result<String, ConvertTreeError> {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    if tree.get_nodes_len() > 0 {
+        if let XmlNode::Element(name) = xml_node(tree, 0, options)? {
+            write_element(tree, 0, &name, 0, options, &mut xml)?;
+        }
+    }
+    Ok(xml)
+}
+
+/// Kind of node, given by its content.
+enum XmlNode {
+    Element(String),
+    Attribute(String, String),
+    Text(String)
+}
+
+fn xml_node<T: NodeContent>(tree: &Tree<T>, position: usize, options: &XmlOptions) -> Result<XmlNode, ConvertTreeError> {
+    let content = tree.get_node_content(position).map(|c| c.gen_content()).unwrap_or_default();
+    let fail = |msg: &str| ConvertTreeError::new(msg, None, content_path(tree, position));
+    let is_leaf = children(tree, position).is_empty();
+    if options.attributes && content.starts_with('@') {
+        return match content[1..].split_once('=') {
+            Some((name, value)) if is_valid_name(name) && is_leaf && position != 0 => Ok(XmlNode::Attribute(String::from(name), String::from(value))),
+            Some((name, _)) if is_valid_name(name) => Err(fail("Attributes must be leaf nodes under an element")),
+            _ => Err(fail("Invalid attribute, expected `@name=value`"))
+        };
+    }
+    if options.text && content.starts_with('#') {
+        return if is_leaf && position != 0 {
+            Ok(XmlNode::Text(String::from(&content[1..])))
+        }
+        else {
+            Err(fail("Text must be leaf nodes under an element"))
+        };
+    }
+    if is_valid_name(&content) {
+        Ok(XmlNode::Element(content))
+    }
+    else {
+        Err(fail("Invalid element name"))
+    }
+}
+
+/// Step of writing an element, to write deep trees without recursion.
+enum Step {
+    Open(usize, String, usize),
+    Text(String, usize),
+    Close(String, usize)
+}
+
+fn write_element<T: NodeContent>(tree: &Tree<T>, position: usize, name: &str, depth: usize, options: &XmlOptions, xml: &mut String) -> Result<(), ConvertTreeError> {
+    let mut steps = vec!(Step::Open(position, String::from(name), depth));
+    while let Some(step) = steps.pop() {
+        match step {
+            Step::Open(position, name, depth) => {
+                let indent = "  ".repeat(depth);
+                let mut attributes = String::new();
+                let mut contents = vec!();
+                for child in children(tree, position) {
+                    match xml_node(tree, child, options)? {
+                        XmlNode::Attribute(name, value) => attributes.push_str(&format!(" {}=\"{}\"", name, xml_escape(&value))),
+                        node => contents.push((child, node))
+                    }
+                }
+                if contents.is_empty() {
+                    xml.push_str(&format!("{}<{}{}/>\n", indent, name, attributes));
+                }
+                else if let [(_, XmlNode::Text(text))] = contents.as_slice() {
+                    xml.push_str(&format!("{}<{}{}>{}</{}>\n", indent, name, attributes, xml_escape(text), name));
+                }
+                else {
+                    xml.push_str(&format!("{}<{}{}>\n", indent, name, attributes));
+                    steps.push(Step::Close(name, depth));
+                    for (child, node) in contents.into_iter().rev() {
+                        match node {
+                            XmlNode::Element(child_name) => steps.push(Step::Open(child, child_name, depth + 1)),
+                            XmlNode::Text(text) => steps.push(Step::Text(text, depth + 1)),
+                            XmlNode::Attribute(..) => {}
+                        }
+                    }
+                }
+            },
+            Step::Text(text, depth) => xml.push_str(&format!("{}{}\n", "  ".repeat(depth), xml_escape(&text))),
+            Step::Close(name, depth) => xml.push_str(&format!("{}</{}>\n", "  ".repeat(depth), name))
+        }
+    }
+    Ok(())
+}
+
+/// Check that a name is a valid XML name, without namespace prefix.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => chars.all(|c| c.is_alphanumeric() || "_-.".contains(c)),
+        _ => false
+    }
+}
+
+fn read_root(xml: &str, options: &XmlOptions) -> Result<Branch, ImportTreeError> {
+    let mut reader = EventReader::new(xml.as_bytes());
+    let mut elements: Vec<Branch> = vec!();
+    let mut root = None;
+    loop {
+        let event = reader.next().map_err(|e| ImportTreeError::new(e.msg(), Some(e.position().row as usize)))?;
+        let line = reader.position().row as usize;
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let mut element = Branch::new(&name.local_name, Some(line));
+                if options.attributes {
+                    for attr in attributes {
+                        element.children.push(Branch::new(&format!("@{}={}", attr.name.local_name, attr.value), Some(line)));
+                    }
+                }
+                elements.push(element);
+            },
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                if let (true, Some(element)) = (options.text, elements.last_mut()) {
+                    // Node contents can't have line breaks
+                    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+                    if !text.is_empty() {
+                        element.children.push(Branch::new(&format!("#{}", text), Some(line)));
+                    }
+                }
+            },
+            XmlEvent::EndElement { .. } => {
+                if let Some(element) = elements.pop() {
+                    match elements.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => root = Some(element)
+                    }
+                }
+            },
+            XmlEvent::EndDocument => break,
+            _ => {}
+        }
+    }
+    root.ok_or_else(|| ImportTreeError::new("No root element found", None))
+}
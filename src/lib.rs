@@ -150,15 +150,146 @@
 //! All nodes inside the tree will be of type `IntegerNode`.
 //! 
 //! The [`NodeContent::new()`][`socarel::NodeContent::new()`] is called every time a node of the tree is parsed. It returns an [`Option`], that means it can be None, in which case the TREF parser will fail, returing an error.
+//! 
+//! Dialects that also implement [`TryNodeContent`] can explain why a node was rejected, by overriding [`TryNodeContent::try_new()`]. Parse them with [`Model::try_parse()`], and the reason will be included in the [`ParseTreeError`]:
+//! 
+//! ```
+//! # use tref::*;
+//! # use std::io::BufReader;
+//! pub struct IntegerNode {
+//!     num: i32,
+//!     content: String
+//! }
+//! 
+//! impl NodeContent for IntegerNode {
+//!     fn new(content: &str) -> Option<Self> {
+//!         Self::try_new(content).ok()
+//!     }
+//! 
+//!     fn get_val(&self) -> &str {
+//!         &self.content
+//!     }
+//! 
+//!     fn gen_content(&self) -> String {
+//!         String::from(self.get_val())
+//!     }
+//! }
+//! 
+//! impl TryNodeContent for IntegerNode {
+//!     fn try_new(content: &str) -> Result<Self, String> {
+//!         match content.trim().parse() {
+//!             Ok(num) => Ok(IntegerNode { num, content: String::from(content) }),
+//!             Err(e) => Err(format!("Not an integer ({})", e))
+//!         }
+//!     }
+//! }
+//! 
+//! let tref = "[my_dialect_tree]\n+ 1000\n+ + abc\n";
+//! if let Err(e) = tref::Model::<IntegerNode>::try_parse(BufReader::new(tref.as_bytes())) {
+//!     // Prints: `Failed parsing node: Not an integer (invalid digit found in string)` at line 3 with node `abc` at path `1000 > abc`
+//!     println!("{}", e);
+//! }
+//! ```
+//! 
+//! Some common dialects, like integers, key-value pairs or weighted edges, are already implemented in the [`dialects`] module.
+//! 
+//! A document can also contain trees of different dialects, parsed with a [`MixedModel`] that knows the dialect of each tree.
+//! 
+//! Trees and forests can also be written directly in Rust code with the [`tref!`] macro.
+//! 
+//! Forests already parsed can be converted to another dialect with [`convert_forest()`].
+//! 
+//! Dialects that depend on the position of the node can also override [`TryNodeContent::validate_in()`], that receives a [`NodeContext`] with the tree ID, the node level, the parent content and the document line.
+//! 
+//! With the `derive` feature enabled, dialects can be derived from a format string, that is used both to parse and to generate the node content:
+//! 
+//! ```ignore
+//! use tref::TrefNode;
+//! 
+//! #[derive(TrefNode)]
+//! #[tref(format = "{weight}:{name}")]
+//! pub struct EdgeNode {
+//!     weight: u32,
+//!     name: String,
+//!     content: String
+//! }
+//! ```
+//! 
+//! The original node content is kept in the `content` field. Check out the `tref_derive` crate for details.
+//! 
+//! # Queries
+//! 
+//! Nodes can be selected with path queries, like `my_tree/root/*/child_1`, `//branch_2_*` or `[level=3]`, using [`query_forest()`] and [`query_tree()`], or the [`Query`] methods of forests and trees.
+//! 
+//! A single node can also be found by its content path, the contents of the nodes from the root to it like `root > branch_1 > child_1`, using [`find_path()`], and [`node_path()`] gets the content path of any node. Parse errors of child nodes include their content path.
+//! 
+//! # Diff
+//! 
+//! The structural changes between two versions of a forest, like added trees or inserted, deleted, moved and renamed nodes, are found with [`diff_forest()`] and [`diff_tree()`]. Reordered siblings are not a change.
+//! 
+//! Two versions of a forest changed in parallel from a common base are merged with [`merge_forest()`] and [`merge_tree()`], which apply the changes of both versions and report the ones that conflict with their content path.
+//! 
+//! # Other formats
+//! 
+//! The [`formats`] module converts trees from and to other formats, like Newick, Nexus, Markdown, Org-mode or S-expressions, and renders them as diagrams, like Graphviz DOT, Mermaid or SVG. Formats based on XML, like generic XML documents or PhyloXML, require the `xml` feature, and JSON requires the `json` feature.
+//! 
+//! # Linting
+//! 
+//! The [`lint`] module finds style problems and likely mistakes in valid documents, like duplicated siblings or empty trees, [`format_document()`] rewrites documents with a canonical layout, and [`format_forest()`] writes a changed forest keeping the comments of its document.
+//! 
+//! # Serde
+//! 
+//! With the `serde` feature enabled, Rust values can be serialized into TREF documents with `tref::to_string()` and deserialized with `tref::from_str()`. Check out the `ser` module for details about how values are mapped.
 
 mod parser;
 mod stack;
+mod builder;
 mod model;
+mod mixed;
+mod pattern;
+mod walk;
+mod convert;
+mod query;
+mod path;
+mod diff;
+mod merge;
+mod format;
 mod error;
+mod dialect;
+pub mod dialects;
+pub mod formats;
+pub mod lint;
+#[doc(hidden)]
+pub mod macros;
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "serde")]
+pub mod de;
 
 pub use model::*;
+pub use mixed::*;
+pub use convert::*;
+pub use query::*;
+pub use path::*;
+pub use diff::*;
+pub use merge::*;
+pub use format::*;
 pub use error::*;
-pub use socarel::NodeContent;
+pub use dialect::*;
+pub use walk::sorted_tree_ids;
+#[cfg(feature = "serde")]
+pub use ser::to_string;
+#[cfg(feature = "serde")]
+pub use de::{from_str, from_reader};
+#[cfg(feature = "derive")]
+pub use tref_derive::TrefNode;
+
+#[cfg(all(test, feature = "derive"))]
+extern crate self as tref;
+pub use socarel::{NodeContent, Forest, Tree, RawNode};
 
 #[cfg(test)]
 mod tests;
\ No newline at end of file
@@ -0,0 +1,360 @@
+//! Linter, to find style problems and likely mistakes in valid documents.
+//!
+//! Each problem is reported as a [`Diagnostic`], found by one of the [`RULES`]. All rules are warnings by default, and can be turned off or into errors with [`LintOptions`]:
+//!
+//! ```
+//! use tref::lint::{self, LintOptions, Severity};
+//!
+//! let tref = "[my tree]\n+ root_node\n+ + child_1\n+ + child_1\n";
+//! let options = LintOptions::new().rule("tree-id-spaces", Severity::Off);
+//! let diagnostics = lint::check(tref, &options).unwrap();
+//! assert_eq!(diagnostics.len(), 1);
+//! assert_eq!(diagnostics[0].rule(), "duplicate-sibling");
+//! assert_eq!(diagnostics[0].line(), 3);
+//! ```
+//!
+//! Documents can also configure the linter with comments:
+//!
+//! * `# tref-lint: <rule>=<severity>, ...` sets the severity of rules for the whole document, overriding the options. Severities are `off`, `warning` and `error`. The maximum level of `deep-nesting` is set with `max-level=<N>`.
+//! * `# tref-lint-ignore` suppresses all diagnostics of the next tree ID or node, and `# tref-lint-ignore <rule> ...` only the ones of the given rules.
+//!
+//! The `mixed-dialects` rule is a heuristic: it only knows the built-in dialects of the [`dialects`][`crate::dialects`] module, and guesses the dialect of each tree from the one most of its nodes match. Trees of other dialects are not checked, or can get wrong diagnostics when most of their nodes look like a built-in dialect, so turn the rule off for documents that use their own dialects.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::BufReader;
+use socarel::RawNode;
+use crate::model::*;
+use crate::parser::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::dialects::*;
+
+/// Rule IDs, with a description of the problems they find.
+pub const RULES: [(&str, &str); 7] = [
+    ("duplicate-sibling", "Two children of the same node have the same content"),
+    ("empty-tree", "A tree has no nodes"),
+    ("deep-nesting", "A node is deeper than the maximum level"),
+    ("trailing-whitespace", "A node content ends with whitespaces"),
+    ("tree-id-spaces", "A tree ID contains whitespaces"),
+    ("mixed-dialects", "A node doesn't match the built-in dialect used by most nodes of its tree"),
+    ("lint-comment", "A lint comment is not valid")
+];
+
+/// Check if a node content matches a dialect.
+type DialectCheck = fn(&str) -> bool;
+
+/// Built-in dialects checked by `mixed-dialects`, from more to less specific.
+const DIALECTS: [(&str, DialectCheck); 7] = [
+    ("integer", matches_dialect::<IntegerNode>),
+    ("float", matches_dialect::<FloatNode>),
+    ("boolean", matches_dialect::<BooleanNode>),
+    ("quoted string", matches_dialect::<QuotedNode>),
+    ("weighted edge", matches_dialect::<WeightedEdgeNode>),
+    ("key-value", matches_dialect::<KeyValueNode>),
+    ("property", matches_dialect::<PropertyNode>)
+];
+
+const CONFIG_COMMENT: &str = "# tref-lint:";
+const IGNORE_COMMENT: &str = "# tref-lint-ignore";
+
+/// Severity of a rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The rule is not checked.
+    Off,
+    Warning,
+    Error
+}
+
+impl Severity {
+    /// Parse a severity: `off`, `warning` or `error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `severity` - Severity name.
+    ///
+    /// # Return
+    ///
+    /// * An [`Option`] with the severity, or None if the name is unknown.
+    ///
+    pub fn from_name(severity: &str) -> Option<Self> {
+        match severity {
+            "off" => Some(Severity::Off),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Off => write!(f, "off"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error")
+        }
+    }
+}
+
+/// Options of the linter.
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    severities: HashMap<String, Severity>,
+    max_level: usize
+}
+
+impl LintOptions {
+    /// Create default options: all rules are warnings, and the maximum level is 10.
+    pub fn new() -> Self {
+        Self {
+            severities: HashMap::new(),
+            max_level: 10
+        }
+    }
+
+    /// Set the severity of a rule.
+    pub fn rule(mut self, rule: &str, severity: Severity) -> Self {
+        self.severities.insert(String::from(rule), severity);
+        self
+    }
+
+    /// Set the maximum level of a node before `deep-nesting` reports it, where the root node is level 1.
+    pub fn max_level(mut self, max_level: usize) -> Self {
+        self.max_level = max_level;
+        self
+    }
+
+    /// Get the severity of a rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - Rule ID.
+    ///
+    /// # Return
+    ///
+    /// * Severity.
+    ///
+    pub fn severity(&self, rule: &str) -> Severity {
+        self.severities.get(rule).copied().unwrap_or(Severity::Warning)
+    }
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Problem found by the linter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    rule: &'static str,
+    severity: Severity,
+    line: usize,
+    message: String
+}
+
+impl Diagnostic {
+    /// Get ID of the rule that found the problem.
+    pub fn rule(&self) -> &str {
+        self.rule
+    }
+
+    /// Get severity.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Get document line where the problem is, starting at 0.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Get message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}: {} [{}]", self.severity, self.line + 1, self.message, self.rule)
+    }
+}
+
+/// Lint a TREF document.
+///
+/// # Arguments
+///
+/// * `tref` - TREF document.
+/// * `options` - Lint options.
+///
+/// # Return
+///
+/// * A [`Result`] with the diagnostics sorted by line, or a [`ParseTreeError`] if the document is not valid.
+///
+pub fn check(tref: &str, options: &LintOptions) -> Result<Vec<Diagnostic>, ParseTreeError> {
+    Model::<RawNode>::parse(BufReader::new(tref.as_bytes()))?;
+    let mut linter = Linter {
+        options: options.clone(),
+        diagnostics: vec!(),
+        ignored: HashMap::new()
+    };
+    let trees = linter.read_document(tref);
+    for tree in trees.iter() {
+        linter.check_tree(tree);
+    }
+    let Linter { options, diagnostics, ignored } = linter;
+    let mut diagnostics: Vec<Diagnostic> = diagnostics.into_iter()
+        .map(|mut d| {
+            d.severity = options.severity(d.rule);
+            d
+        })
+        .filter(|d| d.severity != Severity::Off)
+        .filter(|d| match ignored.get(&d.line) {
+            Some(rules) => !rules.is_empty() && !rules.iter().any(|r| r == d.rule),
+            None => true
+        })
+        .collect();
+    diagnostics.sort_by_key(|d| d.line);
+    Ok(diagnostics)
+}
+
+/// Tree of a document, with the lines of its statements.
+struct LintTree<'a> {
+    tree_id: &'a str,
+    line: usize,
+    /// Node content, level, line and parent node index.
+    nodes: Vec<(&'a str, usize, usize, Option<usize>)>
+}
+
+struct Linter {
+    options: LintOptions,
+    diagnostics: Vec<Diagnostic>,
+    /// Rules ignored at each line, or empty if all are.
+    ignored: HashMap<usize, Vec<String>>
+}
+
+impl Linter {
+    /// Read trees and lint comments of a valid document.
+    fn read_document<'a>(&mut self, tref: &'a str) -> Vec<LintTree<'a>> {
+        let parser = TreeParser::new();
+        let mut trees: Vec<LintTree> = vec!();
+        let mut ignore_next: Option<Vec<String>> = None;
+        for (line, statement) in tref.lines().enumerate() {
+            match parser.parse_statement(statement) {
+                TreeStatement::Comment => {
+                    if let Some(rules) = statement.strip_prefix(IGNORE_COMMENT).filter(|rules| rules.is_empty() || rules.starts_with(char::is_whitespace)) {
+                        ignore_next = Some(rules.split_whitespace().map(String::from).collect());
+                    }
+                    else if let Some(config) = statement.strip_prefix(CONFIG_COMMENT) {
+                        self.read_config(config, line);
+                    }
+                    continue;
+                },
+                TreeStatement::TreeID(_) => {
+                    let tree_id = &statement[1..statement.len() - 1];
+                    trees.push(LintTree { tree_id, line, nodes: vec!() });
+                },
+                TreeStatement::Node(_, level) => {
+                    let content = &statement[level * 2..];
+                    if let Some(tree) = trees.last_mut() {
+                        // Parent is the last node of the previous level
+                        let parent = tree.nodes.iter().rposition(|n| n.1 + 1 == level);
+                        tree.nodes.push((content, level, line, parent));
+                    }
+                },
+                _ => continue
+            }
+            if let Some(rules) = ignore_next.take() {
+                self.ignored.insert(line, rules);
+            }
+        }
+        trees
+    }
+
+    fn read_config(&mut self, config: &str, line: usize) {
+        for setting in config.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let (key, value) = match setting.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => {
+                    self.report("lint-comment", line, format!("Invalid setting `{}`, expected `<rule>=<severity>`", setting));
+                    continue;
+                }
+            };
+            if key == "max-level" {
+                match value.parse() {
+                    Ok(max_level) => self.options.max_level = max_level,
+                    Err(_) => self.report("lint-comment", line, format!("Invalid maximum level `{}`", value))
+                }
+            }
+            else if !RULES.iter().any(|(rule, _)| *rule == key) {
+                self.report("lint-comment", line, format!("Unknown rule `{}`", key));
+            }
+            else {
+                match Severity::from_name(value) {
+                    Some(severity) => self.options.severities.insert(String::from(key), severity),
+                    None => {
+                        self.report("lint-comment", line, format!("Unknown severity `{}`", value));
+                        continue;
+                    }
+                };
+            }
+        }
+    }
+
+    fn check_tree(&mut self, tree: &LintTree) {
+        if tree.tree_id.chars().any(|c| c.is_whitespace()) {
+            self.report("tree-id-spaces", tree.line, format!("Tree ID `{}` contains whitespaces", tree.tree_id));
+        }
+        if tree.nodes.is_empty() {
+            self.report("empty-tree", tree.line, format!("Tree `{}` has no nodes", tree.tree_id));
+            return;
+        }
+        // Parent and content of the nodes found so far
+        let mut siblings: HashSet<(Option<usize>, &str)> = HashSet::new();
+        for (content, level, line, parent) in tree.nodes.iter() {
+            if *level > self.options.max_level {
+                self.report("deep-nesting", *line, format!("Node `{}` is at level {}, deeper than {}", content, level, self.options.max_level));
+            }
+            if content.ends_with(char::is_whitespace) {
+                self.report("trailing-whitespace", *line, format!("Node `{}` ends with whitespaces", content.trim_end()));
+            }
+            if !siblings.insert((*parent, content)) {
+                self.report("duplicate-sibling", *line, format!("Node `{}` has a sibling with the same content", content));
+            }
+        }
+        self.check_dialects(tree);
+    }
+
+    /// Find the dialect most nodes match, and report the ones that don't.
+    fn check_dialects(&mut self, tree: &LintTree) {
+        let num_nodes = tree.nodes.len();
+        let mut best: Option<(&str, DialectCheck, usize)> = None;
+        for (name, matches) in DIALECTS.iter() {
+            let count = tree.nodes.iter().filter(|n| matches(n.0)).count();
+            if best.map(|b| count > b.2).unwrap_or(true) {
+                best = Some((name, *matches, count));
+            }
+        }
+        if let Some((name, matches, count)) = best {
+            if count * 2 > num_nodes && count < num_nodes {
+                for (content, _, line, _) in tree.nodes.iter() {
+                    if !matches(content) {
+                        self.report("mixed-dialects", *line, format!("Node `{}` is not a {} node, like {} of {} nodes in tree `{}`", content, name, count, num_nodes, tree.tree_id));
+                    }
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, rule: &'static str, line: usize, message: String) {
+        self.diagnostics.push(Diagnostic { rule, severity: Severity::Warning, line, message });
+    }
+}
+
+fn matches_dialect<T: TryNodeContent>(content: &str) -> bool {
+    T::try_new(content).is_ok()
+}
@@ -0,0 +1,89 @@
+//! Support functions for the [`tref!`][`crate::tref!`] macro.
+
+use socarel::Tree;
+use crate::dialect::*;
+
+/// Create the root node of a tree, panicking if the dialect rejects it.
+pub fn set_root<T: TryNodeContent>(tree: &mut Tree<T>, content: &str) -> usize {
+    match tree.set_root(content) {
+        Some(position) => position,
+        None => panic!("Invalid root node `{}`: {}", content, rejection::<T>(content))
+    }
+}
+
+/// Link a node to a tree, panicking if the dialect rejects it.
+pub fn link_node<T: TryNodeContent>(tree: &mut Tree<T>, content: &str, parent: usize) -> usize {
+    match tree.link_node(content, parent) {
+        Some(position) => position,
+        None => panic!("Invalid node `{}`: {}", content, rejection::<T>(content))
+    }
+}
+
+fn rejection<T: TryNodeContent>(content: &str) -> String {
+    match T::try_new(content) {
+        Err(reason) => reason,
+        Ok(_) => String::from("Could not link node")
+    }
+}
+
+/// Build a [`Tree`][`socarel::Tree`] or a [`Forest`][`socarel::Forest`] from a TREF-like literal.
+///
+/// A node is a single token, like a string literal, a number or a variable, converted to node content with [`ToString`]. Its children follow `=>`, enclosed in braces and separated by commas:
+///
+/// ```
+/// use tref::{tref, Tree, RawNode};
+///
+/// let tree: Tree<RawNode> = tref!(
+///     "root_node" => {
+///         "child_1" => {
+///             "child_1_1",
+///             "child_1_2"
+///         },
+///         "child_2"
+///     }
+/// );
+/// assert_eq!(tree.get_nodes_len(), 5);
+/// ```
+///
+/// A forest is a list of trees, each one preceded by its tree ID in brackets, like in a TREF document:
+///
+/// ```
+/// use tref::{tref, Forest, dialects::IntegerNode};
+///
+/// let name = "numbers";
+/// let forest: Forest<IntegerNode> = tref!(
+///     ["my_tree"] 1000 => { 800 => { 2500, 130 } }
+///     [name] (5 * 2)
+/// );
+/// assert_eq!(forest.get_tree("numbers").unwrap().get_node_content(0).unwrap().get_num(), 10);
+/// ```
+///
+/// The structure is checked at compile time. Node contents are checked by the dialect at runtime, and the macro panics if one is rejected.
+#[macro_export]
+macro_rules! tref {
+    // Forest
+    ( $( [ $tree_id:tt ] $root:tt $( => { $($children:tt)* } )? )+ ) => {{
+        let mut forest = $crate::Forest::new();
+        $(
+            let tree = $crate::tref!($root $( => { $($children)* } )?);
+            forest.add_tree(&::std::string::ToString::to_string(&$tree_id), tree);
+        )+
+        forest
+    }};
+    // Children of a node
+    ( @children $tree:ident, $parent:ident, $( $content:tt $( => { $($children:tt)* } )? ),* $(,)? ) => {
+        $(
+            #[allow(unused_variables)]
+            let node = $crate::macros::link_node(&mut $tree, &::std::string::ToString::to_string(&$content), $parent);
+            $( $crate::tref!(@children $tree, node, $($children)*); )?
+        )*
+    };
+    // Tree
+    ( $root:tt $( => { $($children:tt)* } )? ) => {{
+        let mut tree = $crate::Tree::new();
+        #[allow(unused_variables)]
+        let root = $crate::macros::set_root(&mut tree, &::std::string::ToString::to_string(&$root));
+        $( $crate::tref!(@children tree, root, $($children)*); )?
+        tree
+    }};
+}
@@ -1,30 +1,608 @@
-use std::{fs::File, io::{BufReader, BufWriter}};
-use tref;
+use std::{env, fs, io::{self, BufReader, Read}, process};
+use tref::{Model, Forest, Tree, RawNode, NodeContent, format_document, format_forest, query_forest, node_path, diff_forest, merge_forest, convert_forest, sorted_tree_ids, TryNodeContent};
+use tref::dialects::WeightedEdgeNode;
+use tref::formats::{self, text, TextOptions, DiagramOptions, SvgOptions};
+use tref::lint::{self, LintOptions, Severity};
+
+const USAGE: &str = "Usage: tref <command> [options] [file]
+
+Reads TREF documents from `file`, or from the standard input if no file or `-` is given.
+
+Commands:
+    validate    Check documents, printing errors and exiting with code 1 if any is invalid
+    fmt         Format documents with the canonical layout
+    lint        Check documents for style problems and likely mistakes
+    convert     Convert a document between formats
+    cat         Print one tree as a TREF document
+    ls          List the tree IDs of a document
+    query       Print the nodes selected by a query
+    diff        Print the structural changes between two documents
+    merge       Merge two versions of a document changed from a common base
+    stats       Print the number of nodes, leaves and levels of each tree
+    tree        Print trees like the Unix `tree` command
+
+Usage of `validate`:
+    tref validate [file...]
+
+Usage of `fmt`:
+    tref fmt [--check] [file...]
+    Files are rewritten in place, the standard input is written to the standard output.
+    Invalid documents are reported and skipped, and the command exits with code 1.
+    --check         Don't write, list the documents that need formatting and exit with code 1 if any
+
+Usage of `lint`:
+    tref lint [--rule <RULE>=<SEVERITY>]... [--max-level <N>] [file...]
+    Exits with code 1 if any document is invalid or has errors.
+    --rule <RULE>=<SEVERITY>
+                    Set the severity of a rule: off, warning or error
+    --max-level <N> Maximum node level, 10 by default
+    Rules: duplicate-sibling, empty-tree, deep-nesting, trailing-whitespace,
+    tree-id-spaces, mixed-dialects, lint-comment.
+
+Usage of `convert`:
+    tref convert [--from <FORMAT>] --to <FORMAT> [--tree <ID>] [--separator <SEP>] [file]
+    --from <FORMAT> Format of the input, `tref` by default
+    --to <FORMAT>   Format of the output
+    --tree <ID>     Only convert the tree with this ID
+    --separator <SEP>
+                    Separator of the `paths` format, `/` by default
+    Formats: tref, newick, nexus, phyloxml, json, xml, sexp, markdown, org, paths, csv,
+    and only as output: dot, mermaid, svg, text.
+    Formats phyloxml, xml and json are only available if the tool was built with their features.
+    Formats newick, sexp, xml, csv and paths don't keep tree IDs: trees read back are named
+    `tree_1`, `tree_2`, etc., or after their root node. Newick, Nexus and sexp skip empty trees.
+
+Usage of `cat`:
+    tref cat <ID> [file]
+
+Usage of `query`:
+    tref query [--path] <QUERY> [file]
+    Prints the tree ID, position and content of each node, like `my_tree:3: child_1`.
+    --path          Print the content path of the nodes, like `my_tree:3: root > branch_1 > child_1`
+    Queries are paths like `my_tree/root/*/child_1`, `//branch_2_*` or `[level=3]`.
+
+Usage of `diff`:
+    tref diff <old> <new>
+    Prints one change per line, and exits with code 1 if there are changes:
+    `+ [tree]` and `- [tree]` for added and removed trees, `+ tree: path` and `- tree: path`
+    for inserted and deleted nodes, `> tree: old -> new` for moved nodes and `~ tree: old -> new`
+    for renamed nodes. Reordered siblings are not a change.
+
+Usage of `merge`:
+    tref merge [--output <file>] <base> <ours> <theirs>
+    Prints the merged document, and exits with code 1 if there are conflicts. Conflicts are resolved
+    with our version, and listed in comments at the beginning of the document. If only one version
+    changed the document, its text is kept as it is. Otherwise the comments of our version are kept,
+    placed like `fmt` does, with the comments inside a tree at its end.
+    --output <file> Write the merged document to a file instead of the standard output
+    To use it as a git merge driver, add to `.gitattributes`:
+        *.tref merge=tref
+    and to the git configuration:
+        [merge \"tref\"]
+            name = TREF structural merge
+            driver = tref merge --output %A %O %A %B
+
+Usage of `ls` and `stats`:
+    tref ls [file]
+    tref stats [file]
+
+Usage of `tree`:
+    tref tree [options] [file]
+    --ascii         Use only ASCII characters
+    --positions     Show the position of each node
+    --depth <N>     Don't show nodes deeper than N, the root is depth 0
+    --width <N>     Truncate node contents longer than N characters
+    --tree <ID>     Only print the tree with this ID";
 
 fn main() {
-    if let Ok(file) = File::open("file.tref") {
-        match <tref::Model>::parse(BufReader::new(file)) {
-            Ok(forest) => {
-                for (tree_id, tree) in forest.iter() {
-                    println!("{} = {:#?}", tree_id, tree);
-    
-                    let f = File::create("serialized.tref").expect("Unable to create file");
-                    let mut buf_writer = BufWriter::new(f);
-                    match <tref::Model>::serialize(&forest, &mut buf_writer) {
-                        Ok(num_lines) => {
-                            println!("Tree serialized correctly, num lines = {}", num_lines);
-                        },
-                        Err(e) => {
-                            println!("Failed serializing tree: {}", e);
-                        }
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|a| a.as_str()) {
+        Some("validate") => cmd_validate(&args[1..]),
+        Some("fmt") => cmd_fmt(&args[1..]),
+        Some("lint") => cmd_lint(&args[1..]),
+        Some("convert") => cmd_convert(&args[1..]),
+        Some("cat") => cmd_cat(&args[1..]),
+        Some("ls") => cmd_ls(&args[1..]),
+        Some("query") => cmd_query(&args[1..]),
+        Some("diff") => cmd_diff(&args[1..]),
+        Some("merge") => cmd_merge(&args[1..]),
+        Some("stats") => cmd_stats(&args[1..]),
+        Some("tree") => cmd_tree(&args[1..]),
+        Some("-h") | Some("--help") | Some("help") => {
+            println!("{}", USAGE);
+            Ok(())
+        },
+        Some(command) => Err(CliError::Usage(format!("Unknown command `{}`", command))),
+        None => Err(CliError::Usage(String::from("Missing command")))
+    };
+    match result {
+        Ok(()) => {},
+        Err(CliError::Usage(msg)) => {
+            eprintln!("{}\nRun `tref help` to see all commands and options.", msg);
+            process::exit(2);
+        },
+        Err(CliError::Failed(msg)) => {
+            eprintln!("{}", msg);
+            process::exit(1);
+        }
+    }
+}
+
+/// Command line error.
+enum CliError {
+    /// Wrong arguments.
+    Usage(String),
+    /// The command failed.
+    Failed(String)
+}
+
+fn cmd_validate(args: &[String]) -> Result<(), CliError> {
+    let files = file_args(args)?;
+    let mut num_invalid = 0;
+    for file in files.iter() {
+        let result = read_text(*file).and_then(|text| parse(&text));
+        if let Err(CliError::Failed(msg)) = result {
+            eprintln!("{}: {}", file_name(*file), msg);
+            num_invalid += 1;
+        }
+    }
+    if num_invalid > 0 {
+        Err(CliError::Failed(format!("{} of {} documents are invalid", num_invalid, files.len())))
+    }
+    else {
+        Ok(())
+    }
+}
+
+fn cmd_fmt(args: &[String]) -> Result<(), CliError> {
+    let check = args.iter().any(|a| a == "--check");
+    let args: Vec<String> = args.iter().filter(|a| *a != "--check").cloned().collect();
+    let files = file_args(&args)?;
+    let mut unformatted = vec!();
+    let mut num_invalid = 0;
+    for file in files {
+        let result = read_text(file).and_then(|text| match format_document(&text) {
+            Ok(formatted) => Ok((text, formatted)),
+            Err(e) => Err(CliError::Failed(format!("Failed parsing document: {}", e)))
+        });
+        let (text, formatted) = match result {
+            Ok(result) => result,
+            Err(CliError::Failed(msg)) | Err(CliError::Usage(msg)) => {
+                eprintln!("{}: {}", file_name(file), msg);
+                num_invalid += 1;
+                continue;
+            }
+        };
+        if check {
+            if formatted != text {
+                println!("{}", file_name(file));
+                unformatted.push(file);
+            }
+        }
+        else {
+            match file {
+                None | Some("-") => print!("{}", formatted),
+                Some(path) => if formatted != text {
+                    if let Err(e) = fs::write(path, formatted) {
+                        eprintln!("Could not write `{}`: {}", path, e);
+                        num_invalid += 1;
                     }
+                }
+            }
+        }
+    }
+    if num_invalid > 0 {
+        Err(CliError::Failed(format!("{} documents failed", num_invalid)))
+    }
+    else if !unformatted.is_empty() {
+        Err(CliError::Failed(format!("{} documents need formatting", unformatted.len())))
+    }
+    else {
+        Ok(())
+    }
+}
 
-                    println!("--------------------------");
+fn cmd_lint(args: &[String]) -> Result<(), CliError> {
+    let mut options = LintOptions::new();
+    let mut files = vec!();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rule" => {
+                let setting = value_arg("--rule", args.next())?;
+                let (rule, severity) = setting.split_once('=')
+                    .and_then(|(rule, severity)| Severity::from_name(severity).map(|severity| (rule, severity)))
+                    .ok_or_else(|| CliError::Usage(format!("Invalid rule setting `{}`, expected `<RULE>=<SEVERITY>`", setting)))?;
+                if !lint::RULES.iter().any(|(id, _)| *id == rule) {
+                    return Err(CliError::Usage(format!("Unknown rule `{}`", rule)));
                 }
+                options = options.rule(rule, severity);
             },
-            Err(e) => {
-                println!("Failed parsing document: {}", e);
+            "--max-level" => options = options.max_level(number_arg("--max-level", args.next())?),
+            _ => files.push(arg.clone())
+        }
+    }
+    let mut num_failed = 0;
+    for file in file_args(&files)? {
+        let name = file_name(file);
+        let diagnostics = read_text(file).and_then(|text| lint::check(&text, &options).map_err(|e| CliError::Failed(format!("Failed parsing document: {}", e))));
+        match diagnostics {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics.iter() {
+                    println!("{}: {}", name, diagnostic);
+                }
+                if diagnostics.iter().any(|d| d.severity() == Severity::Error) {
+                    num_failed += 1;
+                }
+            },
+            Err(CliError::Failed(msg)) | Err(CliError::Usage(msg)) => {
+                eprintln!("{}: {}", name, msg);
+                num_failed += 1;
             }
         }
     }
-}
\ No newline at end of file
+    if num_failed > 0 {
+        Err(CliError::Failed(format!("{} documents failed", num_failed)))
+    }
+    else {
+        Ok(())
+    }
+}
+
+fn cmd_convert(args: &[String]) -> Result<(), CliError> {
+    let mut from = String::from("tref");
+    let mut to = None;
+    let mut tree_id = None;
+    let mut separator = String::from("/");
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = value_arg("--from", args.next())?.clone(),
+            "--to" => to = Some(value_arg("--to", args.next())?),
+            "--tree" => tree_id = Some(value_arg("--tree", args.next())?),
+            "--separator" => separator = value_arg("--separator", args.next())?.clone(),
+            _ if arg.starts_with("--") => return Err(CliError::Usage(format!("Unknown option `{}`", arg))),
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => return Err(CliError::Usage(format!("Unexpected argument `{}`", arg)))
+        }
+    }
+    let to = to.ok_or_else(|| CliError::Usage(String::from("Missing option `--to`")))?;
+    check_format(&from, false)?;
+    check_format(to, true)?;
+    let mut forest = read_format(&from, &read_text(file)?, &separator)?;
+    if let Some(tree_id) = tree_id {
+        let tree = forest.remove_tree(tree_id).ok_or_else(|| tree_not_found(tree_id))?;
+        forest = Forest::new();
+        forest.add_tree(tree_id, tree);
+    }
+    print!("{}", write_format(to, &forest, &separator)?);
+    Ok(())
+}
+
+fn cmd_cat(args: &[String]) -> Result<(), CliError> {
+    let (tree_id, file) = match args {
+        [tree_id] => (tree_id, None),
+        [tree_id, file] => (tree_id, Some(file.as_str())),
+        [] => return Err(CliError::Usage(String::from("Missing tree ID"))),
+        _ => return Err(CliError::Usage(String::from("Too many arguments")))
+    };
+    let mut forest = parse(&read_text(file)?)?;
+    let tree = forest.remove_tree(tree_id).ok_or_else(|| tree_not_found(tree_id))?;
+    let mut single = Forest::new();
+    single.add_tree(tree_id, tree);
+    print!("{}", write_format("tref", &single, "")?);
+    Ok(())
+}
+
+fn cmd_ls(args: &[String]) -> Result<(), CliError> {
+    let forest = parse(&read_text(single_file_arg(args)?)?)?;
+    for tree_id in sorted_tree_ids(&forest) {
+        println!("{}", tree_id);
+    }
+    Ok(())
+}
+
+fn cmd_query(args: &[String]) -> Result<(), CliError> {
+    let path = args.iter().any(|a| a == "--path");
+    let args: Vec<String> = args.iter().filter(|a| *a != "--path").cloned().collect();
+    let (query, file) = match args.as_slice() {
+        [query] => (query, None),
+        [query, file] => (query, Some(file.as_str())),
+        [] => return Err(CliError::Usage(String::from("Missing query"))),
+        _ => return Err(CliError::Usage(String::from("Too many arguments")))
+    };
+    let forest = parse(&read_text(file)?)?;
+    let selected = query_forest(&forest, query).map_err(|e| CliError::Usage(format!("Invalid query: {}", e)))?;
+    for (tree_id, position) in selected {
+        if let Some(tree) = forest.get_tree(tree_id) {
+            let content = if path { node_path(tree, position) } else { tree.get_node_content(position).map(|c| String::from(c.get_val())) };
+            if let Some(content) = content {
+                println!("{}:{}: {}", tree_id, position, content);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_diff(args: &[String]) -> Result<(), CliError> {
+    let (old_file, new_file) = match file_args(args)?.as_slice() {
+        [Some(old_file), Some(new_file)] => (*old_file, *new_file),
+        [None] | [_] => return Err(CliError::Usage(String::from("Missing files to compare"))),
+        _ => return Err(CliError::Usage(String::from("Too many arguments")))
+    };
+    let changes = diff_forest(&parse_file(old_file)?, &parse_file(new_file)?);
+    for change in changes.iter() {
+        println!("{}", change);
+    }
+    if changes.is_empty() {
+        Ok(())
+    }
+    else {
+        Err(CliError::Failed(format!("{} changes", changes.len())))
+    }
+}
+
+fn cmd_merge(args: &[String]) -> Result<(), CliError> {
+    let mut output = None;
+    let mut files = vec!();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(value_arg("--output", args.next())?),
+            _ if arg.starts_with("--") => return Err(CliError::Usage(format!("Unknown option `{}`", arg))),
+            _ => files.push(arg.as_str())
+        }
+    }
+    let (base_file, our_file, their_file) = match files.as_slice() {
+        [base, ours, theirs] => (*base, *ours, *theirs),
+        [_, _, _, ..] => return Err(CliError::Usage(String::from("Too many arguments"))),
+        _ => return Err(CliError::Usage(String::from("Missing files to merge, expected base, ours and theirs")))
+    };
+    let (base_text, our_text, their_text) = (read_text(Some(base_file))?, read_text(Some(our_file))?, read_text(Some(their_file))?);
+    let (base, ours, theirs) = (parse_file_text(base_file, &base_text)?, parse_file_text(our_file, &our_text)?, parse_file_text(their_file, &their_text)?);
+    let (merged, conflicts) = merge_forest(&base, &ours, &theirs);
+    let mut text = String::new();
+    for conflict in conflicts.iter() {
+        text.push_str(&format!("# CONFLICT {}\n", conflict));
+    }
+    if !conflicts.is_empty() {
+        text.push('\n');
+    }
+    // When only one version changed the document, keep its text as it is
+    if our_text == base_text {
+        text.push_str(&their_text);
+    }
+    else if their_text == base_text || their_text == our_text {
+        text.push_str(&our_text);
+    }
+    else {
+        text.push_str(&format_forest(&merged, &our_text).map_err(|e| CliError::Failed(format!("Failed serializing document: {}", e)))?);
+    }
+    match output {
+        Some(path) => fs::write(path, text).map_err(|e| CliError::Failed(format!("Could not write `{}`: {}", path, e)))?,
+        None => print!("{}", text)
+    }
+    if conflicts.is_empty() {
+        Ok(())
+    }
+    else {
+        for conflict in conflicts.iter() {
+            eprintln!("CONFLICT {}", conflict);
+        }
+        Err(CliError::Failed(format!("{} conflicts", conflicts.len())))
+    }
+}
+
+fn cmd_stats(args: &[String]) -> Result<(), CliError> {
+    let forest = parse(&read_text(single_file_arg(args)?)?)?;
+    let mut total_nodes = 0;
+    let tree_ids = sorted_tree_ids(&forest);
+    for tree_id in tree_ids.iter() {
+        if let Some(tree) = forest.get_tree(tree_id) {
+            let (nodes, leaves, levels) = tree_stats(tree);
+            println!("{}: {} nodes, {} leaves, {} levels", tree_id, nodes, leaves, levels);
+            total_nodes += nodes;
+        }
+    }
+    println!("Total: {} trees, {} nodes", tree_ids.len(), total_nodes);
+    Ok(())
+}
+
+fn cmd_tree(args: &[String]) -> Result<(), CliError> {
+    let mut options = TextOptions::new();
+    let mut tree_id = None;
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ascii" => options = options.ascii(true),
+            "--positions" => options = options.positions(true),
+            "--depth" => options = options.max_depth(Some(number_arg("--depth", args.next())?)),
+            "--width" => options = options.max_width(Some(number_arg("--width", args.next())?)),
+            "--tree" => tree_id = Some(value_arg("--tree", args.next())?),
+            _ if arg.starts_with("--") => return Err(CliError::Usage(format!("Unknown option `{}`", arg))),
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => return Err(CliError::Usage(format!("Unexpected argument `{}`", arg)))
+        }
+    }
+    let forest = parse(&read_text(file)?)?;
+    match tree_id {
+        Some(tree_id) => match forest.get_tree(tree_id) {
+            Some(tree) => print!("{}", text::write_tree(tree, &options)),
+            None => return Err(tree_not_found(tree_id))
+        },
+        None => print!("{}", text::write_forest(&forest, &options))
+    }
+    Ok(())
+}
+
+fn value_arg<'a>(option: &str, value: Option<&'a String>) -> Result<&'a String, CliError> {
+    value.ok_or_else(|| CliError::Usage(format!("Missing value of `{}`", option)))
+}
+
+fn number_arg(option: &str, value: Option<&String>) -> Result<usize, CliError> {
+    match value.map(|v| v.parse()) {
+        Some(Ok(n)) => Ok(n),
+        _ => Err(CliError::Usage(format!("Option `{}` requires a number", option)))
+    }
+}
+
+/// Get the files of a command that accepts many, or the standard input if there are none.
+fn file_args(args: &[String]) -> Result<Vec<Option<&str>>, CliError> {
+    if let Some(arg) = args.iter().find(|a| a.starts_with("--")) {
+        return Err(CliError::Usage(format!("Unknown option `{}`", arg)));
+    }
+    if args.is_empty() {
+        Ok(vec!(None))
+    }
+    else {
+        Ok(args.iter().map(|a| Some(a.as_str())).collect())
+    }
+}
+
+/// Get the file of a command that accepts one, or the standard input if there is none.
+fn single_file_arg(args: &[String]) -> Result<Option<&str>, CliError> {
+    match file_args(args)?.as_slice() {
+        [file] => Ok(*file),
+        _ => Err(CliError::Usage(String::from("Too many arguments")))
+    }
+}
+
+fn file_name(file: Option<&str>) -> &str {
+    match file {
+        None | Some("-") => "<stdin>",
+        Some(path) => path
+    }
+}
+
+fn tree_not_found(tree_id: &str) -> CliError {
+    CliError::Failed(format!("Tree `{}` not found", tree_id))
+}
+
+/// Read a file, or the standard input.
+fn read_text(file: Option<&str>) -> Result<String, CliError> {
+    let mut text = String::new();
+    let result = match file {
+        None | Some("-") => io::stdin().read_to_string(&mut text),
+        Some(path) => fs::File::open(path).and_then(|mut f| f.read_to_string(&mut text))
+    };
+    result.map_err(|e| CliError::Failed(format!("Could not read `{}`: {}", file_name(file), e)))?;
+    Ok(text)
+}
+
+/// Read and parse a TREF document file, with the file name in errors.
+fn parse_file(file: &str) -> Result<Forest<RawNode>, CliError> {
+    parse_file_text(file, &read_text(Some(file))?)
+}
+
+/// Parse the text of a TREF document file, with the file name in errors.
+fn parse_file_text(file: &str, text: &str) -> Result<Forest<RawNode>, CliError> {
+    parse(text).map_err(|e| match e {
+        CliError::Failed(msg) => CliError::Failed(format!("{}: {}", file_name(Some(file)), msg)),
+        e => e
+    })
+}
+
+/// Parse a TREF document.
+fn parse(text: &str) -> Result<Forest<RawNode>, CliError> {
+    Model::parse(BufReader::new(text.as_bytes())).map_err(|e| CliError::Failed(format!("Failed parsing document: {}", e)))
+}
+
+/// Get the number of nodes, leaves and levels of a tree.
+fn tree_stats(tree: &Tree<RawNode>) -> (usize, usize, usize) {
+    let (mut nodes, mut leaves, mut levels) = (0, 0, 0);
+    for (node, _) in tree.iterators().pre_dfs() {
+        nodes += 1;
+        if node.get_children_ref().iter().all(|c| *c >= tree.get_nodes_len()) {
+            leaves += 1;
+        }
+        levels = levels.max(node.get_level());
+    }
+    (nodes, leaves, levels)
+}
+
+fn import_error(e: impl std::fmt::Display) -> CliError {
+    CliError::Failed(format!("Failed reading document: {}", e))
+}
+
+/// Check that a format is known before reading the input.
+fn check_format(format: &str, output: bool) -> Result<(), CliError> {
+    let known = match format {
+        "tref" | "newick" | "nexus" | "sexp" | "markdown" | "org" | "paths" | "csv" => true,
+        "phyloxml" | "xml" => cfg!(feature = "xml"),
+        "json" => cfg!(feature = "json"),
+        "dot" | "mermaid" | "svg" | "text" => output,
+        _ => false
+    };
+    if known {
+        Ok(())
+    }
+    else {
+        Err(CliError::Usage(format!("Unknown {} format `{}`", if output { "output" } else { "input" }, format)))
+    }
+}
+
+fn read_format(format: &str, text: &str, separator: &str) -> Result<Forest<RawNode>, CliError> {
+    match format {
+        "tref" => parse(text),
+        "newick" => formats::newick::read_forest(text).map_err(import_error),
+        "nexus" => formats::nexus::read_forest(text).map_err(import_error),
+        #[cfg(feature = "xml")]
+        "phyloxml" => formats::phyloxml::read_forest(text).map_err(import_error),
+        #[cfg(feature = "xml")]
+        "xml" => formats::xml::read_forest(text, &formats::xml::XmlOptions::new().attributes(true).text(true)).map_err(import_error),
+        #[cfg(feature = "json")]
+        "json" => formats::json::read_forest(text).map_err(import_error),
+        "sexp" => formats::sexp::read_forest(text).map_err(import_error),
+        "markdown" => formats::markdown::read_forest(text).map_err(import_error),
+        "org" => formats::org::read_forest(text).map_err(import_error),
+        "paths" => formats::paths::read_forest(text, separator).map_err(import_error),
+        "csv" => formats::csv::read_forest(text).map_err(import_error),
+        _ => Err(CliError::Usage(format!("Unknown input format `{}`", format)))
+    }
+}
+
+/// Write a phylogenetic format, with branch lengths if all node contents are `length:label`, like its reader creates them.
+fn write_phylogenetic(forest: &Forest<RawNode>, weighted: fn(&Forest<WeightedEdgeNode>) -> String, plain: fn(&Forest<RawNode>) -> String) -> String {
+    match convert_forest(forest, |n: &RawNode| WeightedEdgeNode::try_new(n.get_val())) {
+        Ok(forest) => weighted(&forest),
+        Err(_) => plain(forest)
+    }
+}
+
+fn write_format(format: &str, forest: &Forest<RawNode>, separator: &str) -> Result<String, CliError> {
+    let trees = || sorted_tree_ids(forest).into_iter().filter_map(move |tree_id| forest.get_tree(tree_id));
+    // Formats that only have one tree per document
+    let single = || {
+        let mut trees = trees();
+        match (trees.next(), trees.next()) {
+            (Some(tree), None) => Ok(tree),
+            (None, _) => Err(CliError::Failed(String::from("The document has no trees"))),
+            _ => Err(CliError::Failed(format!("Format `{}` can only write one tree, select it with `--tree`", format)))
+        }
+    };
+    let text = match format {
+        // Sorted by tree ID, so the same forest always produces the same document
+        "tref" => format_forest(forest, "").map_err(|e| CliError::Failed(format!("Failed serializing document: {}", e)))?,
+        "newick" => write_phylogenetic(forest, formats::newick::write_weighted_forest, formats::newick::write_forest),
+        "nexus" => write_phylogenetic(forest, formats::nexus::write_weighted_forest, formats::nexus::write_forest),
+        #[cfg(feature = "xml")]
+        "phyloxml" => write_phylogenetic(forest, formats::phyloxml::write_weighted_forest, formats::phyloxml::write_forest),
+        #[cfg(feature = "xml")]
+        "xml" => formats::xml::write_tree(single()?, &formats::xml::XmlOptions::new().attributes(true).text(true)).map_err(|e| CliError::Failed(format!("Failed writing XML: {}", e)))?,
+        #[cfg(feature = "json")]
+        "json" => formats::json::write_forest(forest),
+        "sexp" => formats::sexp::write_forest(forest),
+        "markdown" => formats::markdown::write_forest(forest),
+        "org" => formats::org::write_forest(forest),
+        "paths" => trees().map(|tree| formats::paths::write_tree(tree, separator)).collect(),
+        "csv" => formats::csv::write_tree(single()?),
+        "dot" => formats::dot::write_forest(forest, &DiagramOptions::new()),
+        "mermaid" => formats::mermaid::write_forest(forest, &DiagramOptions::new()),
+        "svg" => formats::svg::write_tree(single()?, &SvgOptions::new()),
+        "text" => text::write_forest(forest, &TextOptions::new()),
+        _ => return Err(CliError::Usage(format!("Unknown output format `{}`", format)))
+    };
+    Ok(text)
+}
@@ -0,0 +1,513 @@
+use std::fmt;
+use std::collections::HashSet;
+use socarel::{Forest, Tree, NodeContent};
+use crate::walk::*;
+use crate::path::*;
+use crate::diff::*;
+
+/// Conflicting change found by [`merge_forest()`] or [`merge_tree()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    tree_id: String,
+    path: String,
+    message: String
+}
+
+impl Conflict {
+    /// Get ID of the tree with the conflict.
+    pub fn tree_id(&self) -> &str {
+        &self.tree_id
+    }
+
+    /// Get content path of the conflicting node, in the base tree or in their tree for nodes they inserted, or empty for conflicts of whole trees.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}: {}", self.tree_id, self.message)
+        }
+        else {
+            write!(f, "{}: {}: {}", self.tree_id, self.path, self.message)
+        }
+    }
+}
+
+/// Merge two versions of a forest that were changed in parallel from a common base.
+///
+/// Trees are merged by tree ID with [`merge_tree()`]. A tree removed in one version is removed, unless the other version changed it, even if only the order of siblings, and a tree added in both versions must be the same.
+///
+/// # Arguments
+///
+/// * `base` - Common base forest.
+/// * `ours` - Our version.
+/// * `theirs` - Their version.
+///
+/// # Return
+///
+/// * The merged forest, and the conflicts sorted by tree ID. Conflicts are resolved with our version.
+///
+pub fn merge_forest<T: NodeContent>(base: &Forest<T>, ours: &Forest<T>, theirs: &Forest<T>) -> (Forest<T>, Vec<Conflict>) {
+    let mut tree_ids: Vec<&String> = sorted_tree_ids(base);
+    tree_ids.extend(sorted_tree_ids(ours));
+    tree_ids.extend(sorted_tree_ids(theirs));
+    tree_ids.sort();
+    tree_ids.dedup();
+    let mut merged = Forest::new();
+    let mut conflicts = vec!();
+    let conflict = |tree_id: &str, message: &str| Conflict { tree_id: String::from(tree_id), path: String::new(), message: String::from(message) };
+    for tree_id in tree_ids {
+        let tree = match (base.get_tree(tree_id), ours.get_tree(tree_id), theirs.get_tree(tree_id)) {
+            (Some(base_tree), Some(our_tree), Some(their_tree)) => {
+                let (tree, tree_conflicts) = merge_tree(tree_id, base_tree, our_tree, their_tree);
+                conflicts.extend(tree_conflicts);
+                Some(tree)
+            },
+            (Some(base_tree), Some(our_tree), None) => {
+                if same_tree(base_tree, our_tree) {
+                    None
+                }
+                else {
+                    conflicts.push(conflict(tree_id, "Tree removed in theirs and changed in ours"));
+                    Some(copy_tree(our_tree))
+                }
+            },
+            (Some(base_tree), None, Some(their_tree)) => {
+                if !same_tree(base_tree, their_tree) {
+                    conflicts.push(conflict(tree_id, "Tree removed in ours and changed in theirs"));
+                }
+                None
+            },
+            (Some(_), None, None) => None,
+            (None, Some(our_tree), Some(their_tree)) => {
+                if !same_tree(our_tree, their_tree) {
+                    conflicts.push(conflict(tree_id, "Tree added in ours and theirs with different nodes"));
+                }
+                Some(copy_tree(our_tree))
+            },
+            (None, Some(tree), None) | (None, None, Some(tree)) => Some(copy_tree(tree)),
+            (None, None, None) => None
+        };
+        if let Some(tree) = tree {
+            merged.add_tree(tree_id, tree);
+        }
+    }
+    (merged, conflicts)
+}
+
+/// Merge two versions of a tree that were changed in parallel from a common base.
+///
+/// The changes of each version are found like in [`diff_tree()`], and their changes are applied to our version when they don't conflict with ours:
+///
+/// * A node renamed in one version takes the new content. Renaming it to different contents is a conflict.
+/// * Nodes inserted in their version are inserted after the same previous sibling, if any. Inserting the same content under the same node in both versions adds it only once.
+/// * A node moved in one version is moved. Moving it to different parents, or under one of its descendants, is a conflict.
+/// * Siblings reordered in their version take their order, unless our version reordered them differently, which is a conflict.
+/// * A node deleted in one version is deleted, unless the other version renamed it, moved it, changed its children or reordered them, which is a conflict.
+///
+/// ```
+/// use tref::{tref, Tree, RawNode, merge_tree, diff_tree};
+///
+/// let base: Tree<RawNode> = tref!("server" => { "host=localhost", "port=80", "users" => { "admin" } });
+/// let ours: Tree<RawNode> = tref!("server" => { "host=example.com", "port=80", "users" => { "admin", "alice" } });
+/// let theirs: Tree<RawNode> = tref!("server" => { "host=localhost", "port=8080", "users" => { "admin", "bob" } });
+/// let (merged, conflicts) = merge_tree("config", &base, &ours, &theirs);
+/// let expected: Tree<RawNode> = tref!("server" => { "host=example.com", "port=8080", "users" => { "admin", "bob", "alice" } });
+/// assert!(conflicts.is_empty());
+/// assert!(diff_tree("config", &merged, &expected).is_empty());
+/// ```
+///
+/// # Arguments
+///
+/// * `tree_id` - Tree ID, for the conflicts.
+/// * `base` - Common base tree.
+/// * `ours` - Our version.
+/// * `theirs` - Their version.
+///
+/// # Return
+///
+/// * The merged tree, and the conflicts. Conflicts are resolved with our version.
+///
+pub fn merge_tree<T: NodeContent>(tree_id: &str, base: &Tree<T>, ours: &Tree<T>, theirs: &Tree<T>) -> (Tree<T>, Vec<Conflict>) {
+    // Without a common root, only one version can change the tree
+    if base.get_nodes_len() == 0 || ours.get_nodes_len() == 0 || theirs.get_nodes_len() == 0 {
+        if same_tree(base, theirs) || same_tree(ours, theirs) {
+            return (copy_tree(ours), vec!());
+        }
+        if same_tree(base, ours) {
+            return (copy_tree(theirs), vec!());
+        }
+        let conflict = Conflict { tree_id: String::from(tree_id), path: String::new(), message: String::from("Tree emptied in one version and changed in the other") };
+        return (copy_tree(ours), vec!(conflict));
+    }
+    let mut merger = Merger::new(tree_id, base, ours, theirs);
+    merger.merge_renamed();
+    merger.merge_inserted();
+    merger.merge_moved();
+    merger.merge_reordered();
+    merger.merge_deleted();
+    let tree = merger.build();
+    (tree, merger.conflicts)
+}
+
+/// Parent of a node in a version, identified by its base node if it has one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Parent {
+    Base(usize),
+    Ours(usize),
+    Theirs(usize)
+}
+
+/// Node of the merged tree.
+struct MergeNode {
+    content: String,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    removed: bool
+}
+
+/// Applies their changes to our tree. The merged nodes start as our nodes, with the same positions.
+struct Merger<'a, T: NodeContent> {
+    tree_id: &'a str,
+    base: &'a Tree<T>,
+    ours: &'a Tree<T>,
+    theirs: &'a Tree<T>,
+    base_to_ours: Vec<Option<usize>>,
+    base_to_theirs: Vec<Option<usize>>,
+    ours_to_base: Vec<Option<usize>>,
+    theirs_to_base: Vec<Option<usize>>,
+    base_parents: Vec<Option<usize>>,
+    our_parents: Vec<Option<usize>>,
+    their_parents: Vec<Option<usize>>,
+    nodes: Vec<MergeNode>,
+    theirs_to_merged: Vec<Option<usize>>,
+    conflicts: Vec<Conflict>
+}
+
+impl<'a, T: NodeContent> Merger<'a, T> {
+    fn new(tree_id: &'a str, base: &'a Tree<T>, ours: &'a Tree<T>, theirs: &'a Tree<T>) -> Self {
+        let base_to_ours = match_nodes(base, ours);
+        let base_to_theirs = match_nodes(base, theirs);
+        let our_parents = parents(ours);
+        let nodes = (0..ours.get_nodes_len()).map(|position| MergeNode {
+            content: String::from(content(ours, position)),
+            parent: our_parents[position],
+            children: children(ours, position),
+            removed: false
+        }).collect();
+        Self {
+            tree_id,
+            base,
+            ours,
+            theirs,
+            ours_to_base: invert(&base_to_ours, ours.get_nodes_len()),
+            theirs_to_base: invert(&base_to_theirs, theirs.get_nodes_len()),
+            base_to_ours,
+            base_to_theirs,
+            base_parents: parents(base),
+            our_parents,
+            their_parents: parents(theirs),
+            nodes,
+            theirs_to_merged: vec![None; theirs.get_nodes_len()],
+            conflicts: vec!()
+        }
+    }
+
+    fn merge_renamed(&mut self) {
+        for position in pre_order(self.base) {
+            let (our_node, their_node) = match (self.base_to_ours[position], self.base_to_theirs[position]) {
+                (our_node, Some(their_node)) if content(self.theirs, their_node) != content(self.base, position) => (our_node, their_node),
+                _ => continue
+            };
+            let their_content = content(self.theirs, their_node);
+            match our_node {
+                None => self.conflict(self.base, position, format!("Renamed to `{}` in theirs and deleted in ours", their_content)),
+                Some(our_node) => {
+                    let our_content = content(self.ours, our_node);
+                    if our_content == content(self.base, position) {
+                        self.nodes[our_node].content = String::from(their_content);
+                    }
+                    else if our_content != their_content {
+                        self.conflict(self.base, position, format!("Renamed to `{}` in ours and `{}` in theirs", our_content, their_content));
+                    }
+                }
+            }
+        }
+    }
+
+    fn merge_inserted(&mut self) {
+        for position in pre_order(self.theirs) {
+            if self.theirs_to_base[position].is_some() {
+                continue;
+            }
+            let their_parent = match self.their_parents[position] {
+                Some(their_parent) => their_parent,
+                None => continue
+            };
+            let parent = match self.merged_of_theirs(their_parent) {
+                Some(parent) => parent,
+                None => {
+                    // Only report the first inserted node
+                    if self.theirs_to_base[their_parent].is_some() {
+                        self.conflict(self.theirs, position, String::from("Inserted in theirs under a node deleted in ours"));
+                    }
+                    continue;
+                }
+            };
+            let their_content = content(self.theirs, position);
+            // Same node inserted in both versions
+            let ours_len = self.ours.get_nodes_len();
+            let same = self.nodes[parent].children.iter().copied().find(|child| {
+                *child < ours_len && self.ours_to_base[*child].is_none() && self.nodes[*child].content == their_content && !self.theirs_to_merged.contains(&Some(*child))
+            });
+            let merged = match same {
+                Some(merged) => merged,
+                None => {
+                    self.nodes.push(MergeNode { content: String::from(their_content), parent: None, children: vec!(), removed: false });
+                    let merged = self.nodes.len() - 1;
+                    self.attach(merged, parent, position);
+                    merged
+                }
+            };
+            self.theirs_to_merged[position] = Some(merged);
+        }
+    }
+
+    fn merge_moved(&mut self) {
+        for position in pre_order(self.base) {
+            let their_node = match self.base_to_theirs[position] {
+                Some(their_node) => their_node,
+                None => continue
+            };
+            let base_parent = self.base_parents[position].map(Parent::Base);
+            let their_parent = self.their_parents[their_node].map(|p| self.theirs_to_base[p].map(Parent::Base).unwrap_or(Parent::Theirs(p)));
+            if their_parent == base_parent {
+                continue;
+            }
+            let our_node = match self.base_to_ours[position] {
+                Some(our_node) => our_node,
+                None => {
+                    self.conflict(self.base, position, String::from("Moved in theirs and deleted in ours"));
+                    continue;
+                }
+            };
+            let target = self.their_parents[their_node].and_then(|p| self.merged_of_theirs(p));
+            let our_parent = self.our_parents[our_node].map(|p| self.ours_to_base[p].map(Parent::Base).unwrap_or(Parent::Ours(p)));
+            if our_parent != base_parent {
+                if target.is_none() || target != self.nodes[our_node].parent {
+                    self.conflict(self.base, position, String::from("Moved to different nodes in ours and theirs"));
+                }
+                continue;
+            }
+            match target {
+                None => self.conflict(self.base, position, String::from("Moved in theirs under a node deleted in ours")),
+                Some(target) if self.is_ancestor(our_node, target) => self.conflict(self.base, position, String::from("Moved in theirs under a node moved under it in ours")),
+                Some(target) => {
+                    if let Some(old_parent) = self.nodes[our_node].parent {
+                        self.nodes[old_parent].children.retain(|c| *c != our_node);
+                    }
+                    self.attach(our_node, target, their_node);
+                }
+            }
+        }
+    }
+
+    fn merge_reordered(&mut self) {
+        for position in pre_order(self.base) {
+            let their_node = match self.base_to_theirs[position] {
+                Some(their_node) => their_node,
+                None => continue
+            };
+            let (base_order, their_order) = self.kept_order(position, self.theirs, their_node, &self.theirs_to_base);
+            if their_order == base_order {
+                continue;
+            }
+            let our_node = match self.base_to_ours[position] {
+                Some(our_node) => our_node,
+                None => {
+                    self.conflict(self.base, position, String::from("Reordered in theirs and deleted in ours"));
+                    continue;
+                }
+            };
+            let (base_order, our_order) = self.kept_order(position, self.ours, our_node, &self.ours_to_base);
+            // Only the children kept in both versions can take their order
+            let ours_kept: HashSet<usize> = our_order.iter().copied().collect();
+            let theirs_kept: HashSet<usize> = their_order.iter().copied().collect();
+            let their_order: Vec<usize> = their_order.into_iter().filter(|b| ours_kept.contains(b)).collect();
+            if our_order != base_order {
+                let our_order: Vec<usize> = our_order.into_iter().filter(|b| theirs_kept.contains(b)).collect();
+                if our_order != their_order {
+                    self.conflict(self.base, position, String::from("Reordered differently in ours and theirs"));
+                }
+                continue;
+            }
+            // Put their order in the places of the reordered children, keeping the other children in place
+            let ours_len = self.ours.get_nodes_len();
+            let kept: HashSet<usize> = their_order.iter().copied().collect();
+            let reordered: Vec<usize> = their_order.into_iter().filter_map(|b| self.base_to_ours[b]).collect();
+            let mut reordered = reordered.into_iter();
+            let mut children = std::mem::take(&mut self.nodes[our_node].children);
+            for child in children.iter_mut() {
+                if *child < ours_len && self.ours_to_base[*child].map(|b| kept.contains(&b)).unwrap_or(false) {
+                    if let Some(next) = reordered.next() {
+                        *child = next;
+                    }
+                }
+            }
+            self.nodes[our_node].children = children;
+        }
+    }
+
+    fn merge_deleted(&mut self) {
+        let order = self.merged_pre_order();
+        for merged in order.into_iter().rev() {
+            let base_node = match Some(merged).filter(|m| *m < self.ours.get_nodes_len()).and_then(|m| self.ours_to_base[m]) {
+                Some(base_node) if self.base_to_theirs[base_node].is_none() => base_node,
+                _ => continue
+            };
+            let renamed = self.nodes[merged].content != content(self.base, base_node);
+            let moved = self.our_parents[merged].map(|p| self.ours_to_base[p]) != self.base_parents[base_node].map(Some);
+            let kept_children = self.nodes[merged].children.iter().any(|c| !self.nodes[*c].removed);
+            let (base_order, our_order) = self.kept_order(base_node, self.ours, merged, &self.ours_to_base);
+            let reordered = our_order != base_order;
+            if renamed || moved || kept_children || reordered {
+                // Only report the first deleted node, or the ones moved away from it
+                let parent_deleted = self.base_parents[base_node].map(|p| self.base_to_theirs[p].is_none()).unwrap_or(false);
+                if !parent_deleted || moved {
+                    self.conflict(self.base, base_node, String::from("Deleted in theirs and changed in ours"));
+                }
+            }
+            else {
+                self.nodes[merged].removed = true;
+            }
+        }
+        let removed: Vec<bool> = self.nodes.iter().map(|n| n.removed).collect();
+        for node in self.nodes.iter_mut() {
+            node.children.retain(|c| !removed[*c]);
+        }
+    }
+
+    /// Build the merged tree, with the linked nodes that were not removed.
+    fn build(&self) -> Tree<T> {
+        let mut tree = Tree::new();
+        let mut positions = vec![None; self.nodes.len()];
+        for merged in self.merged_pre_order() {
+            if self.nodes[merged].removed {
+                continue;
+            }
+            positions[merged] = match self.nodes[merged].parent.and_then(|p| positions[p]) {
+                Some(parent) => tree.link_node(&self.nodes[merged].content, parent),
+                None if merged == 0 => tree.set_root(&self.nodes[merged].content),
+                None => None
+            };
+        }
+        tree
+    }
+
+    /// Get the base children that a node of a version keeps as children, in the base order and in the order of the version.
+    fn kept_order(&self, base_node: usize, tree: &Tree<T>, position: usize, to_base: &[Option<usize>]) -> (Vec<usize>, Vec<usize>) {
+        let base_children = children(self.base, base_node);
+        let in_base: HashSet<usize> = base_children.iter().copied().collect();
+        let version_order: Vec<usize> = children(tree, position).into_iter().filter_map(|c| to_base[c]).filter(|b| in_base.contains(b)).collect();
+        let in_version: HashSet<usize> = version_order.iter().copied().collect();
+        let base_order = base_children.into_iter().filter(|b| in_version.contains(b)).collect();
+        (base_order, version_order)
+    }
+
+    /// Get merged nodes in pre-order, starting from our root.
+    fn merged_pre_order(&self) -> Vec<usize> {
+        let mut positions = vec!();
+        let mut stack = vec!(0);
+        while let Some(position) = stack.pop() {
+            positions.push(position);
+            stack.extend(self.nodes[position].children.iter().rev());
+        }
+        positions
+    }
+
+    /// Get merged node of their node, None if it was deleted or not inserted.
+    fn merged_of_theirs(&self, position: usize) -> Option<usize> {
+        match self.theirs_to_base[position] {
+            Some(base_node) => self.base_to_ours[base_node],
+            None => self.theirs_to_merged[position]
+        }
+    }
+
+    /// Add a merged node to a parent, after the node that is the previous sibling in their tree, or at the beginning if there is none.
+    fn attach(&mut self, merged: usize, parent: usize, their_node: usize) {
+        let siblings = self.their_parents[their_node].map(|p| children(self.theirs, p)).unwrap_or_default();
+        let index = siblings.iter().position(|s| *s == their_node).unwrap_or(0);
+        let previous = if index > 0 { self.merged_of_theirs(siblings[index - 1]) } else { None };
+        let children = &mut self.nodes[parent].children;
+        let at = match previous {
+            Some(previous) => children.iter().position(|c| *c == previous).map(|i| i + 1).unwrap_or(children.len()),
+            None if index == 0 => 0,
+            None => children.len()
+        };
+        children.insert(at, merged);
+        self.nodes[merged].parent = Some(parent);
+    }
+
+    /// Check if a merged node is an ancestor of another, or the same node.
+    fn is_ancestor(&self, ancestor: usize, position: usize) -> bool {
+        let mut current = Some(position);
+        while let Some(position) = current {
+            if position == ancestor {
+                return true;
+            }
+            current = self.nodes[position].parent;
+        }
+        false
+    }
+
+    fn conflict(&mut self, tree: &Tree<T>, position: usize, message: String) {
+        self.conflicts.push(Conflict {
+            tree_id: String::from(self.tree_id),
+            path: node_path(tree, position).unwrap_or_default(),
+            message
+        });
+    }
+}
+
+/// Invert a map of node positions.
+fn invert(map: &[Option<usize>], len: usize) -> Vec<Option<usize>> {
+    let mut inverted = vec![None; len];
+    for (from, to) in map.iter().enumerate() {
+        if let Some(to) = to {
+            inverted[*to] = Some(from);
+        }
+    }
+    inverted
+}
+
+fn content<T: NodeContent>(tree: &Tree<T>, position: usize) -> &str {
+    tree.get_nodes_ref()[position].get_content_ref().get_val()
+}
+
+/// Check if two trees have the same nodes, with siblings in the same order.
+fn same_tree<T: NodeContent>(old: &Tree<T>, new: &Tree<T>) -> bool {
+    let nodes = |tree: &Tree<T>| pre_order(tree).into_iter().map(|p| (content(tree, p).to_string(), children(tree, p).len())).collect::<Vec<(String, usize)>>();
+    nodes(old) == nodes(new)
+}
+
+/// Copy the linked nodes of a tree.
+fn copy_tree<T: NodeContent>(tree: &Tree<T>) -> Tree<T> {
+    let mut copy = Tree::new();
+    let tree_parents = parents(tree);
+    let mut positions = vec![None; tree.get_nodes_len()];
+    for position in pre_order(tree) {
+        positions[position] = match tree_parents[position].and_then(|p| positions[p]) {
+            Some(parent) => copy.link_node(content(tree, position), parent),
+            None => copy.set_root(content(tree, position))
+        };
+    }
+    copy
+}
@@ -0,0 +1,309 @@
+use std::any::Any;
+use std::collections::HashMap as Map;
+use std::io::{prelude::*, BufReader, BufWriter};
+use socarel::{Tree, NodeContent, RawNode};
+use crate::builder::*;
+use crate::parser::*;
+use crate::model::*;
+use crate::error::*;
+use crate::dialect::*;
+use crate::pattern::*;
+
+type BuilderFactory = fn(&str) -> Box<dyn DynTreeBuilder>;
+
+/// Document interaction model for documents where each tree uses its own dialect.
+///
+/// Dialects are registered for a tree ID or for a tree ID pattern. Trees that don't match any registration use the default dialect, [`RawNode`] unless changed with [`MixedModel::default_dialect()`].
+///
+/// ```
+/// use std::io::BufReader;
+/// use tref::{MixedModel, dialects::{IntegerNode, KeyValueNode}};
+///
+/// let tref =
+/// "[numbers]\n\
+/// + 1000\n\
+/// + + 800\n\
+/// [config_db]\n\
+/// + host=localhost\n\
+/// + + port=5432\n";
+///
+/// let mut model = MixedModel::new();
+/// model.dialect::<IntegerNode>("numbers");
+/// model.dialect_pattern::<KeyValueNode>("config_*");
+///
+/// let forest = model.parse(BufReader::new(tref.as_bytes())).unwrap();
+/// let numbers = forest.get_tree::<IntegerNode>("numbers").unwrap();
+/// assert_eq!(numbers.get_node_content(1).unwrap().get_num(), 800);
+/// let config = forest.get_tree::<KeyValueNode>("config_db").unwrap();
+/// assert_eq!(config.get_node_content(1).unwrap().get_value(), "5432");
+/// ```
+pub struct MixedModel {
+    dialects: Map<String, BuilderFactory>,
+    patterns: Vec<(String, BuilderFactory)>,
+    default: BuilderFactory
+}
+
+impl MixedModel {
+    /// Create new mixed model, without registered dialects.
+    pub fn new() -> Self {
+        Self {
+            dialects: Map::new(),
+            patterns: vec!(),
+            default: new_dyn_builder::<RawNode>
+        }
+    }
+
+    /// Register the dialect of a tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `tree_id` - Tree ID.
+    ///
+    /// # Return
+    ///
+    /// * The model itself.
+    ///
+    pub fn dialect<T: TryNodeContent + 'static>(&mut self, tree_id: &str) -> &mut Self {
+        self.dialects.insert(String::from(tree_id), new_dyn_builder::<T>);
+        self
+    }
+
+    /// Register the dialect of all trees with an ID that matches a pattern.
+    ///
+    /// In the pattern, `*` matches any sequence of characters and `?` matches one character. Patterns are checked in the order they were registered, after the tree IDs registered with [`MixedModel::dialect()`].
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - Tree ID pattern.
+    ///
+    /// # Return
+    ///
+    /// * The model itself.
+    ///
+    pub fn dialect_pattern<T: TryNodeContent + 'static>(&mut self, pattern: &str) -> &mut Self {
+        self.patterns.push((String::from(pattern), new_dyn_builder::<T>));
+        self
+    }
+
+    /// Set the dialect of trees that don't match any registration.
+    ///
+    /// # Return
+    ///
+    /// * The model itself.
+    ///
+    pub fn default_dialect<T: TryNodeContent + 'static>(&mut self) -> &mut Self {
+        self.default = new_dyn_builder::<T>;
+        self
+    }
+
+    /// Parse TREF document.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - BufReader to read the document.
+    ///
+    /// # Return
+    ///
+    /// * A [`Result`] with a [`MixedForest`] or a [`ParseTreeError`].
+    ///
+    pub fn parse(&self, reader: BufReader<impl Read>) -> Result<MixedForest, ParseTreeError> {
+        let mut forest = MixedForest::new();
+        parse_document(reader, |tree_id| self.factory(tree_id)(tree_id), |builder| {
+            let tree_id = String::from(builder.tree_id());
+            forest.trees.insert(tree_id, builder.finish());
+        })?;
+        Result::Ok(forest)
+    }
+
+    /// Convert a MixedForest structure into a TREF document, with the trees sorted by tree ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `forest` - Reference to a `MixedForest`.
+    /// * `writer` - BufWriter where to write the TREF.
+    ///
+    /// # Return
+    ///
+    /// * A [`Result`] with a number of lines writen or a [`SerializeTreeError`].
+    ///
+    pub fn serialize(forest: &MixedForest, writer: &mut BufWriter<impl Write>) -> Result<usize, SerializeTreeError> {
+        let parser = TreeParser::new();
+        let mut num_lines_writen = 0;
+        let mut tree_ids: Vec<&String> = forest.trees.keys().collect();
+        tree_ids.sort();
+        for tree_id in tree_ids {
+            forest.trees[tree_id].serialize(&parser, tree_id, writer, &mut num_lines_writen)?;
+        }
+
+        if writer.flush().is_err() {
+            Result::Err(SerializeTreeError::new("Writer flush failed", num_lines_writen, None))
+        }
+        else {
+            Ok(num_lines_writen)
+        }
+    }
+
+    fn factory(&self, tree_id: &str) -> BuilderFactory {
+        if let Some(factory) = self.dialects.get(tree_id) {
+            return *factory;
+        }
+        for (pattern, factory) in self.patterns.iter() {
+            if wildcard_match(pattern, tree_id) {
+                return *factory;
+            }
+        }
+        self.default
+    }
+}
+
+impl Default for MixedModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Set of trees where each tree can use a different dialect.
+///
+/// Trees are accessed indicating the dialect type, and only returned if the tree uses it.
+pub struct MixedForest {
+    trees: Map<String, Box<dyn DialectTree>>
+}
+
+impl MixedForest {
+    /// Create new mixed forest.
+    pub fn new() -> Self {
+        Self {
+            trees: Map::new()
+        }
+    }
+
+    /// Add a tree, replacing any tree with the same ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Tree ID.
+    /// * `tree` - Tree.
+    ///
+    pub fn add_tree<T: NodeContent + 'static>(&mut self, name: &str, tree: Tree<T>) {
+        self.trees.insert(String::from(name), Box::new(tree));
+    }
+
+    /// Remove a tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Tree ID.
+    ///
+    /// # Return
+    ///
+    /// * True if the tree existed.
+    ///
+    pub fn remove_tree(&mut self, name: &str) -> bool {
+        self.trees.remove(name).is_some()
+    }
+
+    /// Get a tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Tree ID.
+    ///
+    /// # Return
+    ///
+    /// * The tree, or None if it doesn't exist or uses a different dialect.
+    ///
+    pub fn get_tree<T: NodeContent + 'static>(&self, name: &str) -> Option<&Tree<T>> {
+        self.trees.get(name).and_then(|t| t.as_any().downcast_ref())
+    }
+
+    /// Get a mutable tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Tree ID.
+    ///
+    /// # Return
+    ///
+    /// * The tree, or None if it doesn't exist or uses a different dialect.
+    ///
+    pub fn get_mut_tree<T: NodeContent + 'static>(&mut self, name: &str) -> Option<&mut Tree<T>> {
+        self.trees.get_mut(name).and_then(|t| t.as_any_mut().downcast_mut())
+    }
+
+    /// Check if a tree uses a dialect.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Tree ID.
+    ///
+    /// # Return
+    ///
+    /// * True if the tree exists and uses dialect `T`.
+    ///
+    pub fn is_dialect<T: NodeContent + 'static>(&self, name: &str) -> bool {
+        self.get_tree::<T>(name).is_some()
+    }
+
+    /// Iterate over tree IDs.
+    pub fn tree_ids(&self) -> impl Iterator<Item = &str> {
+        self.trees.keys().map(|k| k.as_str())
+    }
+}
+
+impl Default for MixedForest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tree of any dialect.
+trait DialectTree {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn serialize(&self, parser: &TreeParser, tree_id: &str, writer: &mut dyn Write, num_lines_writen: &mut usize) -> Result<(), SerializeTreeError>;
+}
+
+impl<T: NodeContent + 'static> DialectTree for Tree<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn serialize(&self, parser: &TreeParser, tree_id: &str, writer: &mut dyn Write, num_lines_writen: &mut usize) -> Result<(), SerializeTreeError> {
+        serialize_tree(parser, tree_id, self, writer, num_lines_writen)
+    }
+}
+
+/// Tree builder of any dialect.
+trait DynTreeBuilder {
+    fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError>;
+    fn tree_id(&self) -> &str;
+    fn finish(self: Box<Self>) -> Box<dyn DialectTree>;
+}
+
+impl<T: TryNodeContent + 'static> DynTreeBuilder for TreeBuilder<T> {
+    fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError> {
+        TreeBuilder::add_node(self, content, level, line)
+    }
+
+    fn tree_id(&self) -> &str {
+        TreeBuilder::tree_id(self)
+    }
+
+    fn finish(self: Box<Self>) -> Box<dyn DialectTree> {
+        Box::new(TreeBuilder::finish(*self))
+    }
+}
+
+impl NodeSink for Box<dyn DynTreeBuilder> {
+    fn add_node(&mut self, content: &str, level: usize, line: usize) -> Result<(), ParseTreeError> {
+        self.as_mut().add_node(content, level, line)
+    }
+}
+
+fn new_dyn_builder<T: TryNodeContent + 'static>(tree_id: &str) -> Box<dyn DynTreeBuilder> {
+    Box::new(TreeBuilder::<T>::checked(tree_id))
+}
@@ -64,16 +64,11 @@ impl TreeParser {
         else if self.comment_matcher.is_match(statement) {
             TreeStatement::Comment
         }
+        else if statement.trim().is_empty() {
+            TreeStatement::Empty
+        }
         else {
-            if statement.len() == 0 {
-                TreeStatement::Empty
-            }
-            else if statement.trim().len() == 0 {
-                TreeStatement::Empty
-            }
-            else {
-                TreeStatement::Invalid
-            }
+            TreeStatement::Invalid
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+use socarel::{Tree, NodeContent};
+use crate::error::*;
+use crate::walk::*;
+
+/// Separator of the node contents in a content path, like `base > branch_2 > branch_2_1`.
+pub const PATH_SEPARATOR: &str = " > ";
+
+/// Node chosen when a content path matches more than one node, because of siblings with the same content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// The first matching node in pre-order.
+    First,
+    /// The last matching node in pre-order.
+    Last,
+    /// Fail if more than one node matches.
+    Unique
+}
+
+/// Find a node by its content path, the contents of the nodes from the root to it.
+///
+/// ```
+/// use tref::{tref, Tree, RawNode, find_path, node_path, DuplicatePolicy};
+///
+/// let tree: Tree<RawNode> = tref!("base" => { "branch_1" => { "leaf" }, "branch_2" => { "branch_2_1" } });
+/// let position = find_path(&tree, "base > branch_2 > branch_2_1", DuplicatePolicy::Unique).unwrap();
+/// assert_eq!(position, 4);
+/// assert_eq!(node_path(&tree, position).unwrap(), "base > branch_2 > branch_2_1");
+/// ```
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `path` - Content path, with the contents separated by [`PATH_SEPARATOR`].
+/// * `policy` - Node chosen when the path matches more than one node.
+///
+/// # Return
+///
+/// * A [`Result`] with the node position, or a [`PathError`] if no node matches, or more than one does with [`DuplicatePolicy::Unique`].
+///
+pub fn find_path<T: NodeContent>(tree: &Tree<T>, path: &str, policy: DuplicatePolicy) -> Result<usize, PathError> {
+    let contents: Vec<&str> = path.split(PATH_SEPARATOR).collect();
+    find_path_contents(tree, &contents, policy)
+}
+
+/// Find a node by the contents of the nodes from the root to it.
+///
+/// Like [`find_path()`], for contents that contain the [`PATH_SEPARATOR`].
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `contents` - Node contents, starting with the root.
+/// * `policy` - Node chosen when the path matches more than one node.
+///
+/// # Return
+///
+/// * A [`Result`] with the node position, or a [`PathError`] if no node matches, or more than one does with [`DuplicatePolicy::Unique`].
+///
+pub fn find_path_contents<T: NodeContent>(tree: &Tree<T>, contents: &[&str], policy: DuplicatePolicy) -> Result<usize, PathError> {
+    let content_of = |position: usize| tree.get_nodes_ref()[position].get_content_ref().get_val();
+    // Nodes matching the path so far, in pre-order
+    let mut matches: Vec<usize> = match (pre_order(tree).first(), contents.first()) {
+        (Some(root), Some(content)) if content_of(*root) == *content => vec!(*root),
+        _ => vec!()
+    };
+    for content in contents.iter().skip(1) {
+        matches = matches.into_iter().flat_map(|parent| children(tree, parent)).filter(|child| content_of(*child) == *content).collect();
+    }
+    let path = contents.join(PATH_SEPARATOR);
+    match (matches.as_slice(), policy) {
+        ([], _) => Err(PathError::new("Node not found", &path, 0)),
+        ([position], _) => Ok(*position),
+        (_, DuplicatePolicy::Unique) => Err(PathError::new("Ambiguous path, siblings with the same content", &path, matches.len())),
+        (_, DuplicatePolicy::First) => Ok(matches[0]),
+        (_, DuplicatePolicy::Last) => Ok(matches[matches.len() - 1])
+    }
+}
+
+/// Get the content path of a node.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `position` - Node position.
+///
+/// # Return
+///
+/// * An [`Option`] with the contents of the nodes from the root to the node, separated by [`PATH_SEPARATOR`], or None if the node doesn't exist or was unlinked.
+///
+pub fn node_path<T: NodeContent>(tree: &Tree<T>, position: usize) -> Option<String> {
+    node_path_contents(tree, position).map(|contents| contents.join(PATH_SEPARATOR))
+}
+
+/// Get the contents of the nodes from the root to a node.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `position` - Node position.
+///
+/// # Return
+///
+/// * An [`Option`] with the node contents, starting with the root, or None if the node doesn't exist or was unlinked.
+///
+pub fn node_path_contents<T: NodeContent>(tree: &Tree<T>, position: usize) -> Option<Vec<String>> {
+    let positions = position_path(tree, position);
+    // Unlinked nodes keep their parent, but the parent doesn't have them as children
+    let linked = positions.first() == Some(&0) && positions.windows(2).all(|pair| children(tree, pair[0]).contains(&pair[1]));
+    if linked {
+        Some(content_path(tree, position))
+    }
+    else {
+        None
+    }
+}
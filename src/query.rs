@@ -0,0 +1,298 @@
+use socarel::{Forest, Tree, NodeContent};
+use crate::pattern::*;
+use crate::error::*;
+use crate::walk::*;
+
+/// Select nodes of a forest with a query.
+///
+/// A query is a tree ID followed by a path of steps separated by `/`, like `my_tree/root/*/child_1`:
+///
+/// * The tree ID and the steps are wildcard patterns, where `*` matches any sequence of characters and `?` matches one character. The first step matches the root node, and each of the following steps matches children of the nodes selected by the previous one.
+/// * A step preceded by `//` matches any descendant, instead of only children. A query that starts with `//`, like `//branch_2_*`, searches all trees. A query without `/` is the same as starting it with `//`.
+/// * Steps can have conditions between brackets, like `*[level=3]`, that compare the `level` of the node (1 for the root), the number of `children` or the node `position` with `=`, `!=`, `<`, `>`, `<=` or `>=`. A step with only conditions matches any content, so `[level=3]` selects all nodes at level 3.
+/// * A `\` before `/`, `[` or `\` matches the character literally, like `dirs/usr\/bin`, to select nodes that contain them.
+///
+/// Patterns are matched against the node content, [`get_val()`][`NodeContent::get_val()`].
+///
+/// ```
+/// use tref::{tref, Forest, RawNode, query_forest};
+///
+/// let mut forest: Forest<RawNode> = Forest::new();
+/// forest.add_tree("my_tree", tref!("root" => { "branch_1" => { "child_1" }, "branch_2" => { "child_1", "child_2" } }));
+/// assert_eq!(query_forest(&forest, "my_tree/root/*/child_1").unwrap(), vec!(("my_tree", 2), ("my_tree", 4)));
+/// assert_eq!(query_forest(&forest, "//branch_2").unwrap(), vec!(("my_tree", 3)));
+/// assert_eq!(query_forest(&forest, "[children=0]").unwrap().len(), 3);
+/// ```
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+/// * `query` - Query.
+///
+/// # Return
+///
+/// * A [`Result`] with the tree ID and position of the selected nodes, sorted by tree ID and in pre-order, or a [`QueryError`] if the query is not valid.
+///
+pub fn query_forest<'a, T: NodeContent>(forest: &'a Forest<T>, query: &str) -> Result<Vec<(&'a str, usize)>, QueryError> {
+    let (tree_pattern, steps) = if query.starts_with("//") {
+        (String::from("*"), parse_path(query, 0)?)
+    }
+    else if let Some(slash) = find_separator(query) {
+        if slash == 0 {
+            return Err(QueryError::new("Missing tree ID", 0));
+        }
+        (unescape(&query[..slash]), parse_path(&query[slash..], query[..slash].chars().count())?)
+    }
+    else {
+        (String::from("*"), parse_path(query, 0)?)
+    };
+    let mut selected = vec!();
+    for (tree_id, tree) in sorted_trees(forest) {
+        if wildcard_match(&tree_pattern, tree_id) {
+            selected.extend(select(tree, &steps).into_iter().map(|position| (tree_id, position)));
+        }
+    }
+    Ok(selected)
+}
+
+/// Select nodes of a tree with a query.
+///
+/// The query is a path of steps, like in [`query_forest()`] but without the tree ID, like `root/*/child_1`, `//branch_2_*` or `[level=3]`.
+///
+/// # Arguments
+///
+/// * `tree` - Tree.
+/// * `query` - Query.
+///
+/// # Return
+///
+/// * A [`Result`] with the positions of the selected nodes in pre-order, or a [`QueryError`] if the query is not valid.
+///
+pub fn query_tree<T: NodeContent>(tree: &Tree<T>, query: &str) -> Result<Vec<usize>, QueryError> {
+    Ok(select(tree, &parse_path(query, 0)?))
+}
+
+/// Query methods for forests and trees.
+///
+/// Shortcut for [`query_forest()`] and [`query_tree()`], implemented for references to [`Forest`] and [`Tree`].
+///
+/// ```
+/// use tref::{tref, Forest, RawNode, Query};
+///
+/// let mut forest: Forest<RawNode> = Forest::new();
+/// forest.add_tree("my_tree", tref!("root" => { "usr/bin" => { "ls" }, "usr/lib" }));
+/// assert_eq!(forest.query("my_tree/root/usr\\/*").unwrap(), vec!(("my_tree", 1), ("my_tree", 3)));
+/// assert_eq!(forest.get_tree("my_tree").unwrap().query("//ls").unwrap(), vec!(2));
+/// ```
+pub trait Query {
+    /// Nodes selected by a query.
+    type Selection;
+
+    /// Select nodes with a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query.
+    ///
+    /// # Return
+    ///
+    /// * A [`Result`] with the selected nodes, like [`query_forest()`] or [`query_tree()`] return, or a [`QueryError`] if the query is not valid.
+    ///
+    fn query(self, query: &str) -> Result<Self::Selection, QueryError>;
+}
+
+impl<'a, T: NodeContent> Query for &'a Forest<T> {
+    type Selection = Vec<(&'a str, usize)>;
+
+    fn query(self, query: &str) -> Result<Self::Selection, QueryError> {
+        query_forest(self, query)
+    }
+}
+
+impl<T: NodeContent> Query for &Tree<T> {
+    type Selection = Vec<usize>;
+
+    fn query(self, query: &str) -> Result<Self::Selection, QueryError> {
+        query_tree(self, query)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Axis {
+    Child,
+    Descendant
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Attribute {
+    Level,
+    Children,
+    Position
+}
+
+/// Condition of a step, like `level>2`.
+struct Condition {
+    attribute: Attribute,
+    operator: &'static str,
+    value: usize
+}
+
+struct Step {
+    axis: Axis,
+    pattern: String,
+    conditions: Vec<Condition>
+}
+
+impl Step {
+    fn matches<T: NodeContent>(&self, tree: &Tree<T>, position: usize) -> bool {
+        let node = &tree.get_nodes_ref()[position];
+        wildcard_match(&self.pattern, node.get_content_ref().get_val()) && self.conditions.iter().all(|condition| {
+            let value = match condition.attribute {
+                Attribute::Level => node.get_level(),
+                Attribute::Children => children(tree, position).len(),
+                Attribute::Position => position
+            };
+            match condition.operator {
+                "=" => value == condition.value,
+                "!=" => value != condition.value,
+                "<" => value < condition.value,
+                ">" => value > condition.value,
+                "<=" => value <= condition.value,
+                _ => value >= condition.value
+            }
+        })
+    }
+}
+
+/// Parse the steps of a path, where `offset` is the position of the path in the whole query.
+fn parse_path(path: &str, offset: usize) -> Result<Vec<Step>, QueryError> {
+    let chars: Vec<char> = path.chars().collect();
+    if chars.is_empty() {
+        return Err(QueryError::new("Empty query", offset));
+    }
+    let mut steps = vec!();
+    let mut i = 0;
+    // A path without separators searches all nodes
+    let mut axis = if find_separator(path).is_some() { Axis::Child } else { Axis::Descendant };
+    loop {
+        // Separator before the step
+        if chars[i..].starts_with(&['/', '/']) {
+            axis = Axis::Descendant;
+            i += 2;
+        }
+        else if chars[i] == '/' {
+            i += 1;
+        }
+        let start = i;
+        let mut pattern = String::new();
+        while i < chars.len() && chars[i] != '/' && chars[i] != '[' {
+            if is_escape(&chars[i..]) {
+                i += 1;
+            }
+            pattern.push(chars[i]);
+            i += 1;
+        }
+        let mut conditions = vec!();
+        while i < chars.len() && chars[i] == '[' {
+            let end = match chars[i..].iter().position(|c| *c == ']') {
+                Some(end) => i + end,
+                None => return Err(QueryError::new("Unclosed `[`", offset + i))
+            };
+            let condition: String = chars[i + 1..end].iter().collect();
+            conditions.push(parse_condition(&condition, offset + i + 1)?);
+            i = end + 1;
+        }
+        if i < chars.len() && chars[i] != '/' {
+            return Err(QueryError::new(&format!("Unexpected `{}` after condition", chars[i]), offset + i));
+        }
+        if pattern.is_empty() && conditions.is_empty() {
+            return Err(QueryError::new("Empty step", offset + start));
+        }
+        if pattern.is_empty() {
+            pattern.push('*');
+        }
+        steps.push(Step { axis, pattern, conditions });
+        if i == chars.len() {
+            return Ok(steps);
+        }
+        axis = Axis::Child;
+    }
+}
+
+/// Check if the text starts with an escaped character, like `\/`.
+fn is_escape(chars: &[char]) -> bool {
+    chars.len() > 1 && chars[0] == '\\' && ['/', '[', '\\'].contains(&chars[1])
+}
+
+/// Find the byte position of the first `/` that is not escaped.
+fn find_separator(text: &str) -> Option<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    let mut position = 0;
+    while i < chars.len() {
+        if chars[i] == '/' {
+            return Some(position);
+        }
+        let len = if is_escape(&chars[i..]) { 2 } else { 1 };
+        position += chars[i..i + len].iter().map(|c| c.len_utf8()).sum::<usize>();
+        i += len;
+    }
+    None
+}
+
+/// Remove the escapes of a pattern.
+fn unescape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut unescaped = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if is_escape(&chars[i..]) {
+            i += 1;
+        }
+        unescaped.push(chars[i]);
+        i += 1;
+    }
+    unescaped
+}
+
+fn parse_condition(condition: &str, offset: usize) -> Result<Condition, QueryError> {
+    let (attribute, operator, value) = match ["<=", ">=", "!=", "=", "<", ">"].iter().find_map(|op| condition.split_once(op).map(|(a, v)| (a, *op, v))) {
+        Some(parts) => parts,
+        None => return Err(QueryError::new(&format!("Invalid condition `{}`, expected an attribute, an operator and a number", condition), offset))
+    };
+    let attribute = match attribute.trim() {
+        "level" => Attribute::Level,
+        "children" => Attribute::Children,
+        "position" => Attribute::Position,
+        other => return Err(QueryError::new(&format!("Unknown attribute `{}`", other), offset))
+    };
+    match value.trim().parse() {
+        Ok(value) => Ok(Condition { attribute, operator, value }),
+        Err(_) => Err(QueryError::new(&format!("Invalid number `{}`", value.trim()), offset))
+    }
+}
+
+/// Get the nodes selected by the steps, in pre-order.
+fn select<T: NodeContent>(tree: &Tree<T>, steps: &[Step]) -> Vec<usize> {
+    let order = pre_order(tree);
+    // Context nodes of each step, None is the parent of the root
+    let mut context: Vec<Option<usize>> = vec!(None);
+    for step in steps {
+        let mut selected = vec![false; tree.get_nodes_len()];
+        for parent in context {
+            let candidates = match (step.axis, parent) {
+                (Axis::Child, None) => order.first().copied().into_iter().collect(),
+                (Axis::Child, Some(parent)) => children(tree, parent),
+                (Axis::Descendant, None) => order.clone(),
+                (Axis::Descendant, Some(parent)) => descendants(tree, parent)
+            };
+            for candidate in candidates {
+                if step.matches(tree, candidate) {
+                    selected[candidate] = true;
+                }
+            }
+        }
+        context = order.iter().filter(|p| selected[**p]).map(|p| Some(*p)).collect();
+    }
+    context.into_iter().flatten().collect()
+}
@@ -0,0 +1,466 @@
+//! Serialize Rust values into TREF documents.
+//!
+//! Requires the `serde` feature.
+//!
+//! The top level value must be a struct or a map. Each field becomes a tree, with the field name as tree ID and as root node, and the value as children of the root. Values are mapped as follows:
+//!
+//! - Scalars: one child node with the value.
+//! - Structs and maps: one child node per field, with the value as children of the field node.
+//! - Sequences: one child node per element. Scalars are written directly, other values as children of a `-` node.
+//! - `None` and unit: no child nodes.
+//! - Enums: unit variants as scalars, other variants as a node with the variant name and the value as children.
+//!
+//! Strings that can't be written as a node, like empty strings or strings with newlines, are quoted using the same format of [`QuotedNode`][`crate::dialects::QuotedNode`], with `\n`, `\r` and `\t` escapes.
+//!
+//! ```
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Server {
+//!     host: String,
+//!     ports: Vec<u16>
+//! }
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Config {
+//!     server: Server,
+//!     debug: bool
+//! }
+//!
+//! let config = Config {
+//!     server: Server { host: String::from("localhost"), ports: vec!(80, 443) },
+//!     debug: true
+//! };
+//!
+//! let tref = tref::to_string(&config).unwrap();
+//! assert_eq!(tref,
+//! "[server]\n\
+//! + server\n\
+//! + + host\n\
+//! + + + localhost\n\
+//! + + ports\n\
+//! + + + 80\n\
+//! + + + 443\n\
+//! [debug]\n\
+//! + debug\n\
+//! + + true\n");
+//!
+//! let config_prima: Config = tref::from_str(&tref).unwrap();
+//! assert_eq!(config, config_prima);
+//! ```
+
+use std::io::BufWriter;
+use serde::ser::{self, Serialize};
+use socarel::{Tree, RawNode};
+use crate::parser::*;
+use crate::model::*;
+use crate::error::*;
+
+/// Serialize a value into a TREF document.
+///
+/// # Arguments
+///
+/// * `value` - Value to serialize, a struct or a map.
+///
+/// # Return
+///
+/// * A [`Result`] with the TREF document or a [`SerdeTreeError`].
+///
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, SerdeTreeError> {
+    let repr = value.serialize(ReprSerializer)?;
+    if repr.kind != Kind::Map && !repr.nodes.is_empty() {
+        return Err(SerdeTreeError::new("Top level value must be a struct or a map", None));
+    }
+
+    let parser = TreeParser::new();
+    let mut num_lines_writen = 0;
+    let mut writer = BufWriter::new(Vec::new());
+    for branch in repr.nodes {
+        let tree_id = unquote(&branch.content).map_err(|e| SerdeTreeError::new(&e, None))?;
+        let mut tree = Tree::<RawNode>::new();
+        if let Some(root) = tree.set_root(&branch.content) {
+            link_branches(&mut tree, root, &branch.children);
+        }
+        serialize_tree(&parser, &tree_id, &tree, &mut writer, &mut num_lines_writen).map_err(|e| SerdeTreeError::new(&e.to_string(), None))?;
+    }
+
+    match writer.into_inner() {
+        Ok(bytes) => String::from_utf8(bytes).map_err(|e| SerdeTreeError::new(&e.to_string(), None)),
+        Err(e) => Err(SerdeTreeError::new(&e.to_string(), None))
+    }
+}
+
+fn link_branches(tree: &mut Tree<RawNode>, parent: usize, branches: &[Branch]) {
+    for branch in branches {
+        if let Some(position) = tree.link_node(&branch.content, parent) {
+            link_branches(tree, position, &branch.children);
+        }
+    }
+}
+
+/// Quote a string if it can't be used as node content as is.
+pub(crate) fn quote(text: &str) -> String {
+    let needs_quotes = text.is_empty()
+        || text == "-"
+        || text.starts_with(|c: char| c == '+' || c == '"' || c.is_whitespace())
+        || text.ends_with(char::is_whitespace)
+        || text.contains(['\n', '\r']);
+    if !needs_quotes {
+        return String::from(text);
+    }
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Get the string in a node content, unquoting it if necessary.
+pub(crate) fn unquote(content: &str) -> Result<String, String> {
+    let mut chars = content.chars();
+    if chars.next() != Some('"') {
+        return Ok(String::from(content));
+    }
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some('t') => text.push('\t'),
+                _ => return Err(format!("Invalid escape sequence in `{}`", content))
+            },
+            '"' => {
+                if chars.next().is_some() {
+                    return Err(format!("Unexpected characters after closing quote in `{}`", content));
+                }
+                return Ok(text);
+            },
+            _ => text.push(c)
+        }
+    }
+    Err(format!("Missing closing quote in `{}`", content))
+}
+
+/// Node of the intermediate representation, with its content already quoted.
+struct Branch {
+    content: String,
+    children: Vec<Branch>
+}
+
+impl Branch {
+    fn new(content: String, children: Vec<Branch>) -> Self {
+        Self { content, children }
+    }
+}
+
+#[derive(PartialEq)]
+enum Kind {
+    Scalar,
+    Empty,
+    Map,
+    Seq
+}
+
+/// A serialized value, the nodes it generates as children of its parent.
+struct Repr {
+    kind: Kind,
+    nodes: Vec<Branch>
+}
+
+impl Repr {
+    fn scalar(text: &str) -> Self {
+        Self { kind: Kind::Scalar, nodes: vec!(Branch::new(quote(text), vec!())) }
+    }
+
+    fn empty() -> Self {
+        Self { kind: Kind::Empty, nodes: vec!() }
+    }
+
+    fn variant(variant: &str, value: Repr) -> Self {
+        Self { kind: Kind::Map, nodes: vec!(Branch::new(quote(variant), value.nodes)) }
+    }
+
+    /// Convert into a sequence element.
+    fn into_element(self) -> Branch {
+        match self.kind {
+            Kind::Scalar => self.nodes.into_iter().next().unwrap_or_else(|| Branch::new(String::from("-"), vec!())),
+            _ => Branch::new(String::from("-"), self.nodes)
+        }
+    }
+
+    /// Convert into a map key.
+    fn into_key(self) -> Result<String, SerdeTreeError> {
+        match (self.kind, self.nodes.into_iter().next()) {
+            (Kind::Scalar, Some(branch)) => Ok(branch.content),
+            _ => Err(SerdeTreeError::new("Map keys must be scalars", None))
+        }
+    }
+}
+
+struct ReprSerializer;
+
+impl ser::Serializer for ReprSerializer {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+    type SerializeSeq = Compound;
+    type SerializeTuple = Compound;
+    type SerializeTupleStruct = Compound;
+    type SerializeTupleVariant = Compound;
+    type SerializeMap = Compound;
+    type SerializeStruct = Compound;
+    type SerializeStructVariant = Compound;
+
+    fn serialize_bool(self, v: bool) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(&v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr { kind: Kind::Seq, nodes: v.iter().map(|b| Branch::new(b.to_string(), vec!())).collect() })
+    }
+
+    fn serialize_none(self) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::empty())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Repr, SerdeTreeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::empty())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::empty())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::scalar(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Repr, SerdeTreeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<Repr, SerdeTreeError> {
+        Ok(Repr::variant(variant, value.serialize(ReprSerializer)?))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Seq, None))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Seq, None))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Seq, None))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Seq, Some(variant)))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Map, None))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Map, None))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<Compound, SerdeTreeError> {
+        Ok(Compound::new(Kind::Map, Some(variant)))
+    }
+}
+
+/// Serializer of sequences, maps, structs and their enum variants.
+struct Compound {
+    repr: Repr,
+    variant: Option<&'static str>,
+    key: Option<String>
+}
+
+impl Compound {
+    fn new(kind: Kind, variant: Option<&'static str>) -> Self {
+        Self { repr: Repr { kind, nodes: vec!() }, variant, key: None }
+    }
+
+    fn push_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeTreeError> {
+        let element = value.serialize(ReprSerializer)?.into_element();
+        self.repr.nodes.push(element);
+        Ok(())
+    }
+
+    fn push_field<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), SerdeTreeError> {
+        let value = value.serialize(ReprSerializer)?;
+        self.repr.nodes.push(Branch::new(key, value.nodes));
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Repr, SerdeTreeError> {
+        match self.variant {
+            Some(variant) => Ok(Repr::variant(variant, self.repr)),
+            None => Ok(self.repr)
+        }
+    }
+}
+
+impl ser::SerializeSeq for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeTreeError> {
+        self.push_element(value)
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeTreeError> {
+        self.push_element(value)
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeTreeError> {
+        self.push_element(value)
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeTreeError> {
+        self.push_element(value)
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeMap for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), SerdeTreeError> {
+        self.key = Some(key.serialize(ReprSerializer)?.into_key()?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), SerdeTreeError> {
+        match self.key.take() {
+            Some(key) => self.push_field(key, value),
+            None => Err(SerdeTreeError::new("Map value without key", None))
+        }
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeTreeError> {
+        self.push_field(quote(key), value)
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for Compound {
+    type Ok = Repr;
+    type Error = SerdeTreeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), SerdeTreeError> {
+        self.push_field(quote(key), value)
+    }
+
+    fn end(self) -> Result<Repr, SerdeTreeError> {
+        self.finish()
+    }
+}
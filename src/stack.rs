@@ -38,10 +38,6 @@ impl NodeStack {
         None
     }
 
-    pub fn flush(&mut self) {
-        self.buffer.truncate(0);
-    }
-
     pub fn top(&mut self) -> Option<&NodeStackContent> {
         self.buffer.last()
     }
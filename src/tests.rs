@@ -24,31 +24,9 @@ fn parse_valid_tref() {
     match <Model>::parse(tref_sample()) {
         Ok(forest) => {
             if let Some(tree) = forest.get_tree("test_tree") {
+                let expected = ["root_node", "child_1", "child_2", "child_2_1", "child_2_1_1", "child_2_2", "child_3"];
                 for (i, (n, _)) in tree.iterators().sequential().enumerate() {
-                    match i {
-                        0 => {
-                            if !n.get_content_ref().get_val().eq("root_node") { panic!("Wrong root_node content") }
-                        },
-                        1 => {
-                            if !n.get_content_ref().get_val().eq("child_1") { panic!("Wrong child_1 content"); }
-                        },
-                        2 => {
-                            if !n.get_content_ref().get_val().eq("child_2") { panic!("Wrong child_2 content"); }
-                        },
-                        3 => {
-                            if !n.get_content_ref().get_val().eq("child_2_1") { panic!("Wrong child_2_1 content"); }
-                        },
-                        4 => {
-                            if !n.get_content_ref().get_val().eq("child_2_1_1") { panic!("Wrong child_2_1_1 content"); }
-                        },
-                        5 => {
-                            if !n.get_content_ref().get_val().eq("child_2_2") { panic!("Wrong child_2_2 content"); }
-                        },
-                        6 => {                          
-                            if !n.get_content_ref().get_val().eq("child_3") { panic!("Wrong child_3 content"); }
-                        }
-                        _ => {}
-                    }
+                    if i < expected.len() && !n.get_content_ref().get_val().eq(expected[i]) { panic!("Wrong {} content", expected[i]); }
                 }
             }
             else {
@@ -69,7 +47,7 @@ fn parse_missing_tree_id() {
     + + child_2\n";
     let tref_reader = BufReader::new(tref.as_bytes());
 
-    if let Ok(_) = <Model>::parse(tref_reader) {
+    if <Model>::parse(tref_reader).is_ok() {
         panic!("Parsed without tree id");
     }
 }
@@ -83,7 +61,7 @@ fn parse_invalid_statement() {
     + + child_2\n";
     let tref_reader = BufReader::new(tref.as_bytes());
 
-    if let Ok(_) = <Model>::parse(tref_reader) {
+    if <Model>::parse(tref_reader).is_ok() {
         panic!("Parsed an invalid statement");
     }
 }
@@ -97,7 +75,7 @@ fn parse_invalid_level() {
     + + child_2\n";
     let tref_reader = BufReader::new(tref.as_bytes());
 
-    if let Ok(_) = <Model>::parse(tref_reader) {
+    if <Model>::parse(tref_reader).is_ok() {
         panic!("Parsed an invalid level");
     }
 }
@@ -219,7 +197,7 @@ fn parse_dialect() {
                         },
                         2 => {
                             if !n.get_content_ref().get_val().eq("2500") { panic!("Wrong 2500 content"); }
-                            if let TypedNode::Text(_) = n.get_content_ref() {
+                            if !matches!(n.get_content_ref(), TypedNode::Number(_, 2500)) {
                                 panic!("Wrong 1500 node type")
                             }
                         },
@@ -243,4 +221,1320 @@ fn parse_dialect() {
             panic!("Failed parsing document: {}", e);
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn parse_dialect_error() {
+    struct IntegerNode(String);
+
+    impl NodeContent for IntegerNode {
+        fn new(content: &str) -> Option<Self> {
+            Self::try_new(content).ok()
+        }
+
+        fn get_val(&self) -> &str {
+            &self.0
+        }
+
+        fn gen_content(&self) -> String {
+            String::from(self.get_val())
+        }
+    }
+
+    impl TryNodeContent for IntegerNode {
+        fn try_new(content: &str) -> Result<Self, String> {
+            match content.parse::<i32>() {
+                Ok(_) => Ok(Self(String::from(content))),
+                Err(_) => Err(String::from("Not an integer"))
+            }
+        }
+    }
+
+    let tref =
+    "[test_tree]\n\
+    + 100\n\
+    + + 200\n\
+    + + abc\n";
+
+    match Model::<IntegerNode>::try_parse(BufReader::new(tref.as_bytes())) {
+        Ok(_) => {
+            panic!("Parsed an invalid dialect node");
+        },
+        Err(e) => {
+            if e.line() != 3 { panic!("Wrong error line {}", e.line()); }
+            if e.content() != Some("abc") { panic!("Wrong error content {:?}", e.content()); }
+            if !e.to_string().contains("Not an integer") { panic!("Missing dialect message: {}", e); }
+        }
+    }
+}
+
+#[test]
+fn parse_dialect_context() {
+    struct HostPortNode(String);
+
+    impl NodeContent for HostPortNode {
+        fn new(content: &str) -> Option<Self> {
+            Some(Self(String::from(content)))
+        }
+
+        fn get_val(&self) -> &str {
+            &self.0
+        }
+
+        fn gen_content(&self) -> String {
+            String::from(self.get_val())
+        }
+    }
+
+    impl TryNodeContent for HostPortNode {
+        fn validate_in(content: &str, context: &NodeContext) -> Result<(), String> {
+            match context.level() {
+                1 => Ok(()),
+                2 => match content.parse::<u16>() {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(format!("Invalid port for host {}", context.parent().unwrap_or_default()))
+                },
+                _ => Err(format!("Too deep in tree {}", context.tree_id()))
+            }
+        }
+    }
+
+    let tref =
+    "[hosts]\n\
+    + example.com\n\
+    + + 80\n\
+    + + 443\n";
+
+    if let Err(e) = Model::<HostPortNode>::try_parse(BufReader::new(tref.as_bytes())) {
+        panic!("Failed parsing document: {}", e);
+    }
+
+    let tref =
+    "[hosts]\n\
+    + example.com\n\
+    + + 80\n\
+    + + http\n";
+
+    match Model::<HostPortNode>::try_parse(BufReader::new(tref.as_bytes())) {
+        Ok(_) => {
+            panic!("Parsed an invalid port");
+        },
+        Err(e) => {
+            if e.line() != 3 { panic!("Wrong error line {}", e.line()); }
+            if !e.to_string().contains("Invalid port for host example.com") { panic!("Missing dialect message: {}", e); }
+        }
+    }
+}
+
+fn dialect_round_trip<T: TryNodeContent>(tref: &str) -> Forest<T> {
+    let forest = match Model::<T>::try_parse(BufReader::new(tref.as_bytes())) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    let mut buf_writer = BufWriter::new(Vec::new());
+    if let Err(e) = Model::<T>::serialize(&forest, &mut buf_writer) {
+        panic!("Failed serializing: {}", e);
+    }
+    let bytes = buf_writer.into_inner().unwrap();
+    match Model::<T>::try_parse(BufReader::new(&bytes[..])) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing serialized document: {}", e)
+    }
+}
+
+#[test]
+fn built_in_dialects() {
+    use crate::dialects::*;
+
+    let forest = dialect_round_trip::<IntegerNode>("[t]\n+ 1000\n+ + -800\n+ + 25 \n");
+    let tree = forest.get_tree("t").unwrap();
+    if tree.get_node_content(2).unwrap().get_num() != 25 { panic!("Wrong integer"); }
+
+    let forest = dialect_round_trip::<FloatNode>("[t]\n+ 1.5\n+ + 1e3\n");
+    let tree = forest.get_tree("t").unwrap();
+    if tree.get_node_content(1).unwrap().get_num() != 1000.0 { panic!("Wrong float"); }
+
+    let forest = dialect_round_trip::<BooleanNode>("[t]\n+ true\n+ + false\n");
+    let tree = forest.get_tree("t").unwrap();
+    if tree.get_node_content(1).unwrap().get_bool() { panic!("Wrong boolean"); }
+
+    let forest = dialect_round_trip::<KeyValueNode>("[t]\n+ name = my app\n+ + url=http://a.com/?x=1\n");
+    let tree = forest.get_tree("t").unwrap();
+    let node = tree.get_node_content(0).unwrap();
+    if node.get_key() != "name" || node.get_value() != "my app" { panic!("Wrong key-value"); }
+    let node = tree.get_node_content(1).unwrap();
+    if node.get_key() != "url" || node.get_value() != "http://a.com/?x=1" { panic!("Wrong key-value"); }
+
+    let forest = dialect_round_trip::<PropertyNode>("[t]\n+ server:\n+ + port: 8080\n");
+    let tree = forest.get_tree("t").unwrap();
+    let node = tree.get_node_content(1).unwrap();
+    if node.get_key() != "port" || node.get_value() != "8080" { panic!("Wrong property"); }
+
+    let forest = dialect_round_trip::<WeightedEdgeNode>("[t]\n+ 0:root_node\n+ + 27:child_1\n+ + 0.5:child:2\n");
+    let tree = forest.get_tree("t").unwrap();
+    let node = tree.get_node_content(2).unwrap();
+    if node.get_weight() != 0.5 || node.get_name() != "child:2" { panic!("Wrong weighted edge"); }
+
+    let forest = dialect_round_trip::<QuotedNode>("[t]\n+ \"root\"\n+ + \"say \\\"hi\\\" \\\\o/\"\n");
+    let tree = forest.get_tree("t").unwrap();
+    if tree.get_node_content(1).unwrap().get_text() != "say \"hi\" \\o/" { panic!("Wrong quoted string"); }
+
+    let forest = dialect_round_trip::<FileNode>("[t]\n+ dir:0:src\n+ + file:1234:main.rs\n+ + link:0:a:b\n");
+    let tree = forest.get_tree("t").unwrap();
+    let node = tree.get_node_content(1).unwrap();
+    if node.get_kind() != FileKind::File || node.get_size() != 1234 || node.get_name() != "main.rs" { panic!("Wrong file node"); }
+    if tree.get_node_content(2).unwrap().get_name() != "a:b" { panic!("Wrong file name"); }
+
+    for tref in ["[t]\n+ 1.5\n", "[t]\n+ abc\n"].iter() {
+        if Model::<IntegerNode>::parse(BufReader::new(tref.as_bytes())).is_ok() { panic!("Parsed an invalid integer"); }
+    }
+    for tref in ["[t]\n+ =value\n", "[t]\n+ value\n"].iter() {
+        if Model::<KeyValueNode>::parse(BufReader::new(tref.as_bytes())).is_ok() { panic!("Parsed an invalid key-value"); }
+    }
+    for tref in ["[t]\n+ child_1\n", "[t]\n+ x:child_1\n"].iter() {
+        if Model::<WeightedEdgeNode>::parse(BufReader::new(tref.as_bytes())).is_ok() { panic!("Parsed an invalid weighted edge"); }
+    }
+    for tref in ["[t]\n+ pipe:0:x\n", "[t]\n+ file:-1:x\n", "[t]\n+ file:0:\n"].iter() {
+        if Model::<FileNode>::parse(BufReader::new(tref.as_bytes())).is_ok() { panic!("Parsed an invalid file node"); }
+    }
+    for tref in ["[t]\n+ \"open\n", "[t]\n+ \"a\" b\n", "[t]\n+ \"\\n\"\n", "[t]\n+ plain\n"].iter() {
+        if Model::<QuotedNode>::parse(BufReader::new(tref.as_bytes())).is_ok() { panic!("Parsed an invalid quoted string"); }
+    }
+}
+
+#[test]
+fn parse_mixed_dialects() {
+    use crate::dialects::*;
+
+    let tref =
+    "[numbers]\n\
+    + 1000\n\
+    + + 800\n\
+    [config_db]\n\
+    + host=localhost\n\
+    + + port=5432\n\
+    [config_web]\n\
+    + host=example.com\n\
+    [notes]\n\
+    + anything\n";
+
+    let mut model = MixedModel::new();
+    model.dialect::<IntegerNode>("numbers").dialect_pattern::<KeyValueNode>("config_*");
+
+    let forest = match model.parse(BufReader::new(tref.as_bytes())) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    if forest.get_tree::<IntegerNode>("numbers").unwrap().get_node_content(1).unwrap().get_num() != 800 { panic!("Wrong integer tree"); }
+    if forest.get_tree::<KeyValueNode>("config_web").unwrap().get_node_content(0).unwrap().get_value() != "example.com" { panic!("Wrong key-value tree"); }
+    if !forest.is_dialect::<RawNode>("notes") { panic!("Wrong default dialect"); }
+    if forest.get_tree::<RawNode>("numbers").is_some() { panic!("Got tree with wrong dialect"); }
+
+    let mut buf_writer = BufWriter::new(Vec::new());
+    if let Err(e) = MixedModel::serialize(&forest, &mut buf_writer) {
+        panic!("Failed serializing: {}", e);
+    }
+    let bytes = buf_writer.into_inner().unwrap();
+    let tree_ids: Vec<String> = String::from_utf8_lossy(&bytes).lines().filter(|l| l.starts_with('[')).map(String::from).collect();
+    if tree_ids != ["[config_db]", "[config_web]", "[notes]", "[numbers]"] { panic!("Trees not sorted by tree ID: {:?}", tree_ids); }
+    match model.parse(BufReader::new(&bytes[..])) {
+        Ok(forest_prima) => {
+            if forest_prima.tree_ids().count() != 4 { panic!("Wrong number of trees"); }
+            if !forest_prima.is_dialect::<KeyValueNode>("config_db") { panic!("Wrong dialect after serializing"); }
+        },
+        Err(e) => panic!("Failed parsing serialized document: {}", e)
+    }
+
+    let tref = "[numbers]\n+ 1000\n+ + abc\n";
+    match model.parse(BufReader::new(tref.as_bytes())) {
+        Ok(_) => panic!("Parsed an invalid integer"),
+        Err(e) => if e.line() != 2 { panic!("Wrong error line {}", e.line()); }
+    }
+}
+
+#[test]
+fn wildcard_patterns() {
+    use crate::pattern::wildcard_match;
+
+    if !wildcard_match("config_*", "config_db") { panic!("Pattern should match"); }
+    if !wildcard_match("*_?", "branch_2") { panic!("Pattern should match"); }
+    if !wildcard_match("a*b*c", "axxbyybc") { panic!("Pattern should match"); }
+    if !wildcard_match("*", "") { panic!("Pattern should match"); }
+    if !wildcard_match("*b", "*xb") { panic!("Pattern should match"); }
+    if wildcard_match("config_*", "my_config") { panic!("Pattern shouldn't match"); }
+    if wildcard_match("branch_?", "branch_22") { panic!("Pattern shouldn't match"); }
+}
+
+#[test]
+fn convert_dialects() {
+    use crate::dialects::*;
+
+    let mut forest = match <Model>::parse(tref_sample()) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    // Unlinked nodes are not converted
+    forest.get_mut_tree("test_tree").unwrap().unlink_node(3);
+
+    let quoted = match convert_forest(&forest, |n: &RawNode| QuotedNode::try_new(&format!("\"{}\"", n.get_val()))) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed converting: {}", e)
+    };
+    let tree = quoted.get_tree("test_tree").unwrap();
+    let contents: Vec<&str> = crate::walk::pre_order(tree).into_iter().map(|p| tree.get_node_content(p).unwrap().get_text()).collect();
+    if contents != vec!("root_node", "child_1", "child_2", "child_2_2", "child_3") { panic!("Wrong converted tree {:?}", contents); }
+
+    match convert_forest(&forest, |n: &RawNode| IntegerNode::try_new(n.get_val())) {
+        Ok(_) => panic!("Converted invalid integers"),
+        Err(e) => {
+            if e.tree_id() != Some("test_tree") { panic!("Wrong error tree"); }
+            if e.path() != ["root_node"] { panic!("Wrong error path {:?}", e.path()); }
+        }
+    }
+
+    match convert_tree(forest.get_tree("test_tree").unwrap(), |n: &RawNode| IntegerNode::try_new(n.get_val())) {
+        Ok(_) => panic!("Converted invalid integers"),
+        Err(e) => if e.tree_id().is_some() || e.to_string().contains("in tree") { panic!("Wrong error without tree: {}", e); }
+    }
+
+    let tref = "[t]\n+ 1\n+ + 2\n+ + + x\n";
+    let forest = <Model>::parse(BufReader::new(tref.as_bytes())).unwrap();
+    match convert_forest(&forest, |n: &RawNode| IntegerNode::try_new(n.get_val())) {
+        Ok(_) => panic!("Converted invalid integers"),
+        Err(e) => if e.path() != ["1", "2", "x"] { panic!("Wrong error path {:?}", e.path()); }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use std::collections::BTreeMap;
+    use serde::{Serialize, Deserialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Rect { w: u32, h: u32 }
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item {
+        name: String,
+        tags: Vec<String>,
+        shape: Shape
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Doc {
+        title: String,
+        count: Option<u32>,
+        missing: Option<u32>,
+        items: Vec<Item>,
+        shapes: Vec<Shape>,
+        matrix: Vec<Vec<i8>>,
+        names: BTreeMap<String, (u8, char)>
+    }
+
+    let mut names = BTreeMap::new();
+    names.insert(String::from("first one"), (1, 'a'));
+    names.insert(String::from("+second"), (2, ' '));
+    let doc = Doc {
+        title: String::from("  Multi\nline \"title\" "),
+        count: Some(7),
+        missing: None,
+        items: vec!(
+            Item { name: String::from("-"), tags: vec!(), shape: Shape::Empty },
+            Item { name: String::from(""), tags: vec!(String::from("x"), String::from("y")), shape: Shape::Rect { w: 2, h: 3 } }
+        ),
+        shapes: vec!(Shape::Empty, Shape::Circle(1.5)),
+        matrix: vec!(vec!(1, -2), vec!(), vec!(3)),
+        names
+    };
+
+    let tref = match crate::to_string(&doc) {
+        Ok(tref) => tref,
+        Err(e) => panic!("Failed serializing: {}", e)
+    };
+    if let Err(e) = <Model>::parse(BufReader::new(tref.as_bytes())) {
+        panic!("Serialized an invalid document: {}\n{}", e, tref);
+    }
+    match crate::from_str::<Doc>(&tref) {
+        Ok(doc_prima) => if doc != doc_prima { panic!("Wrong deserialized value {:?}\n{}", doc_prima, tref) },
+        Err(e) => panic!("Failed deserializing: {}\n{}", e, tref)
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_errors() {
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16
+    }
+
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Config {
+        server: Server
+    }
+
+    let tref =
+    "[server]\n\
+    + server\n\
+    + + host\n\
+    + + + localhost\n\
+    + + port\n\
+    + + + http\n";
+    match crate::from_str::<Config>(tref) {
+        Ok(_) => panic!("Deserialized an invalid port"),
+        Err(e) => if e.line() != Some(5) { panic!("Wrong error line: {}", e) }
+    }
+
+    let tref =
+    "[server]\n\
+    + server\n\
+    + + host\n\
+    + + + localhost\n";
+    match crate::from_str::<Config>(tref) {
+        Ok(_) => panic!("Deserialized without port"),
+        Err(e) => if e.line() != Some(1) || !e.to_string().contains("port") { panic!("Wrong error: {}", e) }
+    }
+
+    match crate::from_str::<Config>("[server]\n+ server\n+ + + host\n") {
+        Ok(_) => panic!("Deserialized an invalid document"),
+        Err(e) => if e.line() != Some(2) { panic!("Wrong error line: {}", e) }
+    }
+
+    if crate::to_string(&vec!(1, 2)).is_ok() { panic!("Serialized a top level sequence"); }
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_dialect() {
+    #[derive(TrefNode, Debug)]
+    #[tref(format = "{weight}:{name}")]
+    struct EdgeNode {
+        weight: u32,
+        name: String,
+        content: String
+    }
+
+    #[derive(TrefNode, Debug)]
+    #[tref(format = "<{x}, {y}> {{{label}}}")]
+    struct PointNode {
+        x: i32,
+        y: i32,
+        label: String,
+        #[tref(content)]
+        raw: String
+    }
+
+    let forest = dialect_round_trip::<EdgeNode>("[t]\n+ 0:root\n+ + 27:child:1\n");
+    let node = forest.get_tree("t").unwrap().get_node_content(1).unwrap();
+    if node.weight != 27 || node.name != "child:1" || node.content != "27:child:1" { panic!("Wrong derived node {:?}", node); }
+
+    let forest = dialect_round_trip::<PointNode>("[t]\n+ <1, -2> {origin}\n");
+    let node = forest.get_tree("t").unwrap().get_node_content(0).unwrap();
+    if node.x != 1 || node.y != -2 || node.label != "origin" || node.raw != "<1, -2> {origin}" { panic!("Wrong derived node {:?}", node); }
+
+    for content in ["27", "-1:child"].iter() {
+        if EdgeNode::try_new(content).is_ok() { panic!("Parsed invalid content `{}`", content); }
+    }
+    for content in ["<1, 2> origin", "<1 2> {origin}", "<1, 2> {origin} more", "1, 2> {origin}"].iter() {
+        if PointNode::try_new(content).is_ok() { panic!("Parsed invalid content `{}`", content); }
+    }
+    match EdgeNode::try_new("x:child") {
+        Ok(_) => panic!("Parsed an invalid weight"),
+        Err(e) => if !e.contains("weight") { panic!("Wrong error message {}", e); }
+    }
+
+    #[derive(TrefNode, Debug)]
+    #[tref(format = "{name}:{weight}")]
+    struct NamedNode {
+        name: String,
+        weight: u32,
+        content: String
+    }
+
+    for name in ["a:b", "a\\", "\\:", "::"].iter() {
+        let node = NamedNode { name: String::from(*name), weight: 7, content: String::new() };
+        match NamedNode::try_new(&node.gen_content()) {
+            Ok(parsed) => if parsed.name != *name || parsed.weight != 7 { panic!("Wrong round trip of `{}`: {:?}", name, parsed); },
+            Err(e) => panic!("Failed parsing generated content `{}`: {}", node.gen_content(), e)
+        }
+    }
+    let node = NamedNode::try_new("a\\:b:1").unwrap();
+    if node.name != "a:b" || node.weight != 1 { panic!("Wrong escaped node {:?}", node); }
+    let node = NamedNode::try_new("a\\b:1").unwrap();
+    if node.name != "a\\b" || node.weight != 1 { panic!("Wrong unescaped backslash {:?}", node); }
+}
+
+#[test]
+fn tref_macro() {
+    use crate::dialects::*;
+
+    let tree: Tree<RawNode> = tref!(
+        "root_node" => {
+            "child_1",
+            "child_2" => {
+                "child_2_1" => { "child_2_1_1" },
+                "child_2_2",
+            },
+            "child_3"
+        }
+    );
+    let forest = <Model>::parse(tref_sample()).unwrap();
+    let sample = forest.get_tree("test_tree").unwrap();
+    if tree.get_nodes_len() != sample.get_nodes_len() { panic!("Wrong number of nodes"); }
+    for ((n, _), (m, _)) in tree.iterators().pre_dfs().zip(sample.iterators().pre_dfs()) {
+        if n.get_content_ref().get_val() != m.get_content_ref().get_val() || n.get_level() != m.get_level() {
+            panic!("Wrong node {}", n.get_content_ref().get_val());
+        }
+    }
+
+    let leaf = 130;
+    let forest: Forest<IntegerNode> = tref!(
+        ["numbers"] 1000 => { 800 => { 2500, leaf } }
+        ["single"] (-5)
+    );
+    if forest.get_tree("numbers").unwrap().get_node_content(3).unwrap().get_num() != 130 { panic!("Wrong variable node"); }
+    if forest.get_tree("single").unwrap().get_node_content(0).unwrap().get_num() != -5 { panic!("Wrong expression node"); }
+
+    let result = std::panic::catch_unwind(|| {
+        let _: Tree<IntegerNode> = tref!(1 => { "abc" });
+    });
+    if result.is_ok() { panic!("Built a tree with an invalid node"); }
+}
+
+#[test]
+fn newick_conversion() {
+    use crate::formats::newick;
+    use crate::dialects::WeightedEdgeNode;
+
+    let text = "((A:0.5,B:1.5)'x y':2,'it''s':3)root;";
+    let tree: Tree<WeightedEdgeNode> = newick::read_tree(text).expect("Failed reading weighted Newick");
+    if tree.get_nodes_len() != 5 { panic!("Wrong number of nodes"); }
+    let root = tree.get_node_content(0).unwrap();
+    if root.get_weight() != 0.0 || root.get_name() != "root" { panic!("Wrong root node"); }
+    let inner = tree.get_node_content(1).unwrap();
+    if inner.get_weight() != 2.0 || inner.get_name() != "x y" { panic!("Wrong quoted node"); }
+    if tree.get_node_content(4).unwrap().get_name() != "it's" { panic!("Wrong escaped quote"); }
+    if newick::write_weighted_tree(&tree) != text { panic!("Weighted round trip failed"); }
+
+    // Unnamed nodes, comments and multiple trees
+    let forest: Forest<RawNode> = newick::read_forest("((A,B)[comment],C);\n(D);\n").expect("Failed reading Newick forest");
+    let tree = forest.get_tree("tree_1").unwrap();
+    if tree.get_node_content(0).unwrap().get_val() != "_" { panic!("Wrong unnamed node"); }
+    if tree.get_node_content(2).unwrap().get_val() != "A" { panic!("Wrong leaf node"); }
+    if newick::write_forest(&forest) != "((A,B),C);\n(D);\n" { panic!("Forest round trip failed"); }
+    let mut with_empty: Forest<RawNode> = Forest::new();
+    with_empty.add_tree("empty", Tree::new());
+    with_empty.add_tree("tree", tref!("D"));
+    if newick::write_forest(&with_empty) != "D;\n" { panic!("Empty tree not skipped: {}", newick::write_forest(&with_empty)); }
+    let tree: Tree<RawNode> = newick::read_tree("((_,B)__,);").expect("Failed reading underscore labels");
+    if tree.get_node_content(1).unwrap().get_val() != "___" || tree.get_node_content(2).unwrap().get_val() != "__" { panic!("Underscore labels not escaped"); }
+    if tree.get_node_content(4).unwrap().get_val() != "_" { panic!("Wrong unnamed leaf"); }
+    if newick::write_tree(&tree) != "((_,B)__,);" { panic!("Underscore labels round trip failed: {}", newick::write_tree(&tree)); }
+
+    // Errors
+    if newick::read_tree::<RawNode>("(A,B").is_ok() { panic!("Read unclosed tree"); }
+    if newick::read_tree::<RawNode>("(A,B);(C);").is_ok() { panic!("Read two trees as one"); }
+    if newick::read_tree::<RawNode>("(A:x,B);").is_ok() { panic!("Read invalid branch length"); }
+    match newick::read_tree::<WeightedEdgeNode>("(A,B);") {
+        Ok(_) => panic!("Read weighted tree without lengths"),
+        Err(e) => if e.content() != Some("_") { panic!("Wrong error content {:?}", e.content()); }
+    }
+    match newick::read_tree::<RawNode>("(A,\nB\n") {
+        Err(e) => if e.line() != Some(2) { panic!("Wrong error line {:?}", e.line()); },
+        Ok(_) => panic!("Read unclosed tree")
+    }
+}
+
+#[test]
+fn deep_tree_conversion() {
+    use crate::formats::{newick, nexus, sexp};
+
+    // Caterpillar trees deeper than the stack allows for recursion
+    let depth = 100000;
+    let text = format!("{}A{};", "(".repeat(depth), ",B)".repeat(depth));
+    let tree: Tree<RawNode> = newick::read_tree(&text).expect("Failed reading deep Newick");
+    if tree.get_nodes_len() != 2 * depth + 1 { panic!("Wrong number of nodes in deep Newick"); }
+    if newick::write_tree(&tree) != text { panic!("Deep Newick round trip failed"); }
+
+    let text = format!("#NEXUS\nBEGIN TREES;\nTRANSLATE 1 A;\nTREE deep = {}1{};\nEND;\n", "(".repeat(depth), ",B)".repeat(depth));
+    let forest: Forest<RawNode> = nexus::read_forest(&text).expect("Failed reading deep Nexus");
+    if forest.get_tree("deep").unwrap().get_nodes_len() != 2 * depth + 1 { panic!("Wrong number of nodes in deep Nexus"); }
+
+    let text = format!("{}A{}\n", "(_ ".repeat(depth), " B)".repeat(depth));
+    let tree: Tree<RawNode> = sexp::read_tree(&text).expect("Failed reading deep S-expression");
+    if tree.get_nodes_len() != 2 * depth + 1 { panic!("Wrong number of nodes in deep S-expression"); }
+    if sexp::write_tree(&tree) != text { panic!("Deep S-expression round trip failed"); }
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn deep_xml_conversion() {
+    use crate::formats::{newick, phyloxml, sexp, xml::{self, XmlOptions}};
+
+    // XML formats indent each level, so a shallower tree keeps the document small
+    let depth = 2000;
+    let mut forest: Forest<RawNode> = Forest::new();
+    forest.add_tree("deep", newick::read_tree(&format!("{}A{};", "(".repeat(depth), ",B)".repeat(depth))).unwrap());
+    let xml = phyloxml::write_forest(&forest);
+    let read: Forest<RawNode> = phyloxml::read_forest(&xml).expect("Failed reading deep PhyloXML");
+    if newick::write_forest(&read) != newick::write_forest(&forest) { panic!("Deep PhyloXML round trip failed"); }
+
+    let tree: Tree<RawNode> = sexp::read_tree(&format!("{}a{}", "(e ".repeat(depth), " b)".repeat(depth))).unwrap();
+    let options = XmlOptions::new();
+    let read: Tree<RawNode> = xml::read_tree(&xml::write_tree(&tree, &options).unwrap(), &options).expect("Failed reading deep XML");
+    if sexp::write_tree(&read) != sexp::write_tree(&tree) { panic!("Deep XML round trip failed"); }
+}
+
+#[test]
+fn nexus_conversion() {
+    use crate::formats::nexus;
+
+    let text = "#NEXUS\n\
+    BEGIN TAXA;\n\
+        DIMENSIONS NTAX=3;\n\
+        TAXLABELS A B 'C;D';\n\
+    END;\n\
+    BEGIN TREES;\n\
+        TRANSLATE 1 A, 2 B, 3 'C;D';\n\
+        TREE first = [&R] ((1,2),3);\n\
+        TREE 'second tree' = (A,B)root;\n\
+    END;\n";
+    let forest: Forest<RawNode> = nexus::read_forest(text).expect("Failed reading Nexus");
+    let first = forest.get_tree("first").expect("Missing first tree");
+    if first.get_node_content(4).unwrap().get_val() != "C;D" { panic!("Translate table not applied"); }
+    let second = forest.get_tree("second tree").expect("Missing second tree");
+    if second.get_node_content(0).unwrap().get_val() != "root" { panic!("Wrong root node"); }
+
+    let expected = "#NEXUS\nBEGIN TREES;\n\tTREE first = ((A,B),'C;D');\n\tTREE 'second tree' = (A,B)root;\nEND;\n";
+    if nexus::write_forest(&forest) != expected { panic!("Wrong Nexus document: {}", nexus::write_forest(&forest)); }
+    let mut with_empty: Forest<RawNode> = Forest::new();
+    with_empty.add_tree("empty", Tree::new());
+    if nexus::write_forest(&with_empty) != "#NEXUS\nBEGIN TREES;\nEND;\n" { panic!("Empty tree not skipped: {}", nexus::write_forest(&with_empty)); }
+
+    if nexus::read_forest::<RawNode>("#NEXUS\nBEGIN TAXA;\nEND;\n").is_ok() { panic!("Read document without trees"); }
+    if nexus::read_forest::<RawNode>("#NEXUS\nBEGIN TREES;\nTREE a (A,B);\nEND;\n").is_ok() { panic!("Read tree without `=`"); }
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn phyloxml_conversion() {
+    use crate::formats::phyloxml;
+    use crate::dialects::WeightedEdgeNode;
+
+    let text = r#"<?xml version="1.0" encoding="UTF-8"?>
+<phyloxml xmlns="http://www.phyloxml.org">
+  <phylogeny rooted="true">
+    <name>named</name>
+    <clade>
+      <name>root</name>
+      <clade branch_length="1.5">
+        <name>A &amp; B</name>
+        <clade><name>A</name><branch_length>0.5</branch_length></clade>
+      </clade>
+    </clade>
+  </phylogeny>
+  <phylogeny rooted="true">
+    <clade><clade><name>C</name></clade></clade>
+  </phylogeny>
+</phyloxml>"#;
+    let forest: Forest<RawNode> = phyloxml::read_forest(text).expect("Failed reading PhyloXML");
+    let named = forest.get_tree("named").expect("Missing named tree");
+    if named.get_node_content(0).unwrap().get_val() != "0:root" { panic!("Wrong root node"); }
+    if named.get_node_content(1).unwrap().get_val() != "1.5:A & B" { panic!("Wrong inner node"); }
+    if named.get_node_content(2).unwrap().get_val() != "0.5:A" { panic!("Wrong leaf node"); }
+    let unnamed = forest.get_tree("tree_2").expect("Missing unnamed tree");
+    if unnamed.get_node_content(0).unwrap().get_val() != "_" { panic!("Wrong unnamed node"); }
+
+    // Round trip
+    let mut weighted = Forest::new();
+    weighted.add_tree("named", tref!("0:root" => { "1.5:A & B" => { "0.5:A" } }));
+    let written = phyloxml::write_weighted_forest(&weighted);
+    if !written.contains("<name>A &amp; B</name>") { panic!("Name not escaped: {}", written); }
+    let mut underscores = Forest::new();
+    underscores.add_tree("t", tref!("0:_" => { "1:__" }));
+    let written_underscores = phyloxml::write_weighted_forest(&underscores);
+    if written_underscores.matches("<name>").count() != 2 || !written_underscores.contains("<name>_</name>") { panic!("Wrong underscore names: {}", written_underscores); }
+    let read_back: Forest<RawNode> = phyloxml::read_forest(&written_underscores).expect("Failed reading underscore names");
+    let tree = read_back.get_tree("t").unwrap();
+    if tree.get_node_content(0).unwrap().get_val() != "0:_" || tree.get_node_content(1).unwrap().get_val() != "1:__" { panic!("Underscore names round trip failed"); }
+    let read_back: Forest<WeightedEdgeNode> = phyloxml::read_forest(&written).expect("Failed reading written PhyloXML");
+    let tree = read_back.get_tree("named").unwrap();
+    let original = weighted.get_tree("named").unwrap();
+    for position in 0..original.get_nodes_len() {
+        if tree.get_node_content(position).unwrap().get_val() != original.get_node_content(position).unwrap().get_val() {
+            panic!("Round trip failed at node {}", position);
+        }
+    }
+
+    // Empty trees
+    let mut empty: Forest<RawNode> = Forest::new();
+    empty.add_tree("empty", Tree::new());
+    let read_back: Forest<RawNode> = phyloxml::read_forest(&phyloxml::write_forest(&empty)).expect("Failed reading empty tree");
+    if read_back.get_tree("empty").map(|t| t.get_nodes_len()) != Some(0) { panic!("Empty tree round trip failed"); }
+
+    if phyloxml::read_forest::<RawNode>("<phyloxml><phylogeny><clade></phylogeny>").is_ok() { panic!("Read invalid XML"); }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_conversion() {
+    use crate::formats::json;
+    use crate::dialects::IntegerNode;
+
+    let text = r#"{"numbers": {"content": 1000, "children": [{"content": "800", "children": [{"content": 2500}]}, {"content": 130}]}}"#;
+    let forest: Forest<IntegerNode> = json::read_forest(text).expect("Failed reading nested JSON");
+    let tree = forest.get_tree("numbers").unwrap();
+    if tree.get_node_content(2).unwrap().get_num() != 2500 { panic!("Wrong nested node"); }
+    let expected = r#"{"numbers":{"content":"1000","children":[{"content":"800","children":[{"content":"2500"}]},{"content":"130"}]}}"#;
+    if json::write_forest(&forest) != expected { panic!("Wrong nested JSON: {}", json::write_forest(&forest)); }
+
+    // Adjacency list, with children listed before their parents
+    let text = r#"{"my_tree": [{"id": "c", "parent": "a", "content": "child_2"}, {"id": "a", "content": "root"}, {"id": "b", "parent": "a", "content": "child_1"}]}"#;
+    let forest: Forest<RawNode> = json::read_adjacency_forest(text).expect("Failed reading adjacency JSON");
+    let expected = r#"{"my_tree":[{"id":0,"parent":null,"content":"root"},{"id":1,"parent":0,"content":"child_2"},{"id":2,"parent":0,"content":"child_1"}]}"#;
+    if json::write_adjacency_forest(&forest) != expected { panic!("Wrong adjacency JSON: {}", json::write_adjacency_forest(&forest)); }
+    let forest: Forest<RawNode> = json::read_adjacency_forest(expected).expect("Failed reading written adjacency JSON");
+    if json::write_forest(&forest) != r#"{"my_tree":{"content":"root","children":[{"content":"child_2"},{"content":"child_1"}]}}"# { panic!("Adjacency round trip failed"); }
+
+    // Errors
+    if json::read_forest::<IntegerNode>(r#"{"t": {"content": "abc"}}"#).is_ok() { panic!("Read invalid dialect node"); }
+    if json::read_forest::<RawNode>(r#"{"t": {"children": []}}"#).is_ok() { panic!("Read node without content"); }
+    match json::read_forest::<RawNode>("{\n\"t\": {\"content\": }\n}") {
+        Err(e) => if e.line() != Some(1) { panic!("Wrong error line {:?}", e.line()); },
+        Ok(_) => panic!("Read invalid JSON")
+    }
+    if json::read_adjacency_forest::<RawNode>(r#"{"t": [{"id": 1, "content": "a"}, {"id": 2, "content": "b"}]}"#).is_ok() { panic!("Read multiple roots"); }
+    if json::read_adjacency_forest::<RawNode>(r#"{"t": [{"id": 1, "content": "a"}, {"id": 2, "parent": 3, "content": "b"}, {"id": 3, "parent": 2, "content": "c"}]}"#).is_ok() { panic!("Read cycle"); }
+    if json::read_adjacency_forest::<RawNode>(r#"{"t": [{"id": 1, "content": "a"}, {"id": 2, "parent": 9, "content": "b"}]}"#).is_ok() { panic!("Read unknown parent"); }
+
+    // Empty trees
+    let text = r#"{"empty":null,"t":{"content":"root"}}"#;
+    let forest: Forest<RawNode> = json::read_forest(text).expect("Failed reading empty nested tree");
+    if forest.get_tree("empty").map(|t| t.get_nodes_len()) != Some(0) { panic!("Empty nested tree not read"); }
+    if json::write_forest(&forest) != text { panic!("Wrong nested JSON with empty tree: {}", json::write_forest(&forest)); }
+    let adjacency = json::write_adjacency_forest(&forest);
+    if adjacency != r#"{"empty":[],"t":[{"id":0,"parent":null,"content":"root"}]}"# { panic!("Wrong adjacency JSON with empty tree: {}", adjacency); }
+    let forest: Forest<RawNode> = json::read_adjacency_forest(&adjacency).expect("Failed reading empty adjacency tree");
+    if json::write_forest(&forest) != text { panic!("Adjacency round trip with empty tree failed"); }
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn deep_json_conversion() {
+    use crate::formats::{newick, json};
+
+    let deep = |depth: usize| {
+        let mut forest: Forest<RawNode> = Forest::new();
+        forest.add_tree("deep", newick::read_tree(&format!("{}A{};", "(".repeat(depth), ",B)".repeat(depth))).unwrap());
+        forest
+    };
+    let forest = deep(20000);
+    let read: Forest<RawNode> = json::read_adjacency_forest(&json::write_adjacency_forest(&forest)).expect("Failed reading deep adjacency list");
+    if newick::write_forest(&read) != newick::write_forest(&forest) { panic!("Deep adjacency list round trip failed"); }
+    if json::write_forest(&forest).matches("\"children\"").count() != 20000 { panic!("Wrong deep nested JSON"); }
+
+    // Nested JSON up to the nesting limit of the parser
+    if json::read_forest::<RawNode>(&json::write_forest(&deep(63))).is_ok() { panic!("Read nested JSON beyond the nesting limit"); }
+    let forest = deep(62);
+    let read: Forest<RawNode> = json::read_forest(&json::write_forest(&forest)).expect("Failed reading deep nested JSON");
+    if newick::write_forest(&read) != newick::write_forest(&forest) { panic!("Deep nested JSON round trip failed"); }
+}
+
+#[test]
+fn diagram_rendering() {
+    use crate::formats::{dot, mermaid, DiagramOptions, Direction, NodeLabel};
+    use crate::dialects::WeightedEdgeNode;
+
+    let tree: Tree<RawNode> = tref!("root" => { "say \"hi\"" });
+    let options = DiagramOptions::new();
+    let expected = "digraph \"tree\" {\n    rankdir=TB;\n    n0 [label=\"root\"];\n    n1 [label=\"say \\\"hi\\\"\"];\n    n0 -> n1;\n}\n";
+    if dot::write_tree(&tree, &options) != expected { panic!("Wrong DOT graph: {}", dot::write_tree(&tree, &options)); }
+    let expected = "flowchart TD\n    n0[\"root\"]\n    n1[\"say #quot;hi#quot;\"]\n    n0 --> n1\n";
+    if mermaid::write_tree(&tree, &options) != expected { panic!("Wrong Mermaid chart: {}", mermaid::write_tree(&tree, &options)); }
+
+    // Weighted forest with clusters
+    let forest: Forest<WeightedEdgeNode> = tref!(
+        ["b"] "0:root" => { "2.5:leaf" }
+        ["a"] "0:alone"
+    );
+    let options = DiagramOptions::new().direction(Direction::LeftRight).label(NodeLabel::ContentAndPosition);
+    let expected = "digraph \"forest\" {\n    rankdir=LR;\n    subgraph \"cluster_0\" {\n        label=\"a\";\n        t0_n0 [label=\"alone (0)\"];\n    }\n    subgraph \"cluster_1\" {\n        label=\"b\";\n        t1_n0 [label=\"root (0)\"];\n        t1_n1 [label=\"leaf (1)\"];\n        t1_n0 -> t1_n1 [label=\"2.5\"];\n    }\n}\n";
+    if dot::write_weighted_forest(&forest, &options) != expected { panic!("Wrong DOT forest: {}", dot::write_weighted_forest(&forest, &options)); }
+    let expected = "flowchart LR\n    subgraph t0 [\"a\"]\n        t0_n0[\"alone (0)\"]\n    end\n    subgraph t1 [\"b\"]\n        t1_n0[\"root (0)\"]\n        t1_n1[\"leaf (1)\"]\n        t1_n0 -->|\"2.5\"| t1_n1\n    end\n";
+    if mermaid::write_weighted_forest(&forest, &options) != expected { panic!("Wrong Mermaid forest: {}", mermaid::write_weighted_forest(&forest, &options)); }
+
+    // Without clusters, unlinked nodes skipped
+    let mut tree: Tree<RawNode> = tref!("root" => { "a", "b" });
+    tree.unlink_node(1);
+    let mut forest = Forest::new();
+    forest.add_tree("t", tree);
+    let chart = mermaid::write_forest(&forest, &DiagramOptions::new().clusters(false));
+    if chart != "flowchart TD\n    t0_n0[\"root\"]\n    t0_n2[\"b\"]\n    t0_n0 --> t0_n2\n" { panic!("Wrong Mermaid chart without clusters: {}", chart); }
+}
+
+#[test]
+fn svg_rendering() {
+    use crate::formats::{svg, SvgOptions, NodeShape, Direction};
+
+    // Get centers of all rectangles in the SVG
+    fn centers(image: &str) -> Vec<(f64, f64, f64)> {
+        image.lines().filter(|l| l.trim_start().starts_with("<rect")).map(|line| {
+            let attr = |name: &str| -> f64 {
+                let start = line.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+                line[start..start + line[start..].find('"').unwrap()].parse().unwrap()
+            };
+            (attr("x") + attr("width") / 2.0, attr("y") + attr("height") / 2.0, attr("width"))
+        }).collect()
+    }
+
+    let tree: Tree<RawNode> = tref!(
+        "root" => {
+            "a" => { "a_1", "a_2", "a_3" },
+            "b",
+            "c" => { "c_1" => { "c_1_1", "c_1_2" } }
+        }
+    );
+    let image = svg::write_tree(&tree, &SvgOptions::new().shape(NodeShape::Rectangle));
+    if !image.starts_with("<svg") || !image.ends_with("</svg>\n") { panic!("Not an SVG document"); }
+    let nodes = centers(&image);
+    if nodes.len() != tree.get_nodes_len() { panic!("Wrong number of nodes"); }
+
+    // Nodes of the same level don't overlap
+    for (i, (x1, y1, w1)) in nodes.iter().enumerate() {
+        for (x2, y2, w2) in nodes.iter().skip(i + 1) {
+            if y1 == y2 && (x1 - x2).abs() < (w1 + w2) / 2.0 + 20.0 - 0.1 { panic!("Overlapping nodes at {} and {}", x1, x2); }
+        }
+    }
+    // Parents are centered over their first and last children, deeper levels go down
+    let (root, a, c) = (nodes[0], nodes[1], nodes[6]);
+    if (root.0 - (a.0 + c.0) / 2.0).abs() > 0.1 { panic!("Root not centered"); }
+    if !(root.1 < a.1 && a.1 < nodes[2].1) { panic!("Wrong levels"); }
+
+    // Left to right, escaped contents
+    let tree: Tree<RawNode> = tref!("a<b" => { "c" });
+    let image = svg::write_tree(&tree, &SvgOptions::new().direction(Direction::LeftRight));
+    if !image.contains(">a&lt;b</text>") { panic!("Content not escaped"); }
+    let nodes = centers(&image);
+    if !(nodes[0].0 < nodes[1].0 && nodes[0].1 == nodes[1].1) { panic!("Wrong left to right layout"); }
+}
+
+#[test]
+fn text_rendering() {
+    use crate::formats::{text, TextOptions};
+
+    let tree: Tree<RawNode> = tref!(
+        "root" => {
+            "a_very_long_content" => { "deep" => { "deeper" } },
+            "b"
+        }
+    );
+    let options = TextOptions::new().ascii(true).positions(true).max_depth(Some(2)).max_width(Some(8));
+    let expected = "root (0)\n|-- a_ver... (1)\n|   `-- deep (2)\n`-- b (4)\n";
+    if text::write_tree(&tree, &options) != expected { panic!("Wrong text: {}", text::write_tree(&tree, &options)); }
+
+    let forest: Forest<RawNode> = tref!(
+        ["second"] "x" => { "y" }
+        ["first"] "z"
+    );
+    if text::write_forest(&forest, &TextOptions::new()) != "[first]\nz\n\n[second]\nx\n└── y\n" { panic!("Wrong forest text"); }
+}
+
+#[test]
+fn outline_conversion() {
+    use crate::formats::{markdown, org};
+
+    let text = "# Title\n\nSome text.\n\n## first\n\n* root\n    1. child_1\n\t\t+ child_1_1\n    2) child_2\n\n## second\n- alone\n";
+    let forest: Forest<RawNode> = markdown::read_forest(text).expect("Failed reading Markdown");
+    let tree = forest.get_tree("first").unwrap();
+    if tree.get_nodes_len() != 4 { panic!("Wrong number of nodes"); }
+    if tree.get_nodes_ref()[2].get_level() != 3 { panic!("Wrong nested level"); }
+    if tree.get_node_content(3).unwrap().get_val() != "child_2" { panic!("Wrong numbered item"); }
+    let expected = "## first\n\n- root\n  - child_1\n    - child_1_1\n  - child_2\n\n## second\n\n- alone\n";
+    if markdown::write_forest(&forest) != expected { panic!("Wrong Markdown: {}", markdown::write_forest(&forest)); }
+
+    let expected = "* first\n** root\n*** child_1\n**** child_1_1\n*** child_2\n* second\n** alone\n";
+    if org::write_forest(&forest) != expected { panic!("Wrong Org-mode: {}", org::write_forest(&forest)); }
+    let forest: Forest<RawNode> = org::read_forest(&format!("#+TITLE: Trees\n{}", expected)).expect("Failed reading Org-mode");
+    if markdown::write_forest(&forest) != markdown::write_forest(&markdown::read_forest::<RawNode>(text).unwrap()) { panic!("Org-mode round trip failed"); }
+
+    // Same level rules as TREF documents
+    if markdown::read_forest::<RawNode>("- root\n").is_ok() { panic!("Read node without tree"); }
+    if markdown::read_forest::<RawNode>("## t\n- root\n- other_root\n").is_ok() { panic!("Read multiple roots"); }
+    match org::read_forest::<RawNode>("* t\n** root\n**** too_deep\n") {
+        Err(e) => if e.line() != Some(2) { panic!("Wrong error line {:?}", e.line()); },
+        Ok(_) => panic!("Read invalid level")
+    }
+}
+
+#[test]
+fn table_conversion() {
+    use crate::formats::{paths, csv};
+
+    // Path lists merge common prefixes
+    let text = "/usr/bin/ls\n/usr/lib\n\n/usr/bin/cat\n/etc/hosts\n";
+    let forest: Forest<RawNode> = paths::read_forest(text, "/").expect("Failed reading paths");
+    let usr = forest.get_tree("usr").expect("Missing usr tree");
+    if usr.get_nodes_len() != 5 { panic!("Prefixes not merged"); }
+    if paths::write_tree(usr, "/") != "usr/bin/ls\nusr/bin/cat\nusr/lib\n" { panic!("Wrong paths: {}", paths::write_tree(usr, "/")); }
+    if forest.get_tree("etc").is_none() { panic!("Missing etc tree"); }
+    match paths::read_tree::<RawNode>(text, "/") {
+        Err(e) => if e.line() != Some(4) { panic!("Wrong error line {:?}", e.line()); },
+        Ok(_) => panic!("Read multiple roots as a tree")
+    }
+    let tree: Tree<RawNode> = paths::read_tree("a::b\na::c", "::").expect("Failed reading paths with a long separator");
+    if tree.get_nodes_len() != 3 { panic!("Wrong separator"); }
+
+    // Parent/child tables
+    let text = "3,1,\"child \"\"2\"\"\"\n1,,root\n2,1,child_1\n10,,other\n";
+    let forest: Forest<RawNode> = csv::read_forest(text).expect("Failed reading CSV");
+    let root = forest.get_tree("root").expect("Missing root tree");
+    if root.get_node_content(1).unwrap().get_val() != "child \"2\"" { panic!("Wrong quoted field"); }
+    if csv::write_tree(root) != "id,parent_id,label\n0,,root\n1,0,\"child \"\"2\"\"\"\n2,0,child_1\n" { panic!("Wrong CSV: {}", csv::write_tree(root)); }
+    let tree: Tree<RawNode> = csv::read_tree(&csv::write_tree(root)).expect("Failed reading written CSV");
+    if csv::write_tree(&tree) != csv::write_tree(root) { panic!("CSV round trip failed"); }
+
+    if csv::read_tree::<RawNode>(text).is_ok() { panic!("Read multiple roots as a tree"); }
+    if csv::read_tree::<RawNode>("1,,root\n2,3,a\n3,2,b\n").is_ok() { panic!("Read cycle"); }
+    if csv::read_tree::<RawNode>("1,,root\n1,,again\n").is_ok() { panic!("Read duplicated ID"); }
+    if csv::read_tree::<RawNode>("1,,root\n2,1\n").is_ok() { panic!("Read missing column"); }
+    if csv::read_forest::<RawNode>("1,,root\n2,,root\n").is_ok() { panic!("Read duplicated tree ID"); }
+}
+
+#[test]
+fn fs_conversion() {
+    use std::{fs, path::PathBuf};
+    use crate::formats::fs::{self as tref_fs, DirOptions};
+    use crate::dialects::{FileNode, FileKind};
+
+    let base: PathBuf = std::env::temp_dir().join(format!("tref_fs_conversion_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&base);
+    let source = base.join("project");
+    fs::create_dir_all(source.join("src")).unwrap();
+    fs::create_dir_all(source.join("empty")).unwrap();
+    fs::create_dir_all(source.join(".git")).unwrap();
+    fs::write(source.join("Cargo.toml"), "12345").unwrap();
+    fs::write(source.join("src").join("main.rs"), "").unwrap();
+
+    // Names only, hidden entries skipped and children sorted
+    let forest: Forest<RawNode> = tref_fs::read_forest(&source, &DirOptions::new()).expect("Failed reading directory");
+    let tree = forest.get_tree("project").expect("Missing project tree");
+    let names: Vec<&str> = (0..tree.get_nodes_len()).map(|p| tree.get_node_content(p).unwrap().get_val()).collect();
+    if names != vec!("project", "Cargo.toml", "empty", "src", "main.rs") { panic!("Wrong entries: {:?}", names); }
+
+    // Metadata and depth limit
+    let tree: Tree<FileNode> = tref_fs::read_tree(&source, &DirOptions::new().metadata(true).hidden(true).max_depth(Some(1))).expect("Failed reading metadata");
+    let toml = tree.get_node_content(2).unwrap();
+    if toml.get_kind() != FileKind::File || toml.get_size() != 5 || toml.get_name() != "Cargo.toml" { panic!("Wrong file node {:?}", toml); }
+    if tree.get_node_content(1).unwrap().get_val() != "dir:0:.git" { panic!("Hidden entry not included"); }
+    if tree.get_nodes_len() != 5 { panic!("Depth limit not applied"); }
+
+    // Materialize, keeping empty directories
+    let target = base.join("target");
+    let tree: Tree<FileNode> = tref_fs::read_tree(&source, &DirOptions::new().metadata(true)).unwrap();
+    tref_fs::write_file_tree(&tree, &target).expect("Failed writing tree");
+    if !target.join("project").join("empty").is_dir() { panic!("Empty directory not created"); }
+    if fs::metadata(target.join("project").join("Cargo.toml")).unwrap().len() != 0 { panic!("File not empty"); }
+    if tref_fs::write_file_tree(&tree, &target).is_ok() { panic!("Overwrote existing files"); }
+
+    // Leaves are files for other dialects, invalid names are rejected
+    let tree: Tree<RawNode> = tref!("other" => { "dir" => { "file" } });
+    tref_fs::write_tree(&tree, &target).expect("Failed writing raw tree");
+    if !target.join("other").join("dir").join("file").is_file() { panic!("Leaf file not created"); }
+    let tree: Tree<RawNode> = tref!("bad" => { ".." => { "escape" } });
+    if tref_fs::write_tree(&tree, &target).is_ok() { panic!("Wrote invalid name"); }
+    if target.join("bad").exists() { panic!("Created entries before checking names"); }
+    let tree: Tree<RawNode> = tref!("dup" => { "a" => { "file" }, "b", "a" });
+    if tref_fs::write_tree(&tree, &target).is_ok() { panic!("Wrote duplicated names"); }
+    if target.join("dup").exists() { panic!("Created entries before checking duplicated names"); }
+
+    // Names that can't be node contents
+    let odd = base.join("odd");
+    fs::create_dir_all(&odd).unwrap();
+    fs::write(odd.join("+plus"), "").unwrap();
+    if tref_fs::read_tree::<RawNode>(&odd, &DirOptions::new()).is_ok() { panic!("Read name starting with `+`"); }
+    if tref_fs::read_tree::<FileNode>(&odd, &DirOptions::new().metadata(true)).is_err() { panic!("Failed reading name starting with `+` with metadata"); }
+    fs::remove_file(odd.join("+plus")).unwrap();
+    fs::write(odd.join(" space"), "").unwrap();
+    if tref_fs::read_tree::<RawNode>(&odd, &DirOptions::new()).is_ok() { panic!("Read name starting with a space"); }
+
+    fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn sexp_conversion() {
+    use crate::formats::sexp;
+
+    let text = "; Legacy config\n(app (server \"example.com\" 8080)\n     (\"log level\" debug)) ; trailing\n(other)\n";
+    let forest: Forest<RawNode> = sexp::read_forest(text).expect("Failed reading S-expressions");
+    let tree = forest.get_tree("app").expect("Missing app tree");
+    let contents: Vec<&str> = (0..tree.get_nodes_len()).map(|p| tree.get_node_content(p).unwrap().get_val()).collect();
+    if contents != vec!("app", "server", "example.com", "8080", "log level", "debug") { panic!("Wrong contents: {:?}", contents); }
+    if forest.get_tree("other").map(|t| t.get_nodes_len()) != Some(1) { panic!("Wrong single atom tree"); }
+
+    let written = sexp::write_forest(&forest);
+    if written != "(app (server example.com 8080) (\"log level\" debug))\nother\n" { panic!("Wrong S-expressions:\n{}", written); }
+    let tree: Tree<RawNode> = tref!("a\"b" => { "c\\d", "" });
+    let written = sexp::write_tree(&tree);
+    let back: Tree<RawNode> = sexp::read_tree(&written).expect("Failed reading escaped atoms");
+    if sexp::write_tree(&back) != written { panic!("Escaped atoms don't round trip: {}", written); }
+
+    for (text, line) in [("(a (b c)\n", 0), ("(a)\n)", 1), ("(a\n(() b))", 1), ("(a \"b)", 0), ("()", 0)].iter() {
+        match sexp::read_tree::<RawNode>(text) {
+            Ok(_) => panic!("Read invalid S-expression {:?}", text),
+            Err(e) => if e.line() != Some(*line) { panic!("Wrong error line in {:?}: {}", text, e); }
+        }
+    }
+    if sexp::read_tree::<RawNode>("a b").is_ok() { panic!("Read multiple roots as a tree"); }
+}
+
+#[cfg(feature = "xml")]
+#[test]
+fn xml_conversion() {
+    use crate::formats::xml::{self, XmlOptions};
+    use crate::walk::pre_order;
+
+    let text = r#"<?xml version="1.0"?>
+<project name="tref" xmlns:x="urn:x">
+  <x:dependency version="0.4">socarel</x:dependency>
+  <dependency>regex<![CDATA[ & more]]></dependency>
+  <empty/>
+</project>"#;
+    let tree: Tree<RawNode> = xml::read_tree(text, &XmlOptions::new()).expect("Failed reading elements");
+    let contents: Vec<&str> = (0..tree.get_nodes_len()).map(|p| tree.get_node_content(p).unwrap().get_val()).collect();
+    if contents != vec!("project", "dependency", "dependency", "empty") { panic!("Wrong elements: {:?}", contents); }
+
+    let options = XmlOptions::new().attributes(true).text(true);
+    let forest: Forest<RawNode> = xml::read_forest(text, &options).expect("Failed reading attributes and text");
+    let tree = forest.get_tree("project").expect("Missing project tree");
+    let contents: Vec<&str> = pre_order(tree).into_iter().map(|p| tree.get_node_content(p).unwrap().get_val()).collect();
+    if contents != vec!("project", "@name=tref", "dependency", "@version=0.4", "#socarel", "dependency", "#regex", "#& more", "empty") {
+        panic!("Wrong nodes: {:?}", contents);
+    }
+    let written = xml::write_tree(tree, &options).expect("Failed writing XML");
+    if !written.contains("<project name=\"tref\">") || !written.contains("<dependency version=\"0.4\">socarel</dependency>") || !written.contains("    &amp; more\n") {
+        panic!("Wrong XML:\n{}", written);
+    }
+    let tree: Tree<RawNode> = tref!("config" => { "@version=2", "server" => { "#example.com" }, "#note", "debug" });
+    let written = xml::write_tree(&tree, &options).expect("Failed writing XML");
+    let back: Tree<RawNode> = xml::read_tree(&written, &options).expect("Failed reading written XML");
+    if xml::write_tree(&back, &options).unwrap() != written || back.get_nodes_len() != 6 { panic!("XML doesn't round trip:\n{}", written); }
+    let tree: Tree<RawNode> = xml::read_tree("<a>line 1\n    line  2\r\n</a>", &options).expect("Failed reading multi-line text");
+    if tree.get_node_content(1).unwrap().get_val() != "#line 1 line 2" { panic!("Whitespaces not collapsed: {:?}", tree.get_node_content(1).unwrap().get_val()); }
+
+    // Nodes that can't be written
+    let tree: Tree<RawNode> = tref!("root" => { "@id=1" => { "child" } });
+    match xml::write_tree(&tree, &options) {
+        Ok(_) => panic!("Wrote attribute with children"),
+        Err(e) => if e.path() != ["root", "@id=1"] { panic!("Wrong error path {:?}", e.path()); }
+    }
+    let tree: Tree<RawNode> = tref!("root" => { "@id=1" });
+    if xml::write_tree(&tree, &XmlOptions::new()).is_ok() { panic!("Wrote attribute as element"); }
+    let tree: Tree<RawNode> = tref!("root" => { "not valid" });
+    if xml::write_tree(&tree, &options).is_ok() { panic!("Wrote invalid element name"); }
+
+    match xml::read_tree::<RawNode>("<a>\n<b>\n</a>", &options) {
+        Ok(_) => panic!("Read invalid XML"),
+        Err(e) => if e.line() != Some(2) { panic!("Wrong error line: {}", e); }
+    }
+}
+
+#[test]
+fn format_canonical_layout() {
+    let tref = "# File header  \r\n# two lines\r\n\r\n\r\n# Detached header\n\n# First tree\n[first]\n\n+ root\t\n# Inside the tree\n\n+ + child\n# End of first tree\n\n\n\n# Second tree\n[second]\n+ root\n[empty]\n   \n# Last comment\n\n";
+    let expected = "# File header\n# two lines\n\n# Detached header\n\n# First tree\n[first]\n+ root\t\n# Inside the tree\n+ + child\n# End of first tree\n\n# Second tree\n[second]\n+ root\n\n[empty]\n# Last comment\n";
+    let formatted = match format_document(tref) {
+        Ok(formatted) => formatted,
+        Err(e) => panic!("Failed formatting: {}", e)
+    };
+    if formatted != expected { panic!("Wrong layout:\n{}", formatted); }
+    match format_document(&formatted) {
+        Ok(again) => if again != formatted { panic!("Formatting is not idempotent:\n{}", again); },
+        Err(e) => panic!("Failed formatting twice: {}", e)
+    }
+    if format_document("").map(|f| f.is_empty()).ok() != Some(true) { panic!("Empty document not kept empty"); }
+
+    match format_document("[t]\n\n+ root\n+ + + child\n") {
+        Ok(_) => panic!("Formatted an invalid document"),
+        Err(e) => if e.line() != 3 { panic!("Wrong error line {}", e.line()); }
+    }
+    // Documents are checked before removing trailing whitespaces
+    if format_document("[t]  \n+ root\n").is_ok() { panic!("Formatted a tree ID with trailing whitespaces"); }
+}
+
+#[test]
+fn lint_rules() {
+    use crate::lint::{self, LintOptions, Severity};
+
+    let tref = "[my tree]\n+ 0:root\n+ + 1:a\n+ + 2:b \n+ + c\n+ + + d\n+ + + + e\n+ + 1:a\n[empty]\n";
+    let diagnostics = lint::check(tref, &LintOptions::new().max_level(3)).expect("Failed linting");
+    let found: Vec<(&str, usize)> = diagnostics.iter().map(|d| (d.rule(), d.line())).collect();
+    let expected = vec!(
+        ("tree-id-spaces", 0), ("trailing-whitespace", 3), ("mixed-dialects", 4), ("mixed-dialects", 5),
+        ("deep-nesting", 6), ("mixed-dialects", 6), ("duplicate-sibling", 7), ("empty-tree", 8)
+    );
+    if found != expected { panic!("Wrong diagnostics: {:?}", found); }
+    if diagnostics.iter().any(|d| d.severity() != Severity::Warning) { panic!("Rules are not warnings by default"); }
+
+    // Options, overridden by the document
+    let options = LintOptions::new().rule("mixed-dialects", Severity::Off).rule("empty-tree", Severity::Error).rule("duplicate-sibling", Severity::Error);
+    let tref = "# tref-lint: duplicate-sibling=off, max-level=2\n[t]\n+ root\n+ + a\n+ + a\n+ + + b\n[empty]\n";
+    let diagnostics = lint::check(tref, &options).expect("Failed linting");
+    let found: Vec<(&str, Severity)> = diagnostics.iter().map(|d| (d.rule(), d.severity())).collect();
+    if found != vec!(("deep-nesting", Severity::Warning), ("empty-tree", Severity::Error)) { panic!("Wrong configured diagnostics: {:?}", found); }
+
+    // Suppression comments, and invalid lint comments
+    let tref = "# tref-lint: nothing=off, empty-tree=loud\n[t]\n+ root \n# tref-lint-ignore duplicate-sibling\n+ + a \n# tref-lint-ignore\n+ + a \n";
+    let diagnostics = lint::check(tref, &LintOptions::new()).expect("Failed linting");
+    let found: Vec<(&str, usize)> = diagnostics.iter().map(|d| (d.rule(), d.line())).collect();
+    if found != vec!(("lint-comment", 0), ("lint-comment", 0), ("trailing-whitespace", 2), ("trailing-whitespace", 4)) { panic!("Wrong suppressed diagnostics: {:?}", found); }
+    if !diagnostics[0].to_string().contains("Unknown rule `nothing`") { panic!("Wrong message: {}", diagnostics[0]); }
+    let tref = "[t]\n+ root\n# tref-lint-ignored for now\n+ + a \n# tref-lint-ignore\ttrailing-whitespace\n+ + b \n";
+    let found: Vec<usize> = lint::check(tref, &LintOptions::new()).expect("Failed linting").iter().map(|d| d.line()).collect();
+    if found != vec!(3) { panic!("Wrong ignore comment prefix: {:?}", found); }
+
+    if lint::check("[t]\n+ + a\n", &LintOptions::new()).is_ok() { panic!("Linted an invalid document"); }
+}
+
+#[test]
+fn query_nodes() {
+    let tref = "[config]\n+ base\n+ + branch_1\n+ + + branch_1_1\n+ + branch_2\n+ + + branch_2_1\n+ + + branch_2_2\n+ + + + leaf\n[other]\n+ base\n+ + branch_2_9\n";
+    let forest = match <Model>::parse(BufReader::new(tref.as_bytes())) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    let cases: Vec<(&str, Vec<(&str, usize)>)> = vec!(
+        ("config/base/*/branch_2_?", vec!(("config", 4), ("config", 5))),
+        ("config/base", vec!(("config", 0))),
+        ("config/branch_1", vec!()),
+        ("//branch_2_*", vec!(("config", 4), ("config", 5), ("other", 1))),
+        ("o*//branch_2_*", vec!(("other", 1))),
+        ("config/base//leaf", vec!(("config", 6))),
+        ("[level=3]", vec!(("config", 2), ("config", 4), ("config", 5))),
+        ("config//*[level>=3][children=0]", vec!(("config", 2), ("config", 4), ("config", 6))),
+        ("branch_2", vec!(("config", 3))),
+        ("*/base/[position!=1]", vec!(("config", 3)))
+    );
+    for (query, expected) in cases {
+        match query_forest(&forest, query) {
+            Ok(selected) => if selected != expected { panic!("Wrong nodes for `{}`: {:?}", query, selected); },
+            Err(e) => panic!("Failed query `{}`: {}", query, e)
+        }
+    }
+
+    let tree = forest.get_tree("config").unwrap();
+    match query_tree(tree, "base/branch_2//*") {
+        Ok(selected) => if selected != vec!(4, 5, 6) { panic!("Wrong tree nodes: {:?}", selected); },
+        Err(e) => panic!("Failed tree query: {}", e)
+    }
+    if tree.query("base/branch_2//*").ok() != Some(vec!(4, 5, 6)) || forest.query("o*//base").ok() != Some(vec!(("other", 0))) { panic!("Wrong query methods"); }
+
+    // Escaped separators
+    let mut forest: Forest<RawNode> = Forest::new();
+    forest.add_tree("fs/root", tref!("/" => { "usr/bin" => { "ls" }, "usr\\lib", "[x]" }));
+    let cases: Vec<(&str, Vec<(&str, usize)>)> = vec!(
+        ("fs\\/root/\\//usr\\/bin/ls", vec!(("fs/root", 2))),
+        ("usr\\/*", vec!(("fs/root", 1))),
+        ("//usr\\\\lib", vec!(("fs/root", 3))),
+        ("//\\[x]", vec!(("fs/root", 4)))
+    );
+    for (query, expected) in cases {
+        match forest.query(query) {
+            Ok(selected) => if selected != expected { panic!("Wrong nodes for `{}`: {:?}", query, selected); },
+            Err(e) => panic!("Failed query `{}`: {}", query, e)
+        }
+    }
+
+    for (query, position) in [("", 0), ("/base", 0), ("config/", 7), ("config/base[level=1", 11), ("config/base[depth=1]", 12), ("config/base[level=x]", 12), ("config/base[level=1]x", 20)].iter() {
+        match query_forest(&forest, query) {
+            Ok(_) => panic!("Accepted invalid query `{}`", query),
+            Err(e) => if e.position() != *position { panic!("Wrong error position for `{}`: {}", query, e); }
+        }
+    }
+}
+
+#[test]
+fn node_paths() {
+    let tref = "[config]\n+ base\n+ + branch\n+ + + leaf_1\n+ + branch\n+ + + leaf_2\n+ + + leaf_1\n";
+    let mut forest = match <Model>::parse(BufReader::new(tref.as_bytes())) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    let tree = forest.get_mut_tree("config").unwrap();
+    let cases = [
+        ("base > branch > leaf_2", DuplicatePolicy::Unique, Some(4)),
+        ("base > branch > leaf_1", DuplicatePolicy::First, Some(2)),
+        ("base > branch > leaf_1", DuplicatePolicy::Last, Some(5)),
+        ("base > branch > leaf_1", DuplicatePolicy::Unique, None),
+        ("base > leaf_1", DuplicatePolicy::First, None),
+        ("other", DuplicatePolicy::First, None)
+    ];
+    for (path, policy, expected) in cases.iter() {
+        match (find_path(tree, path, *policy), expected) {
+            (Ok(position), Some(expected)) => if position != *expected { panic!("Wrong node for `{}`: {}", path, position); },
+            (Err(e), None) => if e.path() != *path { panic!("Wrong error path: {}", e); },
+            (result, _) => panic!("Wrong result for `{}` with {:?}: {:?}", path, policy, result)
+        }
+    }
+    match find_path(tree, "base > branch", DuplicatePolicy::Unique) {
+        Err(e) => if e.matches() != 2 { panic!("Wrong number of matches: {}", e); },
+        Ok(position) => panic!("Accepted ambiguous path: {}", position)
+    }
+    if find_path_contents(tree, &["base", "branch", "leaf_2"], DuplicatePolicy::Unique).ok() != Some(4) {
+        panic!("Wrong node for contents");
+    }
+
+    for position in 0..tree.get_nodes_len() {
+        let path = match node_path(tree, position) {
+            Some(path) => path,
+            None => panic!("Missing path of node {}", position)
+        };
+        if find_path(tree, &path, DuplicatePolicy::First).is_err() {
+            panic!("Path `{}` not found", path);
+        }
+    }
+    if node_path(tree, 5).as_deref() != Some("base > branch > leaf_1") { panic!("Wrong path of node 5"); }
+    if node_path(tree, 10).is_some() { panic!("Path of a missing node"); }
+    tree.unlink_node(3);
+    if node_path(tree, 4).is_some() || node_path_contents(tree, 3).is_some() { panic!("Path of an unlinked node"); }
+
+    let tref = "[numbers]\n+ 1\n+ + 2\n+ + + x\n";
+    match Model::<dialects::IntegerNode>::parse(BufReader::new(tref.as_bytes())) {
+        Ok(_) => panic!("Parsed invalid integer node"),
+        Err(e) => if e.path() != Some("1 > 2 > x") { panic!("Wrong error path: {}", e); }
+    }
+}
+
+#[test]
+fn structural_diff() {
+    let parse = |tref: &str| match <Model>::parse(BufReader::new(tref.as_bytes())) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    let old = parse("[config]\n+ base\n+ + server\n+ + + host=localhost\n+ + + port=80\n+ + users\n+ + + admin\n+ + + guest\n+ + logs\n+ + + debug\n+ + + + verbose\n[removed]\n+ x\n");
+
+    let reordered = parse("[removed]\n+ x\n[config]\n+ base\n+ + logs\n+ + + debug\n+ + + + verbose\n+ + users\n+ + + guest\n+ + + admin\n+ + server\n+ + + port=80\n+ + + host=localhost\n");
+    let changes = diff_forest(&old, &reordered);
+    if !changes.is_empty() { panic!("Reordered siblings are changes: {:?}", changes); }
+
+    let new = parse("[config]\n+ base\n+ + servers\n+ + + host=localhost\n+ + + port=8080\n+ + + debug\n+ + + + verbose\n+ + users\n+ + + admin\n+ + + root\n+ + + + sudo\n[added]\n+ y\n");
+    let changes: Vec<String> = diff_forest(&old, &new).iter().map(|c| c.to_string()).collect();
+    let expected = vec!(
+        "+ [added]",
+        "- config: base > users > guest",
+        "- config: base > logs",
+        "~ config: base > server -> base > servers",
+        "~ config: base > server > port=80 -> base > servers > port=8080",
+        "> config: base > logs > debug -> base > servers > debug",
+        "+ config: base > users > root",
+        "- [removed]"
+    );
+    if changes != expected { panic!("Wrong changes: {:#?}", changes); }
+
+    let changes = diff_tree("config", old.get_tree("config").unwrap(), new.get_tree("config").unwrap());
+    let moved = match changes.iter().find(|c| c.kind() == ChangeKind::Move) {
+        Some(change) => change,
+        None => panic!("Missing move")
+    };
+    if moved.old_position() != Some(8) || moved.new_position() != Some(4) || moved.tree_id() != "config" {
+        panic!("Wrong move: {:?}", moved);
+    }
+    let inserted = changes.iter().find(|c| c.kind() == ChangeKind::Insert).unwrap();
+    if inserted.old_path().is_some() || inserted.new_path() != Some("base > users > root") {
+        panic!("Wrong insert: {:?}", inserted);
+    }
+}
+
+#[test]
+fn diff_large_trees() {
+    // Many renamed siblings
+    let (mut old, mut new): (Tree<RawNode>, Tree<RawNode>) = (Tree::new(), Tree::new());
+    old.set_root("root");
+    new.set_root("root");
+    for i in 0..20000 {
+        old.link_node(&format!("old_{}", i), 0);
+        new.link_node(&format!("new_{}", i), 0);
+    }
+    let changes = diff_tree("wide", &old, &new);
+    if changes.len() != 20000 || changes.iter().any(|c| c.kind() != ChangeKind::Rename) { panic!("Wrong changes of renamed siblings"); }
+
+    // Deep trees, with the deepest node renamed
+    let (mut old, mut new): (Tree<RawNode>, Tree<RawNode>) = (Tree::new(), Tree::new());
+    let (mut old_parent, mut new_parent) = (old.set_root("root").unwrap(), new.set_root("root").unwrap());
+    for i in 0..50000 {
+        old_parent = old.link_node(&format!("node_{}", i), old_parent).unwrap();
+        new_parent = new.link_node(&format!("node_{}", i), new_parent).unwrap();
+    }
+    old.link_node("leaf", old_parent);
+    new.link_node("renamed leaf", new_parent);
+    let changes = diff_tree("deep", &old, &new);
+    if changes.len() != 1 || changes[0].kind() != ChangeKind::Rename { panic!("Wrong changes of deep trees: {}", changes.len()); }
+}
+
+#[test]
+fn three_way_merge() {
+    let parse = |tref: &str| match <Model>::parse(BufReader::new(tref.as_bytes())) {
+        Ok(forest) => forest,
+        Err(e) => panic!("Failed parsing document: {}", e)
+    };
+    // Sorted by tree ID
+    let serialize = |forest: &Forest<RawNode>| match format_forest(forest, "") {
+        Ok(tref) => tref,
+        Err(e) => panic!("Failed serializing: {}", e)
+    };
+    let base = parse("[config]\n+ server\n+ + host=localhost\n+ + port=80\n+ + users\n+ + + admin\n+ + + guest\n+ + logs\n+ + + debug\n[old]\n+ x\n");
+    let ours = parse("[config]\n+ server\n+ + host=example.com\n+ + port=80\n+ + users\n+ + + admin\n+ + + guest\n+ + + alice\n+ + logs\n+ + + debug\n[old]\n+ x\n[added]\n+ a\n");
+    let theirs = parse("[config]\n+ server\n+ + host=localhost\n+ + users\n+ + + admin\n+ + + guest\n+ + + + debug\n+ + port=8080\n+ + logs\n[added]\n+ a\n");
+    let (merged, conflicts) = merge_forest(&base, &ours, &theirs);
+    if !conflicts.is_empty() { panic!("Unexpected conflicts: {:?}", conflicts); }
+    let expected = "[added]\n+ a\n\n[config]\n+ server\n+ + host=example.com\n+ + users\n+ + + admin\n+ + + guest\n+ + + + debug\n+ + + alice\n+ + port=8080\n+ + logs\n";
+    if serialize(&merged) != expected { panic!("Wrong merge:\n{}", serialize(&merged)); }
+
+    let theirs = parse("[config]\n+ server\n+ + host=127.0.0.1\n+ + port=80\n+ + users\n+ + + admin\n+ + + + root\n[old]\n+ y\n");
+    let ours = parse("[config]\n+ server\n+ + host=example.com\n+ + port=80\n+ + users\n+ + + guest\n+ + + + root\n+ + logs\n+ + + debug\n");
+    let (merged, conflicts) = merge_forest(&base, &ours, &theirs);
+    let conflicts: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+    let expected = vec!(
+        "config: server > host=localhost: Renamed to `host=example.com` in ours and `host=127.0.0.1` in theirs",
+        "config: server > users > admin > root: Inserted in theirs under a node deleted in ours",
+        "config: server > users > guest: Deleted in theirs and changed in ours",
+        "old: Tree removed in ours and changed in theirs"
+    );
+    if conflicts != expected { panic!("Wrong conflicts: {:#?}", conflicts); }
+    let tree = merged.get_tree("config").unwrap();
+    if find_path(tree, "server > host=example.com", DuplicatePolicy::Unique).is_err() || merged.get_tree("old").is_some() {
+        panic!("Conflicts not resolved with our version:\n{}", serialize(&merged));
+    }
+    // Reordered siblings
+    let ours = parse("[config]\n+ server\n+ + host=example.com\n+ + port=80\n+ + users\n+ + + admin\n+ + + guest\n+ + + alice\n+ + logs\n+ + + debug\n[old]\n+ x\n");
+    let theirs = parse("[config]\n+ server\n+ + users\n+ + + guest\n+ + + admin\n+ + host=localhost\n+ + port=80\n+ + logs\n+ + + debug\n[old]\n+ x\n");
+    let (merged, conflicts) = merge_forest(&base, &ours, &theirs);
+    if !conflicts.is_empty() { panic!("Unexpected conflicts: {:?}", conflicts); }
+    let expected = "[config]\n+ server\n+ + users\n+ + + guest\n+ + + admin\n+ + + alice\n+ + host=example.com\n+ + port=80\n+ + logs\n+ + + debug\n\n[old]\n+ x\n";
+    if serialize(&merged) != expected { panic!("Wrong merge:\n{}", serialize(&merged)); }
+
+    let ours = parse("[config]\n+ server\n+ + port=80\n+ + host=localhost\n+ + users\n+ + + guest\n+ + + admin\n+ + logs\n+ + + debug\n");
+    let theirs = parse("[config]\n+ server\n+ + logs\n+ + + debug\n+ + host=localhost\n+ + port=80\n+ + users\n+ + + admin\n[old]\n+ x\n");
+    let (merged, conflicts) = merge_forest(&base, &ours, &theirs);
+    let conflicts: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+    if conflicts != vec!("config: server: Reordered differently in ours and theirs") { panic!("Wrong conflicts: {:#?}", conflicts); }
+    let expected = "[config]\n+ server\n+ + port=80\n+ + host=localhost\n+ + users\n+ + + admin\n+ + logs\n+ + + debug\n";
+    if serialize(&merged) != expected { panic!("Conflicts not resolved with our version:\n{}", serialize(&merged)); }
+
+    let base = parse("[list]\n+ items\n+ + a\n+ + b\n");
+    let ours = parse("[list]\n+ items\n+ + b\n+ + a\n");
+    let (merged, conflicts) = merge_forest(&base, &ours, &parse(""));
+    let conflicts: Vec<String> = conflicts.iter().map(|c| c.to_string()).collect();
+    if conflicts != vec!("list: Tree removed in theirs and changed in ours") || merged.get_tree("list").is_none() {
+        panic!("Wrong conflicts: {:#?}", conflicts);
+    }
+}
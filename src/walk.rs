@@ -0,0 +1,97 @@
+use socarel::{Forest, Tree, NodeContent};
+
+/// Get positions of the linked children of a node, skipping the ones that were unlinked.
+pub fn children<T: NodeContent>(tree: &Tree<T>, position: usize) -> Vec<usize> {
+    let nodes = tree.get_nodes_ref();
+    match nodes.get(position) {
+        Some(node) => node.get_children_ref().iter().filter(|c| **c < nodes.len()).copied().collect(),
+        None => vec!()
+    }
+}
+
+/// Get positions of all linked nodes in pre-order DFS, starting from the root.
+pub fn pre_order<T: NodeContent>(tree: &Tree<T>) -> Vec<usize> {
+    let mut positions = vec!();
+    if tree.get_nodes_len() == 0 {
+        return positions;
+    }
+    let mut stack = vec!(0);
+    while let Some(position) = stack.pop() {
+        positions.push(position);
+        for child in children(tree, position).into_iter().rev() {
+            stack.push(child);
+        }
+    }
+    positions
+}
+
+/// Get contents of the nodes from the root to a node.
+pub fn content_path<T: NodeContent>(tree: &Tree<T>, position: usize) -> Vec<String> {
+    let nodes = tree.get_nodes_ref();
+    position_path(tree, position).into_iter().map(|p| String::from(nodes[p].get_content_ref().get_val())).collect()
+}
+
+/// Get the tree IDs of a forest, sorted.
+///
+/// Forests don't keep the order of their trees, use it to go through them always in the same order.
+///
+/// # Arguments
+///
+/// * `forest` - Forest.
+///
+/// # Return
+///
+/// * Tree IDs, sorted.
+///
+pub fn sorted_tree_ids<T: NodeContent>(forest: &Forest<T>) -> Vec<&String> {
+    let mut tree_ids: Vec<&String> = forest.iter().map(|(tree_id, _)| tree_id).collect();
+    tree_ids.sort();
+    tree_ids
+}
+
+/// Get trees of a forest, sorted by tree ID.
+pub fn sorted_trees<T: NodeContent>(forest: &Forest<T>) -> Vec<(&str, &Tree<T>)> {
+    sorted_tree_ids(forest).into_iter().filter_map(|tree_id| forest.get_tree(tree_id).map(|tree| (tree_id.as_str(), tree))).collect()
+}
+
+/// Get positions of the nodes from the root to a node.
+pub fn position_path<T: NodeContent>(tree: &Tree<T>, position: usize) -> Vec<usize> {
+    let nodes = tree.get_nodes_ref();
+    let mut path = vec!();
+    let mut current = Some(position);
+    while let Some(position) = current {
+        match nodes.get(position) {
+            Some(node) => {
+                path.push(position);
+                current = node.get_parent_position();
+            },
+            None => break
+        }
+    }
+    path.reverse();
+    path
+}
+
+/// Get positions of all linked descendants of a node in pre-order DFS, without the node itself.
+pub fn descendants<T: NodeContent>(tree: &Tree<T>, position: usize) -> Vec<usize> {
+    let mut positions = vec!();
+    let mut stack: Vec<usize> = children(tree, position).into_iter().rev().collect();
+    while let Some(position) = stack.pop() {
+        positions.push(position);
+        for child in children(tree, position).into_iter().rev() {
+            stack.push(child);
+        }
+    }
+    positions
+}
+
+/// Get the parent of each linked node.
+pub fn parents<T: NodeContent>(tree: &Tree<T>) -> Vec<Option<usize>> {
+    let mut parents = vec![None; tree.get_nodes_len()];
+    for position in pre_order(tree) {
+        for child in children(tree, position) {
+            parents[child] = Some(position);
+        }
+    }
+    parents
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+const VALID: &str = "[b]\n+ root\n+ + leaf\n\n[a]\n+ other\n";
+const UNFORMATTED: &str = "# Trees   \n[a]\n+ root\n\n\n[b]\n+ other\n";
+const INVALID: &str = "[a]\n+ root\n+ + + deep\n";
+
+/// Run the tool with some arguments, writing `stdin` to its standard input.
+fn tref(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tref"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed running tref");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// Create a directory for the files of a test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tref_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_file(dir: &Path, name: &str, text: &str) -> String {
+    let path = dir.join(name);
+    fs::write(&path, text).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn usage_errors() {
+    for args in [vec!(), vec!("unknown"), vec!("cat"), vec!("convert", "--from", "tref"), vec!("validate", "--unknown")].iter() {
+        let output = tref(args, "");
+        if output.status.code() != Some(2) { panic!("Wrong exit code {:?} for {:?}", output.status.code(), args); }
+    }
+    if !tref(&["help"], "").status.success() { panic!("Help failed"); }
+}
+
+#[test]
+fn validate() {
+    let dir = test_dir("validate");
+    let valid = write_file(&dir, "valid.tref", VALID);
+    let invalid = write_file(&dir, "invalid.tref", INVALID);
+
+    if !tref(&["validate", &valid], "").status.success() { panic!("Valid document failed"); }
+    if !tref(&["validate"], VALID).status.success() { panic!("Valid standard input failed"); }
+
+    // All files are checked, even after an invalid one
+    let output = tref(&["validate", &invalid, &valid, &invalid], "");
+    if output.status.code() != Some(1) { panic!("Wrong exit code {:?}", output.status.code()); }
+    let errors = stderr(&output);
+    if errors.matches("invalid.tref").count() != 2 || !errors.contains("2 of 3 documents are invalid") { panic!("Wrong errors:\n{}", errors); }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fmt() {
+    let dir = test_dir("fmt");
+    let formatted = write_file(&dir, "formatted.tref", "[a]\n+ root\n\n[b]\n+ other\n");
+    let unformatted = write_file(&dir, "unformatted.tref", UNFORMATTED);
+    let invalid = write_file(&dir, "invalid.tref", INVALID);
+
+    // Check lists the documents that need formatting
+    let output = tref(&["fmt", "--check", &formatted, &unformatted], "");
+    if output.status.code() != Some(1) || stdout(&output) != format!("{}\n", unformatted) { panic!("Wrong check output:\n{}", stdout(&output)); }
+    if fs::read_to_string(&unformatted).unwrap() != UNFORMATTED { panic!("Check rewrote a file"); }
+
+    // Invalid documents are reported, and the others are still formatted
+    let output = tref(&["fmt", &invalid, &unformatted], "");
+    if output.status.code() != Some(1) || !stderr(&output).contains("invalid.tref: Failed parsing document") { panic!("Wrong errors:\n{}", stderr(&output)); }
+    if fs::read_to_string(&unformatted).unwrap() != "# Trees\n[a]\n+ root\n\n[b]\n+ other\n" { panic!("File not formatted"); }
+    if fs::read_to_string(&invalid).unwrap() != INVALID { panic!("Invalid file rewritten"); }
+
+    let output = tref(&["fmt"], UNFORMATTED);
+    if !output.status.success() || stdout(&output) != "# Trees\n[a]\n+ root\n\n[b]\n+ other\n" { panic!("Wrong formatted standard input:\n{}", stdout(&output)); }
+
+    // Node contents keep their trailing whitespaces, and documents that are not valid are not formatted
+    let output = tref(&["fmt"], "[a]\n+ root  \n");
+    if !output.status.success() || stdout(&output) != "[a]\n+ root  \n" { panic!("Changed node content:\n{}", stdout(&output)); }
+    if tref(&["fmt"], "[a]  \n+ root\n").status.code() != Some(1) { panic!("Formatted an invalid document"); }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn convert() {
+    let output = tref(&["convert", "--to", "newick"], VALID);
+    if !output.status.success() || stdout(&output) != "other;\n(leaf)root;\n" { panic!("Wrong Newick:\n{}", stdout(&output)); }
+
+    let output = tref(&["convert", "--from", "newick", "--to", "tref"], "(A,B)root;");
+    if !output.status.success() || stdout(&output) != "[tree_1]\n+ root\n+ + A\n+ + B\n" { panic!("Wrong TREF:\n{}", stdout(&output)); }
+
+    // Branch lengths round trip
+    let newick = "((A:0.5,B:1.5)'x y':2,C:3)root;\n";
+    let output = tref(&["convert", "--from", "newick", "--to", "newick"], newick);
+    if !output.status.success() || stdout(&output) != newick { panic!("Wrong branch lengths:\n{}", stdout(&output)); }
+    let output = tref(&["convert", "--from", "newick", "--to", "nexus"], newick);
+    let output = tref(&["convert", "--from", "nexus", "--to", "newick"], &stdout(&output));
+    if !output.status.success() || stdout(&output) != newick { panic!("Wrong branch lengths through Nexus:\n{}", stdout(&output)); }
+
+    let output = tref(&["convert", "--to", "paths", "--tree", "b"], VALID);
+    if !output.status.success() || stdout(&output) != "root/leaf\n" { panic!("Wrong paths:\n{}", stdout(&output)); }
+
+    let output = tref(&["convert", "--from", "xml", "--to", "tref"], "<notes>\n  first line\n  second line\n</notes>\n");
+    if cfg!(feature = "xml") && (!output.status.success() || !stdout(&output).contains("+ + #first line second line\n")) { panic!("Wrong XML text:\n{}{}", stdout(&output), stderr(&output)); }
+
+    if tref(&["convert", "--to", "unknown"], VALID).status.code() != Some(2) { panic!("Accepted unknown format"); }
+    if tref(&["convert", "--to", "csv"], VALID).status.code() != Some(1) { panic!("Wrote two trees as CSV"); }
+}
+
+#[test]
+fn cat_ls_stats() {
+    let output = tref(&["cat", "b"], VALID);
+    if !output.status.success() || stdout(&output) != "[b]\n+ root\n+ + leaf\n" { panic!("Wrong tree:\n{}", stdout(&output)); }
+    if tref(&["cat", "c"], VALID).status.code() != Some(1) { panic!("Printed a missing tree"); }
+
+    let output = tref(&["ls"], VALID);
+    if !output.status.success() || stdout(&output) != "a\nb\n" { panic!("Wrong tree IDs:\n{}", stdout(&output)); }
+
+    let output = tref(&["stats"], VALID);
+    if !output.status.success() || stdout(&output) != "a: 1 nodes, 1 leaves, 1 levels\nb: 2 nodes, 1 leaves, 2 levels\nTotal: 2 trees, 3 nodes\n" { panic!("Wrong stats:\n{}", stdout(&output)); }
+
+    if tref(&["ls"], INVALID).status.code() != Some(1) { panic!("Listed an invalid document"); }
+}
+
+#[test]
+fn merge() {
+    let dir = test_dir("merge");
+    let base = write_file(&dir, "base.tref", "# Servers\n\n[web]\n+ server\n+ + host=localhost\n+ + port=80\n");
+    let ours = write_file(&dir, "ours.tref", "# Servers\n\n# Public\n[web]\n+ server\n# Changed\n+ + host=example.com\n+ + port=80\n");
+    let theirs = write_file(&dir, "theirs.tref", "# Servers\n\n[web]\n+ server\n+ + host=localhost\n+ + port=8080\n");
+
+    let output = tref(&["merge", &base, &ours, &theirs], "");
+    let expected = "# Servers\n\n# Public\n[web]\n+ server\n+ + host=example.com\n+ + port=8080\n# Changed\n";
+    if !output.status.success() || stdout(&output) != expected { panic!("Wrong merge:\n{}", stdout(&output)); }
+
+    // Only one version changed
+    let output = tref(&["merge", &base, &base, &ours], "");
+    if !output.status.success() || stdout(&output) != fs::read_to_string(&ours).unwrap() { panic!("Changed their text:\n{}", stdout(&output)); }
+
+    let conflicting = write_file(&dir, "conflicting.tref", "[web]\n+ server\n+ + host=127.0.0.1\n+ + port=80\n");
+    let merged = dir.join("merged.tref");
+    let output = tref(&["merge", "--output", &merged.to_string_lossy(), &base, &ours, &conflicting], "");
+    if output.status.code() != Some(1) || !stderr(&output).contains("CONFLICT web: server > host=localhost") { panic!("Missing conflict:\n{}", stderr(&output)); }
+    let text = fs::read_to_string(&merged).unwrap();
+    if !text.starts_with("# CONFLICT web: server > host=localhost: Renamed to `host=example.com` in ours and `host=127.0.0.1` in theirs\n\n# Servers\n") { panic!("Wrong merged file:\n{}", text); }
+}
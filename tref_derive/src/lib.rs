@@ -0,0 +1,253 @@
+//! # TREF derive
+//!
+//! Derive macro for TREF dialects. Use it through the `derive` feature of the [tref](https://crates.io/crates/tref) crate.
+//!
+//! `#[derive(TrefNode)]` implements `NodeContent` and `TryNodeContent` for a struct, using a format string to both parse and generate the node content:
+//!
+//! ```ignore
+//! use tref::TrefNode;
+//!
+//! #[derive(TrefNode)]
+//! #[tref(format = "{weight}:{name}")]
+//! struct EdgeNode {
+//!     weight: u32,
+//!     name: String,
+//!     content: String
+//! }
+//! ```
+//!
+//! Each `{field}` in the format is parsed with [`FromStr`][`std::str::FromStr`] and generated with [`Display`][`std::fmt::Display`]. Use `{{` and `}}` for literal braces. Two fields must be separated by some literal text. Except in the last field, the literal that follows a field and any backslash are escaped with `\` in the generated text, so every generated content can be parsed back.
+//!
+//! The original content is stored in the field marked with `#[tref(content)]`, or in the field named `content` if none is marked. It must be a `String`. All other fields must appear in the format.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr, Type};
+
+/// Derive `NodeContent` and `TryNodeContent` from a format string.
+#[proc_macro_derive(TrefNode, attributes(tref))]
+pub fn derive_tref_node(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into()
+    }
+}
+
+/// Piece of a format string.
+enum Segment {
+    Literal(String),
+    Field(Ident)
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(input, "TrefNode requires a struct with named fields"))
+        },
+        _ => return Err(Error::new_spanned(input, "TrefNode can only be derived for structs"))
+    };
+
+    // Find format attribute
+    let mut format: Option<LitStr> = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("tref")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("format") {
+                format = Some(meta.value()?.parse()?);
+                Ok(())
+            }
+            else {
+                Err(meta.error("Unknown tref attribute, expected `format`"))
+            }
+        })?;
+    }
+    let format = format.ok_or_else(|| Error::new_spanned(input, "Missing `#[tref(format = \"...\")]` attribute"))?;
+    let segments = parse_format(&format)?;
+
+    // Find content field
+    let mut content_field: Option<&Ident> = None;
+    for field in fields.iter() {
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("tref")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("content") {
+                    content_field = field.ident.as_ref();
+                    Ok(())
+                }
+                else {
+                    Err(meta.error("Unknown tref attribute, expected `content`"))
+                }
+            })?;
+        }
+    }
+    if content_field.is_none() {
+        content_field = fields.iter().filter_map(|f| f.ident.as_ref()).find(|i| *i == "content");
+    }
+    let content_field = content_field.ok_or_else(|| Error::new_spanned(input, "Missing content field, name it `content` or mark it with `#[tref(content)]`"))?;
+
+    // Check that every field is in the format, and every format field exists
+    let mut field_types: Vec<(&Ident, &Type)> = vec!();
+    for field in fields.iter() {
+        if let Some(ident) = &field.ident {
+            if ident == content_field {
+                continue;
+            }
+            let in_format = segments.iter().any(|s| matches!(s, Segment::Field(f) if f == ident));
+            if !in_format {
+                return Err(Error::new_spanned(ident, format!("Field `{}` is not in the format", ident)));
+            }
+            field_types.push((ident, &field.ty));
+        }
+    }
+    for segment in segments.iter() {
+        if let Segment::Field(f) = segment {
+            if f == content_field || !field_types.iter().any(|(i, _)| *i == f) {
+                return Err(Error::new(format.span(), format!("Unknown field `{}` in format", f)));
+            }
+        }
+    }
+
+    // Parsing code, segment by segment
+    let mut parse_steps = vec!();
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Literal(lit) => {
+                let msg = format!("Expected `{}`", lit);
+                parse_steps.push(quote! {
+                    __rest = __rest.strip_prefix(#lit).ok_or_else(|| ::std::string::String::from(#msg))?;
+                });
+            },
+            Segment::Field(ident) => {
+                let ty = field_types.iter().find(|(f, _)| *f == ident).map(|(_, t)| *t).unwrap();
+                let text = Ident::new(&format!("__text_{}", ident), Span::call_site());
+                let split = match segments.get(i + 1) {
+                    Some(Segment::Literal(next)) if i + 2 == segments.len() => {
+                        // Last literal, the field ends where the suffix starts
+                        let msg = format!("Expected `{}` at the end", next);
+                        quote! {
+                            let #text = __rest.strip_suffix(#next).ok_or_else(|| ::std::string::String::from(#msg))?;
+                            __rest = &__rest[#text.len()..];
+                        }
+                    },
+                    Some(Segment::Literal(next)) => {
+                        // The separator can be escaped inside the field
+                        let msg = format!("Expected `{}`", next);
+                        quote! {
+                            let (#text, __next) = ::tref::derive::split_field(__rest, #next).ok_or_else(|| ::std::string::String::from(#msg))?;
+                            __rest = __next;
+                        }
+                    },
+                    _ => quote! {
+                        let #text = __rest;
+                        __rest = "";
+                    }
+                };
+                let msg = format!("Invalid `{}` ({{}})", ident);
+                let var = field_var(ident);
+                parse_steps.push(quote! {
+                    #split
+                    let #var = <#ty as ::std::str::FromStr>::from_str(&#text).map_err(|e| ::std::format!(#msg, e))?;
+                });
+            }
+        }
+    }
+
+    // Generation code
+    let gen_steps = segments.iter().enumerate().map(|(i, segment)| match (segment, segments.get(i + 1)) {
+        (Segment::Literal(lit), _) => quote! { content.push_str(#lit); },
+        (Segment::Field(ident), Some(Segment::Literal(next))) if i + 2 < segments.len() => quote! {
+            content.push_str(&::tref::derive::escape_field(&self.#ident.to_string(), #next));
+        },
+        (Segment::Field(ident), _) => quote! { content.push_str(&self.#ident.to_string()); }
+    });
+
+    let field_names = field_types.iter().map(|(i, _)| *i);
+    let field_vars = field_types.iter().map(|(i, _)| field_var(i));
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::tref::NodeContent for #name #ty_generics #where_clause {
+            fn new(content: &str) -> ::std::option::Option<Self> {
+                <Self as ::tref::TryNodeContent>::try_new(content).ok()
+            }
+
+            fn get_val(&self) -> &str {
+                &self.#content_field
+            }
+
+            fn gen_content(&self) -> ::std::string::String {
+                let mut content = ::std::string::String::new();
+                #(#gen_steps)*
+                content
+            }
+        }
+
+        impl #impl_generics ::tref::TryNodeContent for #name #ty_generics #where_clause {
+            fn try_new(__content: &str) -> ::std::result::Result<Self, ::std::string::String> {
+                let mut __rest = __content;
+                #(#parse_steps)*
+                if !__rest.is_empty() {
+                    return ::std::result::Result::Err(::std::format!("Unexpected `{}` at the end", __rest));
+                }
+                ::std::result::Result::Ok(Self {
+                    #(#field_names: #field_vars,)*
+                    #content_field: ::std::string::String::from(__content)
+                })
+            }
+        }
+    })
+}
+
+/// Name of the local variable that holds a parsed field.
+fn field_var(ident: &Ident) -> Ident {
+    Ident::new(&format!("__field_{}", ident), Span::call_site())
+}
+
+/// Split a format string into literals and fields.
+fn parse_format(format: &LitStr) -> Result<Vec<Segment>, Error> {
+    let value = format.value();
+    let mut segments = vec!();
+    let mut literal = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            },
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => field.push(c),
+                        None => return Err(Error::new(format.span(), "Unclosed `{` in format"))
+                    }
+                }
+                let ident = syn::parse_str::<Ident>(field.trim()).map_err(|_| Error::new(format.span(), format!("Invalid field name `{}` in format", field)))?;
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                else if let Some(Segment::Field(_)) = segments.last() {
+                    return Err(Error::new(format.span(), "Fields in format must be separated by some text"));
+                }
+                segments.push(Segment::Field(ident));
+            },
+            '}' => return Err(Error::new(format.span(), "Unmatched `}` in format, use `}}` for a literal brace")),
+            _ => literal.push(c)
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,41 @@
+use syn::DeriveInput;
+use crate::expand;
+
+fn derive_error(input: &str) -> String {
+    let input: DeriveInput = syn::parse_str(input).unwrap();
+    match expand(&input) {
+        Ok(_) => panic!("Derived invalid dialect `{}`", input.ident),
+        Err(e) => e.to_string()
+    }
+}
+
+#[test]
+fn valid_format() {
+    let input: DeriveInput = syn::parse_str("#[tref(format = \"{name}:{weight}\")] struct Node { name: String, weight: u32, content: String }").unwrap();
+    if let Err(e) = expand(&input) {
+        panic!("Failed deriving dialect: {}", e);
+    }
+}
+
+#[test]
+fn invalid_formats() {
+    let cases = [
+        ("#[tref(format = \"{name}{weight}\")] struct Node { name: String, weight: u32, content: String }", "must be separated"),
+        ("#[tref(format = \"<{name}>{weight}{id}\")] struct Node { name: String, weight: u32, id: u32, content: String }", "must be separated"),
+        ("#[tref(format = \"{name\")] struct Node { name: String, content: String }", "Unclosed"),
+        ("#[tref(format = \"name}\")] struct Node { content: String }", "Unmatched"),
+        ("#[tref(format = \"{1name}\")] struct Node { content: String }", "Invalid field name"),
+        ("#[tref(format = \"{other}\")] struct Node { name: String, content: String }", "not in the format"),
+        ("#[tref(format = \"{name}:{other}\")] struct Node { name: String, content: String }", "Unknown field"),
+        ("#[tref(format = \"{content}\")] struct Node { content: String }", "Unknown field"),
+        ("#[tref(format = \"{name}\")] struct Node { name: String }", "Missing content field"),
+        ("struct Node { name: String, content: String }", "Missing `#[tref(format"),
+        ("#[tref(form = \"{name}\")] struct Node { name: String, content: String }", "Unknown tref attribute"),
+        ("#[tref(format = \"{name}\")] enum Node { Name }", "only be derived for structs"),
+        ("#[tref(format = \"{0}\")] struct Node(String);", "named fields")
+    ];
+    for (input, message) in cases.iter() {
+        let error = derive_error(input);
+        if !error.contains(message) { panic!("Wrong error `{}` for `{}`", error, input); }
+    }
+}